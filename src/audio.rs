@@ -4,72 +4,101 @@ use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{FromSample, SizedSample};
 use fundsp::hacker32::*;
 
-use crossbeam_channel::{bounded, Sender};
+use std::time::{Duration, Instant};
 
-use crate::components::*;
+use crate::{components::*, config::*, jack::*, nodes::*};
 
-pub fn default_out_device(world: &mut World) {
-    let slot = Slot::new(Box::new(dc(0.) | dc(0.)));
-    world.insert_resource(SlotRes(slot.0));
-    let host = cpal::default_host();
-    if let Some(device) = host.default_output_device() {
-        let default_config = device.default_output_config().unwrap();
-        let mut config = default_config.config();
-        config.channels = 2;
-        let stream = match default_config.sample_format() {
-            cpal::SampleFormat::F32 => run::<f32>(&device, &config, slot.1),
-            cpal::SampleFormat::I16 => run::<i16>(&device, &config, slot.1),
-            cpal::SampleFormat::U16 => run::<u16>(&device, &config, slot.1),
-            format => {
-                error!("unsupported sample format: {}", format);
-                None
-            }
-        };
-        if let Some(stream) = stream {
-            world.insert_non_send_resource(OutStream(stream));
-        } else {
-            error!("couldn't build stream");
+// what the output stream shares with the rest of the app
+#[derive(Clone, Default)]
+pub struct OutShared {
+    pub transport: Transport,
+    pub clock: AudioClock,
+    pub load: DspLoad,
+    pub status: AudioStatus,
+    pub input: InputRing,
+}
+
+impl OutShared {
+    pub fn new(world: &World) -> Self {
+        OutShared {
+            transport: world.resource::<TransportRes>().0.clone(),
+            clock: world.resource::<Timing>().clock.clone(),
+            load: world.resource::<Profiler>().dsp.clone(),
+            status: world.resource::<AudioStatusRes>().0.clone(),
+            input: world.resource::<InputRingRes>().0.clone(),
         }
     }
 }
 
+// the configured output device, or the default one if it's not set or not there
+pub fn default_out_device(world: &mut World) {
+    let audio = world.resource::<Config>().audio.clone();
+    let host = config_host(&audio);
+    let device = audio.out_device.and_then(|d| host.output_devices().ok()?.nth(d));
+    let device = device.or_else(|| host.default_output_device());
+    if let Err(err) = open_out_device(world, device, audio.sample_rate, audio.buffer) {
+        failed(world, err, true);
+    }
+}
+
+fn open_out_device(
+    world: &mut World,
+    device: Option<cpal::Device>,
+    sr: Option<u32>,
+    buffer: Option<u32>,
+) -> Result<(), String> {
+    let (slot, backend) = Slot::new(Box::new(dc(0.) | dc(0.)));
+    world.insert_resource(SlotRes(slot));
+    let shared = OutShared::new(world);
+    let device = device.ok_or("no output device")?;
+    let default_config = device.default_output_config().map_err(|e| e.to_string())?;
+    let mut config = default_config.config();
+    config.channels = 2;
+    apply_config(&mut config, sr, buffer);
+    let stream = match default_config.sample_format() {
+        cpal::SampleFormat::F32 => run::<f32>(&device, &config, backend, shared),
+        cpal::SampleFormat::I16 => run::<i16>(&device, &config, backend, shared),
+        cpal::SampleFormat::U16 => run::<u16>(&device, &config, backend, shared),
+        format => return Err(format!("unsupported sample format: {}", format)),
+    };
+    let stream = stream.ok_or("couldn't build stream")?;
+    world.insert_non_send_resource(OutStream(stream));
+    Ok(())
+}
+
+// the host from the config file (the default one if it's not set or doesn't exist)
+fn config_host(audio: &AudioConfig) -> cpal::Host {
+    audio
+        .host
+        .and_then(|h| cpal::platform::ALL_HOSTS.get(h))
+        .and_then(|id| cpal::platform::host_from_id(*id).ok())
+        .unwrap_or_else(cpal::default_host)
+}
+
+fn apply_config(config: &mut cpal::StreamConfig, sr: Option<u32>, buffer: Option<u32>) {
+    if let Some(sr) = sr {
+        config.sample_rate = cpal::SampleRate(sr);
+    }
+    if let Some(b) = buffer {
+        config.buffer_size = cpal::BufferSize::Fixed(b);
+    }
+}
+
+// a device that couldn't be opened counts as lost, reconnect tries the default one
+fn failed(world: &World, err: String, output: bool) {
+    world.resource::<AudioStatusRes>().0.error(err, output, true);
+}
+
 pub fn set_out_device(world: &mut World) {
     let mut out_events = world.resource_mut::<Events<OutDeviceCommand>>();
     let events: Vec<OutDeviceCommand> = out_events.drain().collect();
-    for e in events {
-        let slot = Slot::new(Box::new(dc(0.) | dc(0.)));
-        world.insert_resource(SlotRes(slot.0));
-        let OutDeviceCommand(h, d, sr, b) = e;
-        if let Some(host_id) = cpal::platform::ALL_HOSTS.get(h) {
-            if let Ok(host) = cpal::platform::host_from_id(*host_id) {
-                if let Ok(mut devices) = host.output_devices() {
-                    if let Some(device) = devices.nth(d) {
-                        let default_config = device.default_output_config().unwrap();
-                        let mut config = default_config.config();
-                        config.channels = 2;
-                        if let Some(sr) = sr {
-                            config.sample_rate = cpal::SampleRate(sr);
-                        }
-                        if let Some(b) = b {
-                            config.buffer_size = cpal::BufferSize::Fixed(b);
-                        }
-                        let stream = match default_config.sample_format() {
-                            cpal::SampleFormat::F32 => run::<f32>(&device, &config, slot.1),
-                            cpal::SampleFormat::I16 => run::<i16>(&device, &config, slot.1),
-                            cpal::SampleFormat::U16 => run::<u16>(&device, &config, slot.1),
-                            format => {
-                                error!("unsupported sample format: {}", format);
-                                None
-                            }
-                        };
-                        if let Some(stream) = stream {
-                            world.insert_non_send_resource(OutStream(stream));
-                        } else {
-                            error!("couldn't build stream");
-                        }
-                    }
-                }
-            }
+    for OutDeviceCommand(h, d, sr, b) in events {
+        let device = cpal::platform::ALL_HOSTS
+            .get(h)
+            .and_then(|id| cpal::platform::host_from_id(*id).ok())
+            .and_then(|host| host.output_devices().ok()?.nth(d));
+        if let Err(err) = open_out_device(world, device, sr, b) {
+            failed(world, err, true);
         }
     }
 }
@@ -78,23 +107,38 @@ fn run<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
     slot: SlotBackend,
+    shared: OutShared,
 ) -> Option<cpal::Stream>
 where
     T: SizedSample + FromSample<f32>,
 {
-    let mut slot = BlockRateAdapter::new(Box::new(slot));
+    let mut out = out_unit(slot, &shared, config.sample_rate.0 as f64);
+    let OutShared { clock, load, status, .. } = shared;
 
+    let callback_clock = clock.clone();
     let mut next_value = move || {
-        let (l, r) = slot.get_stereo();
-        (
-            if l.is_normal() { l.clamp(-1., 1.) } else { 0. },
-            if r.is_normal() { r.clamp(-1., 1.) } else { 0. },
-        )
+        let (l, r) = out.get_stereo();
+        clock.take_sample();
+        (clean(l), clean(r))
     };
-    let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
+    let sr = config.sample_rate.0 as f64;
+    let mut prev_callback = None;
+    let err_status = status.clone();
+    let err_fn = move |err| on_error(&err_status, err, true);
     let stream = device.build_output_stream(
         config,
-        move |data: &mut [T], _: &cpal::OutputCallbackInfo| write_data(data, &mut next_value),
+        move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+            callback_clock.start_callback();
+            let start = Instant::now();
+            write_data(data, &mut next_value);
+            let busy = start.elapsed();
+            let frames = data.len() / 2;
+            load.record(busy, frames, sr);
+            let now = info.timestamp().callback;
+            let gap = prev_callback.and_then(|prev| now.duration_since(&prev));
+            status.callback(gap, Duration::from_secs_f64(frames as f64 / sr), busy);
+            prev_callback = Some(now);
+        },
         err_fn,
         None,
     );
@@ -106,6 +150,27 @@ where
     None
 }
 
+/// the unit an output plays, fading to the nets set in the slot
+pub fn out_unit(slot: SlotBackend, shared: &OutShared, sr: f64) -> BlockRateAdapter {
+    let out = Clocked::new(
+        slot,
+        shared.transport.clone(),
+        shared.clock.clone(),
+        shared.input.clone(),
+        sr,
+    );
+    BlockRateAdapter::new(Box::new(out))
+}
+
+/// keep a sample within what the device takes (no nan or inf)
+pub fn clean(x: f32) -> f32 {
+    if x.is_normal() {
+        x.clamp(-1., 1.)
+    } else {
+        0.
+    }
+}
+
 fn write_data<T>(output: &mut [T], next_sample: &mut dyn FnMut() -> (f32, f32))
 where
     T: SizedSample + FromSample<f32>,
@@ -117,67 +182,52 @@ where
     }
 }
 
+// the configured input device, or the default one if it's not set or not there
 pub fn default_in_device(world: &mut World) {
-    let (ls, lr) = bounded(4096);
-    let (rs, rr) = bounded(4096);
-    world.insert_resource(InputReceivers(lr, rr));
-    let host = cpal::default_host();
-    if let Some(device) = host.default_input_device() {
-        let config = device.default_input_config().unwrap();
-        let stream = match config.sample_format() {
-            cpal::SampleFormat::F32 => run_in::<f32>(&device, &config.into(), ls, rs),
-            cpal::SampleFormat::I16 => run_in::<i16>(&device, &config.into(), ls, rs),
-            cpal::SampleFormat::U16 => run_in::<u16>(&device, &config.into(), ls, rs),
-            format => {
-                error!("unsupported sample format: {}", format);
-                None
-            }
-        };
-        if let Some(stream) = stream {
-            world.insert_non_send_resource(InStream(stream));
-        } else {
-            error!("couldn't build stream");
-        }
+    let audio = world.resource::<Config>().audio.clone();
+    let host = config_host(&audio);
+    let device = audio.in_device.and_then(|d| host.input_devices().ok()?.nth(d));
+    let device = device.or_else(|| host.default_input_device());
+    if let Err(err) = open_in_device(world, device, audio.sample_rate, audio.buffer) {
+        failed(world, err, false);
     }
 }
 
+fn open_in_device(
+    world: &mut World,
+    device: Option<cpal::Device>,
+    sr: Option<u32>,
+    buffer: Option<u32>,
+) -> Result<(), String> {
+    let ring = world.resource::<InputRingRes>().0.clone();
+    let status = world.resource::<AudioStatusRes>().0.clone();
+    let device = device.ok_or("no input device")?;
+    let default_config = device.default_input_config().map_err(|e| e.to_string())?;
+    let mut config = default_config.config();
+    apply_config(&mut config, sr, buffer);
+    let stream = match default_config.sample_format() {
+        cpal::SampleFormat::F32 => run_in::<f32>(&device, &config, ring, status),
+        cpal::SampleFormat::I16 => run_in::<i16>(&device, &config, ring, status),
+        cpal::SampleFormat::U16 => run_in::<u16>(&device, &config, ring, status),
+        format => return Err(format!("unsupported sample format: {}", format)),
+    };
+    let stream = stream.ok_or("couldn't build stream")?;
+    world.insert_non_send_resource(InStream(stream));
+    Ok(())
+}
+
 pub fn set_in_device(world: &mut World) {
     let mut out_events = world.resource_mut::<Events<InDeviceCommand>>();
     let events: Vec<InDeviceCommand> = out_events.drain().collect();
-    for e in events {
-        let (ls, lr) = bounded(4096);
-        let (rs, rr) = bounded(4096);
-        world.insert_resource(InputReceivers(lr, rr));
-        let InDeviceCommand(h, d, sr, b) = e;
-        if let Some(host_id) = cpal::platform::ALL_HOSTS.get(h) {
-            if let Ok(host) = cpal::platform::host_from_id(*host_id) {
-                if let Ok(mut devices) = host.input_devices() {
-                    if let Some(device) = devices.nth(d) {
-                        let default_config = device.default_input_config().unwrap();
-                        let mut config = default_config.config();
-                        if let Some(sr) = sr {
-                            config.sample_rate = cpal::SampleRate(sr);
-                        }
-                        if let Some(b) = b {
-                            config.buffer_size = cpal::BufferSize::Fixed(b);
-                        }
-                        let stream = match default_config.sample_format() {
-                            cpal::SampleFormat::F32 => run_in::<f32>(&device, &config, ls, rs),
-                            cpal::SampleFormat::I16 => run_in::<i16>(&device, &config, ls, rs),
-                            cpal::SampleFormat::U16 => run_in::<u16>(&device, &config, ls, rs),
-                            format => {
-                                error!("unsupported sample format: {}", format);
-                                None
-                            }
-                        };
-                        if let Some(stream) = stream {
-                            world.insert_non_send_resource(InStream(stream));
-                        } else {
-                            error!("couldn't build stream");
-                        }
-                    }
-                }
-            }
+    for InDeviceCommand(h, d, sr, b) in events {
+        // only one stream should be writing to the ring
+        world.remove_non_send_resource::<InStream>();
+        let device = cpal::platform::ALL_HOSTS
+            .get(h)
+            .and_then(|id| cpal::platform::host_from_id(*id).ok())
+            .and_then(|host| host.input_devices().ok()?.nth(d));
+        if let Err(err) = open_in_device(world, device, sr, b) {
+            failed(world, err, false);
         }
     }
 }
@@ -185,19 +235,24 @@ pub fn set_in_device(world: &mut World) {
 fn run_in<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
-    ls: Sender<f32>,
-    rs: Sender<f32>,
+    ring: InputRing,
+    status: AudioStatus,
 ) -> Option<cpal::Stream>
 where
     T: SizedSample,
     f32: FromSample<T>,
 {
     let channels = config.channels as usize;
-    let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
+    let err_status = status.clone();
+    let err_fn = move |err| on_error(&err_status, err, false);
     let stream = device.build_input_stream(
         config,
         move |data: &[T], _: &cpal::InputCallbackInfo| {
-            read_data(data, channels, ls.clone(), rs.clone())
+            let skipped = ring.take_skipped() * channels as u64;
+            let dropped = read_data(data, channels, &ring) + skipped;
+            if dropped > 0 {
+                status.overflow(dropped);
+            }
         },
         err_fn,
         None,
@@ -210,18 +265,166 @@ where
     None
 }
 
-fn read_data<T>(input: &[T], channels: usize, ls: Sender<f32>, rs: Sender<f32>)
+// returns the number of samples that didn't fit
+fn read_data<T>(input: &[T], channels: usize, ring: &InputRing) -> u64
 where
     T: SizedSample,
     f32: FromSample<T>,
 {
+    let mut dropped = 0;
     for frame in input.chunks(channels) {
-        for (channel, sample) in frame.iter().enumerate() {
-            if channel & 1 == 0 {
-                let _ = ls.try_send(sample.to_sample::<f32>());
-            } else {
-                let _ = rs.try_send(sample.to_sample::<f32>());
+        // mono devices go to both sides
+        let l = frame[0].to_sample::<f32>();
+        let r = frame.get(1).map_or(l, |r| r.to_sample::<f32>());
+        if !ring.push(l, r) {
+            dropped += channels as u64;
+        }
+    }
+    dropped
+}
+
+fn on_error(status: &AudioStatus, err: cpal::StreamError, output: bool) {
+    let lost = matches!(err, cpal::StreamError::DeviceNotAvailable);
+    status.error(err.to_string(), output, lost);
+}
+
+// when a device disappears, try the default one every second until it works
+pub fn reconnect(world: &mut World, mut wait: Local<f32>) {
+    if world.contains_non_send::<JackClient>() {
+        return;
+    }
+    let status = world.resource::<AudioStatusRes>().0.clone();
+    if !status.lost(true) && !status.lost(false) {
+        *wait = 0.;
+        return;
+    }
+    *wait -= world.resource::<Time>().delta_seconds();
+    if *wait > 0. {
+        return;
+    }
+    *wait = 1.;
+    // not the configured ones, their index may point at another device now
+    let audio = world.resource::<Config>().audio.clone();
+    let host = config_host(&audio);
+    let (sr, buffer) = (audio.sample_rate, audio.buffer);
+    if status.lost(true) {
+        world.remove_non_send_resource::<OutStream>();
+        // still lost if it fails, tried again next time
+        let opened = open_out_device(world, host.default_output_device(), sr, buffer);
+        if opened.is_ok() {
+            status.found(true);
+            show_status(world, "output reconnected to the default device");
+            resend_out(world);
+        }
+    }
+    if status.lost(false) {
+        world.remove_non_send_resource::<InStream>();
+        if open_in_device(world, host.default_input_device(), sr, buffer).is_ok() {
+            status.found(false);
+            show_status(world, "input reconnected to the default device");
+        }
+    }
+}
+
+// the new output starts silent, send it the graph again
+fn resend_out(world: &mut World) {
+    let mut outs = world.query::<(&OpNum, &mut OpChanged)>();
+    for (op_num, mut op_changed) in outs.iter_mut(world) {
+        if op_num.0 == 90 {
+            op_changed.0 = true;
+        }
+    }
+}
+
+// start and stop jack mode (`:jack`), and keep its connections
+pub fn jack_mode(world: &mut World) {
+    let enabled = world.resource::<Jack>().enabled;
+    let running = world.get_non_send_resource::<JackClient>().map(|client| client.shut_down());
+    match running {
+        None if enabled => {
+            // the cpal streams make way for the client
+            world.remove_non_send_resource::<OutStream>();
+            world.remove_non_send_resource::<InStream>();
+            let (slot, backend) = Slot::new(Box::new(dc(0.) | dc(0.)));
+            world.insert_resource(SlotRes(slot));
+            let shared = OutShared::new(world);
+            let follow = world.resource::<Jack>().follow.clone();
+            match JackClient::new(CLIENT_NAME, backend, shared, follow) {
+                Ok(client) => {
+                    let msg = format!("jack: on as {} ({}Hz)", client.name(), client.sample_rate());
+                    show_status(world, &msg);
+                    world.insert_non_send_resource(client);
+                }
+                Err(err) => {
+                    show_status(world, &format!("jack: {}", err));
+                    world.resource_mut::<Jack>().enabled = false;
+                    default_out_device(world);
+                    default_in_device(world);
+                }
             }
+            resend_out(world);
+        }
+        Some(shut_down) if !enabled || shut_down => {
+            // dropping it closes the client
+            world.remove_non_send_resource::<JackClient>();
+            let mut jack = world.resource_mut::<Jack>();
+            jack.enabled = false;
+            jack.ports.clear();
+            default_out_device(world);
+            default_in_device(world);
+            resend_out(world);
+            show_status(world, if shut_down { "jack: the server shut down" } else { "jack: off" });
+        }
+        _ => {}
+    }
+    let Some(client) = world.get_non_send_resource::<JackClient>() else { return };
+    let ports_changed = client.take_ports_changed();
+    let jack = world.resource::<Jack>();
+    if ports_changed || jack.rules_changed {
+        let failed = client.connect(&jack.rules);
+        let ports = client.ports();
+        let mut jack = world.resource_mut::<Jack>();
+        jack.rules_changed = false;
+        jack.ports = ports;
+        if !failed.is_empty() {
+            let msg = format!("jack: couldn't connect {}", failed.join(", "));
+            show_status(world, &msg);
+        }
+    }
+}
+
+// show stream errors, and the xrun counts when they go up
+pub fn report_audio_status(
+    status: Res<AudioStatusRes>,
+    mut last: Local<[u64; 4]>,
+    mut command_line_text: Query<&mut Text, With<CommandText>>,
+) {
+    let counts = status.0.counts();
+    let error = status.0.take_error();
+    if counts == *last && error.is_none() {
+        return;
+    }
+    let msg = if let Some(error) = error {
+        format!("audio error: {}", error)
+    } else {
+        let [underruns, overflows, ..] = counts;
+        format!("audio: {} underruns, {} input samples dropped", underruns, overflows)
+    };
+    *last = counts;
+    if let Ok(mut text) = command_line_text.get_single_mut() {
+        // don't clobber a command being typed
+        if !text.sections[0].value.starts_with([':', '/']) {
+            text.sections[0].value = format!(">{}", msg);
+        }
+    }
+}
+
+fn show_status(world: &mut World, msg: &str) {
+    info!("{}", msg);
+    let mut query = world.query_filtered::<&mut Text, With<CommandText>>();
+    if let Ok(mut text) = query.get_single_mut(world) {
+        if !text.sections[0].value.starts_with([':', '/']) {
+            text.sections[0].value = format!(">{}", msg);
         }
     }
 }
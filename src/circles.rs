@@ -1,13 +1,22 @@
 use bevy::{
     prelude::*,
-    render::view::{RenderLayers, VisibleEntities},
-    sprite::{Mesh2dHandle, WithMesh2d},
-    text::Text2dBounds,
+    render::{
+        mesh::PrimitiveTopology,
+        render_asset::RenderAssetUsages,
+        view::{RenderLayers, VisibleEntities},
+    },
+    sprite::{Anchor, Mesh2dHandle, WithMesh2d},
+    text::{Text2dBounds, TextLayoutInfo},
+    utils::{HashMap, HashSet},
 };
 
 use fundsp::net::Net;
 
-use crate::{components::*, functions::*};
+use crate::{
+    components::*,
+    functions::*,
+    layout::{grid_lines, snap},
+};
 
 pub fn spawn_circles(
     mut commands: Commands,
@@ -100,6 +109,49 @@ pub fn highlight_selected(
     }
 }
 
+pub fn mark_bad_ops(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    bad: Query<(Entity, &Transform), (With<BadOp>, Without<BadOpMarker>)>,
+    fixed: Query<(Entity, &BadOpMarker), Without<BadOp>>,
+    moved: Query<(&Transform, &BadOpMarker), Changed<Transform>>,
+    mut trans_query: Query<&mut Transform, Without<BadOpMarker>>,
+    mut mesh: Local<Option<Mesh2dHandle>>,
+) {
+    let mesh = mesh.get_or_insert_with(|| meshes.add(Triangle2d::default()).into());
+    for (e, t) in bad.iter() {
+        let marker = commands
+            .spawn(ColorMesh2dBundle {
+                mesh: mesh.clone(),
+                material: materials.add(ColorMaterial::from_color(Hsla::new(0., 1., 0.5, 1.))),
+                transform: bad_op_marker_transform(t),
+                ..default()
+            })
+            .id();
+        commands.entity(e).insert(BadOpMarker(marker));
+    }
+    for (e, marker) in fixed.iter() {
+        commands.entity(marker.0).despawn();
+        commands.entity(e).remove::<BadOpMarker>();
+    }
+    for (t, marker) in moved.iter() {
+        if let Ok(mut trans) = trans_query.get_mut(marker.0) {
+            *trans = bad_op_marker_transform(t);
+        }
+    }
+}
+
+// a little red triangle on the top right of the circle
+fn bad_op_marker_transform(t: &Transform) -> Transform {
+    let r = t.scale.x;
+    Transform {
+        translation: (t.translation.xy() + Vec2::splat(r * 0.7)).extend(t.translation.z + 0.0001),
+        scale: Vec3::new(r * 0.3 + 5., r * 0.3 + 5., 1.),
+        ..default()
+    }
+}
+
 pub fn transform_highlights(
     moved: Query<(&Transform, &Highlight), Changed<Transform>>,
     changed_verts: Query<(&Vertices, &Highlight), Changed<Vertices>>,
@@ -127,13 +179,15 @@ pub fn update_selection(
     mut commands: Commands,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     circle_trans_query: Query<&Transform, With<Vertices>>,
-    visible: Query<&VisibleEntities>,
+    visible: Query<&VisibleEntities, With<MainCamera>>,
     selected: Query<Entity, With<Selected>>,
     cursor: Res<CursorInfo>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut top_clicked_circle: Local<Option<(Entity, f32)>>,
     order_query: Query<(), With<Order>>, // non-hole circle
     mut clicked_on_space: ResMut<ClickedOnSpace>,
+    comment_query: Query<(Entity, &Transform, &CommentText)>,
+    layout_query: Query<&TextLayoutInfo>,
 ) {
     if keyboard_input.pressed(KeyCode::Space) {
         return;
@@ -159,6 +213,14 @@ pub fn update_selection(
                 }
             }
         }
+        for (e, t, text) in comment_query.iter() {
+            if top_clicked_circle.is_some_and(|top| top.1 >= t.translation.z) {
+                continue;
+            }
+            if comment_rect(t, layout_query.get(text.0).ok()).contains(cursor.i) {
+                *top_clicked_circle = Some((e, t.translation.z));
+            }
+        }
         if let Some(top) = *top_clicked_circle {
             clicked_on_space.0 = false;
             if !selected.contains(top.0) {
@@ -209,55 +271,146 @@ pub fn update_selection(
                 }
             }
         }
+        // comments aren't holes
+        if !ctrl {
+            for (e, t, _) in comment_query.iter() {
+                if (min_x < t.translation.x && t.translation.x < max_x)
+                    && (min_y < t.translation.y && t.translation.y < max_y)
+                {
+                    commands.entity(e).insert(Selected);
+                }
+            }
+        }
     }
 }
 
 pub fn move_selected(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     cursor: Res<CursorInfo>,
-    mut circle_query: Query<&mut Transform, With<Selected>>,
+    mut circle_query: Query<(Entity, &mut Transform), (With<Selected>, Without<Locked>)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     drag_modes: Res<DragModes>,
+    grid: Res<Grid>,
+    // where the dragged circles would be if there was no grid
+    mut free: Local<HashMap<Entity, Vec2>>,
 ) {
     if keyboard_input.pressed(KeyCode::Space) {
         return;
     }
+    if mouse_button_input.just_released(MouseButton::Left) {
+        free.clear();
+    }
     if drag_modes.t {
         if mouse_button_input.pressed(MouseButton::Left)
             && !mouse_button_input.just_pressed(MouseButton::Left)
         {
-            for mut t in circle_query.iter_mut() {
-                t.translation.x += cursor.d.x;
-                t.translation.y += cursor.d.y;
+            for (e, mut t) in circle_query.iter_mut() {
+                if grid.0 > 0. {
+                    let p = free.entry(e).or_insert(t.translation.xy());
+                    *p += cursor.d;
+                    t.translation.x = snap(p.x, grid.0);
+                    t.translation.y = snap(p.y, grid.0);
+                } else {
+                    t.translation.x += cursor.d.x;
+                    t.translation.y += cursor.d.y;
+                }
             }
         }
+        // a cell per press
+        if grid.0 > 0. {
+            let step = arrow_step(&keyboard_input) * grid.0;
+            if step != Vec2::ZERO {
+                for (_, mut t) in circle_query.iter_mut() {
+                    t.translation.x = snap(t.translation.x + step.x, grid.0);
+                    t.translation.y = snap(t.translation.y + step.y, grid.0);
+                }
+            }
+            return;
+        }
         if keyboard_input.pressed(KeyCode::ArrowUp) {
-            for mut t in circle_query.iter_mut() {
+            for (_, mut t) in circle_query.iter_mut() {
                 t.translation.y += 1.;
             }
         }
         if keyboard_input.pressed(KeyCode::ArrowDown) {
-            for mut t in circle_query.iter_mut() {
+            for (_, mut t) in circle_query.iter_mut() {
                 t.translation.y -= 1.;
             }
         }
         if keyboard_input.pressed(KeyCode::ArrowRight) {
-            for mut t in circle_query.iter_mut() {
+            for (_, mut t) in circle_query.iter_mut() {
                 t.translation.x += 1.;
             }
         }
         if keyboard_input.pressed(KeyCode::ArrowLeft) {
-            for mut t in circle_query.iter_mut() {
+            for (_, mut t) in circle_query.iter_mut() {
                 t.translation.x -= 1.;
             }
         }
     }
 }
 
+// lines every grid size across the view, behind everything
+pub fn draw_grid(
+    mut commands: Commands,
+    grid: Res<Grid>,
+    camera: Query<(Ref<Transform>, Ref<OrthographicProjection>), With<MainCamera>>,
+    mut lines: Query<(&Mesh2dHandle, &mut Visibility), With<GridLines>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    command_color: Res<CommandColor>,
+) {
+    let (cam, ortho) = camera.single();
+    if !grid.is_changed() && !cam.is_changed() && !ortho.is_changed() {
+        return;
+    }
+    let view = Rect::from_center_size(cam.translation.xy(), ortho.area.size());
+    let points = grid_lines(view, grid.0);
+    if let Ok((mesh, mut visibility)) = lines.get_single_mut() {
+        if points.is_empty() {
+            visibility.set_if_neq(Visibility::Hidden);
+        } else {
+            visibility.set_if_neq(Visibility::Inherited);
+            if let Some(mesh) = meshes.get_mut(&mesh.0) {
+                mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, points);
+            }
+        }
+    } else if !points.is_empty() {
+        let mesh = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, points);
+        commands.spawn((
+            ColorMesh2dBundle {
+                mesh: meshes.add(mesh).into(),
+                material: materials
+                    .add(ColorMaterial::from_color(command_color.0.with_alpha(0.15))),
+                transform: Transform::from_translation(Vec3::Z * -100.),
+                ..default()
+            },
+            GridLines,
+        ));
+    }
+}
+
+// arrows just pressed, as a direction
+fn arrow_step(keyboard_input: &ButtonInput<KeyCode>) -> Vec2 {
+    let mut step = Vec2::ZERO;
+    for (key, dir) in [
+        (KeyCode::ArrowUp, Vec2::Y),
+        (KeyCode::ArrowDown, Vec2::NEG_Y),
+        (KeyCode::ArrowRight, Vec2::X),
+        (KeyCode::ArrowLeft, Vec2::NEG_X),
+    ] {
+        if keyboard_input.just_pressed(key) {
+            step += dir;
+        }
+    }
+    step
+}
+
 pub fn rotate_selected(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     cursor: Res<CursorInfo>,
-    mut query: Query<&mut Transform, With<Selected>>,
+    mut query: Query<&mut Transform, (With<Selected>, Without<Locked>)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     drag_modes: Res<DragModes>,
 ) {
@@ -288,7 +441,7 @@ pub fn rotate_selected(
 pub fn update_color(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     cursor: Res<CursorInfo>,
-    mut query: Query<&mut Col, With<Selected>>,
+    mut query: Query<&mut Col, (With<Selected>, Without<Locked>)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     drag_modes: Res<DragModes>,
 ) {
@@ -379,32 +532,58 @@ pub fn update_mat(
 }
 
 pub fn update_radius(
-    mut query: Query<&mut Transform, With<Selected>>,
+    mut query: Query<(Entity, &mut Transform), (With<Selected>, Without<Locked>)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     cursor: Res<CursorInfo>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     drag_modes: Res<DragModes>,
+    grid: Res<Grid>,
+    mut free: Local<HashMap<Entity, Vec2>>,
 ) {
     if keyboard_input.pressed(KeyCode::Space) {
         return;
     }
+    if mouse_button_input.just_released(MouseButton::Left) {
+        free.clear();
+    }
     if drag_modes.r {
+        // radii snap to half the grid, so diameters are whole cells
+        let half = grid.0 / 2.;
         if mouse_button_input.pressed(MouseButton::Left)
             && !mouse_button_input.just_pressed(MouseButton::Left)
         {
-            for mut t in query.iter_mut() {
-                t.scale.x = (t.scale.x + cursor.d.y).max(0.);
-                t.scale.y = (t.scale.y + cursor.d.y).max(0.);
+            for (e, mut t) in query.iter_mut() {
+                if grid.0 > 0. {
+                    let r = free.entry(e).or_insert(t.scale.xy());
+                    *r += cursor.d.y;
+                    t.scale.x = snap(r.x, half).max(0.);
+                    t.scale.y = snap(r.y, half).max(0.);
+                } else {
+                    t.scale.x = (t.scale.x + cursor.d.y).max(0.);
+                    t.scale.y = (t.scale.y + cursor.d.y).max(0.);
+                }
+            }
+        }
+        if grid.0 > 0. {
+            let step = arrow_step(&keyboard_input);
+            let step = step.x + step.y;
+            if step != 0. {
+                let step = step.signum() * half;
+                for (_, mut t) in query.iter_mut() {
+                    t.scale.x = snap(t.scale.x + step, half).max(0.);
+                    t.scale.y = snap(t.scale.y + step, half).max(0.);
+                }
             }
+            return;
         }
         if keyboard_input.any_pressed([KeyCode::ArrowUp, KeyCode::ArrowRight]) {
-            for mut t in query.iter_mut() {
+            for (_, mut t) in query.iter_mut() {
                 t.scale.x = (t.scale.x + 1.).max(0.);
                 t.scale.y = (t.scale.y + 1.).max(0.);
             }
         }
         if keyboard_input.any_pressed([KeyCode::ArrowDown, KeyCode::ArrowLeft]) {
-            for mut t in query.iter_mut() {
+            for (_, mut t) in query.iter_mut() {
                 t.scale.x = (t.scale.x - 1.).max(0.);
                 t.scale.y = (t.scale.y - 1.).max(0.);
             }
@@ -413,7 +592,7 @@ pub fn update_radius(
 }
 
 pub fn update_vertices(
-    mut query: Query<&mut Vertices, With<Selected>>,
+    mut query: Query<&mut Vertices, (With<Selected>, Without<Locked>)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     drag_modes: Res<DragModes>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
@@ -475,7 +654,7 @@ pub fn update_mesh(
 }
 
 pub fn update_num(
-    mut query: Query<&mut Number, With<Selected>>,
+    mut query: Query<&mut Number, (With<Selected>, Without<Locked>)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     cursor: Res<CursorInfo>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
@@ -557,7 +736,11 @@ pub fn update_info_text(
     black_hole_query: Query<&BlackHole>,
     mut color_query: Query<&mut Col>,
     text_size: Res<TextSize>,
+    bad_op_query: Query<&BadOp>,
+    locked_query: Query<Ref<Locked>>,
+    mut unlocked: RemovedComponents<Locked>,
 ) {
+    let unlocked: HashSet<Entity> = unlocked.read().collect();
     for (id, info) in query.iter_mut() {
         let t = trans_query.get_mut(id).unwrap();
         if t.is_changed() || info.is_added() || text_size.is_changed() {
@@ -566,8 +749,15 @@ pub fn update_info_text(
             trans_query.get_mut(info.0).unwrap().translation = t.xy().extend(t.z + 0.00001);
         }
         if let Ok(ord) = order_query.get_mut(id) {
-            if ord.is_changed() || info.is_added() {
-                text_query.get_mut(info.0).unwrap().sections[1].value = format!("{}\n", ord.0);
+            let locked = locked_query.get(id);
+            if ord.is_changed()
+                || info.is_added()
+                || locked.as_ref().is_ok_and(|l| l.is_added())
+                || unlocked.contains(&id)
+            {
+                let lock = if locked.is_ok() { " locked" } else { "" };
+                text_query.get_mut(info.0).unwrap().sections[1].value =
+                    format!("{}{}\n", ord.0, lock);
             }
         }
         if let Ok(n) = num_query.get_mut(id) {
@@ -577,7 +767,12 @@ pub fn update_info_text(
         }
         if let Ok(op) = op_query.get_mut(id) {
             if op.is_changed() || info.is_added() {
-                text_query.get_mut(info.0).unwrap().sections[2].value = format!("{}\n", op.0);
+                let t = &mut text_query.get_mut(info.0).unwrap();
+                if let Ok(bad) = bad_op_query.get(id) {
+                    t.sections[2].value = format!("{}\n! {}\n", op.0, bad.0);
+                } else {
+                    t.sections[2].value = format!("{}\n", op.0);
+                }
             }
         }
         if let Ok(wh) = white_hole_query.get_mut(id) {
@@ -606,9 +801,71 @@ pub fn update_info_text(
     }
 }
 
+// comment text is laid out at this size then scaled down to the comment's
+const COMMENT_FONT_SIZE: f32 = 120.;
+
+pub fn update_comments(
+    mut commands: Commands,
+    comments: Query<(
+        Entity,
+        Ref<Comment>,
+        Ref<Transform>,
+        &Col,
+        Has<Selected>,
+        Option<&CommentText>,
+    )>,
+    mut texts: Query<(&mut Text, &mut Transform), Without<Comment>>,
+    highlight_color: Res<HighlightColor>,
+) {
+    for (e, comment, t, col, selected, text) in comments.iter() {
+        let color: Color = if selected { highlight_color.0 } else { col.0 }.into();
+        let Some(text) = text else {
+            let text = commands
+                .spawn(Text2dBundle {
+                    text: Text::from_section(
+                        comment.0.clone(),
+                        TextStyle { color, font_size: COMMENT_FONT_SIZE, ..default() },
+                    )
+                    .with_justify(JustifyText::Left),
+                    text_anchor: Anchor::TopLeft,
+                    transform: comment_text_transform(&t),
+                    ..default()
+                })
+                .id();
+            commands.entity(e).insert(CommentText(text));
+            continue;
+        };
+        let Ok((mut text, mut trans)) = texts.get_mut(text.0) else { continue };
+        if comment.is_changed() {
+            text.sections[0].value.clone_from(&comment.0);
+        }
+        // only touch the text when needed, changing it means laying it out again
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+        if t.is_changed() {
+            *trans = comment_text_transform(&t);
+        }
+    }
+}
+
+fn comment_text_transform(t: &Transform) -> Transform {
+    let s = t.scale.x / COMMENT_FONT_SIZE;
+    Transform { translation: t.translation, rotation: t.rotation, scale: Vec3::new(s, s, 1.) }
+}
+
+// the area a comment covers (ignoring rotation). at least a square of its size
+// so empty comments can still be clicked
+pub fn comment_rect(t: &Transform, layout: Option<&TextLayoutInfo>) -> Rect {
+    let size = layout.map_or(Vec2::ZERO, |l| l.logical_size * t.scale.x / COMMENT_FONT_SIZE);
+    let size = size.max(Vec2::splat(t.scale.x));
+    let top_left = t.translation.xy();
+    Rect::from_corners(top_left, top_left + Vec2::new(size.x, -size.y))
+}
+
 pub fn delete_selected(
     mut commands: Commands,
-    selected_query: Query<Entity, With<Selected>>,
+    selected_query: Query<Entity, (With<Selected>, Without<Locked>)>,
     mut holes_query: Query<&mut Holes>,
     bh_query: Query<&BlackHole>,
     wh_query: Query<&WhiteHole>,
@@ -617,10 +874,17 @@ pub fn delete_selected(
     highlight_query: Query<&Highlight>,
     mut order_change: EventWriter<OrderChange>,
     mut lost_wh_query: Query<&mut LostWH>,
+    marker_query: Query<&BadOpMarker>,
+    comment_query: Query<Option<&CommentText>, With<Comment>>,
 ) {
     let mut order = false;
     for e in selected_query.iter() {
-        if let Ok(holes) = holes_query.get(e) {
+        if let Ok(text) = comment_query.get(e) {
+            if let Some(text) = text {
+                commands.entity(text.0).despawn();
+            }
+            commands.entity(e).despawn();
+        } else if let Ok(holes) = holes_query.get(e) {
             // it's a circle
             for hole in &holes.0.clone() {
                 if let Ok(bh) = bh_query.get(*hole) {
@@ -673,6 +937,9 @@ pub fn delete_selected(
             if let Ok(highlight) = highlight_query.get(e) {
                 commands.entity(highlight.0).despawn();
             }
+            if let Ok(marker) = marker_query.get(e) {
+                commands.entity(marker.0).despawn();
+            }
             commands.entity(e).despawn();
         } else {
             // it's a hole
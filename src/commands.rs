@@ -3,18 +3,27 @@ use bevy::{
     core_pipeline::bloom::{BloomCompositeMode, BloomSettings},
     input::keyboard::{Key, KeyboardInput},
     prelude::*,
-    render::view::{RenderLayers, VisibleEntities},
+    render::{
+        camera::RenderTarget,
+        view::{RenderLayers, VisibleEntities},
+    },
     sprite::WithMesh2d,
+    window::WindowRef,
 };
 
-use crate::{components::*, functions::*};
+use crate::{
+    completion::*, components::*, config::Config, functions::*, jack::Jack, layout::*, link::Link,
+    minimap::bounds, views::open_view,
+};
 
-use fundsp::audiounit::AudioUnit;
+use fundsp::{audiounit::AudioUnit, net::Net};
 
 use copypasta::ClipboardProvider;
 
 use cpal::traits::{DeviceTrait, HostTrait};
 
+use std::{collections::HashMap, io::Write, time::Duration};
+
 pub fn command_parser(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut key_event: EventReader<KeyboardInput>,
@@ -60,7 +69,7 @@ pub fn command_parser(
         EventWriter<CopyCommand>,
         EventWriter<DeleteCommand>,
         Query<&mut Targets>,
-        Query<&mut RenderLayers, With<Camera>>,
+        Query<&mut RenderLayers, With<MainCamera>>,
     ),
     (
         mut net_query,
@@ -88,16 +97,70 @@ pub fn command_parser(
         ResMut<DefaultDrawVerts>,
         ResMut<DefaultLT>,
         Res<Version>,
-        Query<&VisibleEntities>,
+        Query<&VisibleEntities, With<MainCamera>>,
         EventWriter<OutDeviceCommand>,
         EventWriter<InDeviceCommand>,
         ResMut<NodeLimit>,
         Query<&mut OpNum>,
-        ResMut<SystemClipboard>,
+        Option<ResMut<SystemClipboard>>,
         Res<PasteChannel>,
-        Query<&mut BloomSettings, With<Camera>>,
+        Query<&mut BloomSettings, With<MainCamera>>,
+    ),
+    (
+        mut ortho,
+        cam,
+        mut history,
+        mut cursor,
+        mut pending,
+        transport,
+        mut link,
+        mut timing,
+        mut profiler,
+        input_ring,
+        mut jack,
+        config,
+        mut comment_query,
+        cursor_info,
+        text_size,
+        mut search,
+    ): (
+        Query<&mut OrthographicProjection, With<MainCamera>>,
+        Query<Entity, With<MainCamera>>,
+        ResMut<CommandHistory>,
+        ResMut<CommandCursor>,
+        ResMut<PendingCommands>,
+        Res<TransportRes>,
+        ResMut<Link>,
+        ResMut<Timing>,
+        ResMut<Profiler>,
+        Res<InputRingRes>,
+        ResMut<Jack>,
+        Res<Config>,
+        Query<(Entity, &mut Comment)>,
+        Res<CursorInfo>,
+        Res<TextSize>,
+        ResMut<SearchResults>,
+    ),
+    (
+        mut minimap,
+        mut bookmarks,
+        mut grid,
+        mut connection_style,
+        mut link_colors,
+        mut views,
+        locked_query,
+    ): (
+        ResMut<Minimap>,
+        ResMut<Bookmarks>,
+        ResMut<Grid>,
+        ResMut<ConnectionStyle>,
+        ResMut<LinkColors>,
+        Query<
+            (Entity, &Camera, &mut OrthographicProjection),
+            (With<ViewCamera>, Without<MainCamera>),
+        >,
+        Query<(), With<Locked>>,
     ),
-    (mut ortho, cam): (Query<&mut OrthographicProjection>, Query<Entity, With<Camera>>),
 ) {
     let clt = &mut command_line_text.single_mut();
     if key_event.is_empty() && !clt.is_changed() && !keyboard_input.just_released(KeyCode::KeyT) {
@@ -163,39 +226,152 @@ pub fn command_parser(
             next_mode.set(Mode::Draw);
         }
     } else if *mode.get() == Mode::Edit {
-        if keyboard_input.just_pressed(KeyCode::Delete) {
+        // typing a command, arrows and delete edit the text instead
+        let typing = text.starts_with([':', '/']);
+        // key bindings from the config file (when nothing's being typed)
+        if text.is_empty() || text.starts_with('>') {
+            if let Some(binding) = config.keys.iter().find(|b| b.just_pressed(&keyboard_input)) {
+                pending.0.push_back(binding.command.clone());
+                key_event.clear();
+                return;
+            }
+        }
+        if keyboard_input.just_pressed(KeyCode::Delete) && !typing {
             delete_event.send_default();
             return;
         }
+        let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+        cursor.0 = cursor.0.min(text.chars().count());
+        // the key was typed over a search status (n/N step through the matches then)
+        let mut after_search = false;
 
         for key in key_event.read() {
             if key.state.is_pressed() {
+                let at = cursor_index(text, cursor.0);
+                if ctrl && typing {
+                    match key.key_code {
+                        // delete word
+                        KeyCode::KeyW => {
+                            let start = text[..at].trim_end().rfind(' ').map_or(0, |i| i + 1);
+                            text.replace_range(start..at, "");
+                            history.index = None;
+                            continue;
+                        }
+                        // delete to start
+                        KeyCode::KeyU => {
+                            text.replace_range(..at, "");
+                            history.index = None;
+                            continue;
+                        }
+                        KeyCode::KeyV => {
+                            #[cfg(not(target_arch = "wasm32"))]
+                            if let Some(clipboard) = &mut clipboard {
+                                if let Ok(string) = clipboard.0.get_contents() {
+                                    let string = string.trim().replace('\n', ";");
+                                    text.insert_str(at, &string);
+                                    history.index = None;
+                                }
+                            }
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
                 match &key.logical_key {
                     Key::Character(c) => {
                         if let Some(c) = c.chars().next() {
+                            after_search = text.starts_with(">/");
                             if text.starts_with('>') {
                                 text.clear();
+                                cursor.0 = 0;
                             }
                             if !c.is_control() && *text != "F" {
-                                text.push(c);
+                                text.insert(cursor_index(text, cursor.0), c);
+                                history.index = None;
                             }
                         }
                     }
-                    Key::Space => {
-                        if !text.ends_with(' ') && !text.is_empty() && *text != "F" {
-                            text.push(' ');
-                        }
+                    Key::Space
+                        if !text[..at].ends_with(' ') && !text.is_empty() && *text != "F" =>
+                    {
+                        text.insert(at, ' ');
+                        history.index = None;
+                    }
+                    Key::Backspace if ctrl => {
+                        let start = text[..at].trim_end().rfind(' ').map_or(0, |i| i + 1);
+                        text.replace_range(start..at, "");
+                        history.index = None;
                     }
                     Key::Backspace => {
-                        text.pop();
+                        if let Some((i, _)) = text[..at].char_indices().next_back() {
+                            text.remove(i);
+                        }
+                        history.index = None;
+                    }
+                    Key::Delete if typing && cursor.0 > 0 => {
+                        text.remove(at);
+                        cursor.0 -= 1;
+                        history.index = None;
                     }
                     Key::Escape => {
                         text.clear();
+                        cursor.0 = 0;
+                        history.index = None;
                     }
                     Key::Enter => {
+                        remember(&mut history, text);
                         text.push('\t');
+                        cursor.0 = 0;
+                    }
+                    Key::Tab if cursor.0 == 0 => {
+                        if let Some(completed) = complete(text) {
+                            *text = completed;
+                        }
+                    }
+                    Key::ArrowLeft | Key::Home if typing => {
+                        if key.logical_key == Key::Home {
+                            cursor.0 = text.chars().count();
+                        } else if cursor.0 < text.chars().count() {
+                            cursor.0 += 1;
+                        }
+                    }
+                    Key::ArrowRight | Key::End if typing => {
+                        if key.logical_key == Key::End {
+                            cursor.0 = 0;
+                        } else {
+                            cursor.0 = cursor.0.saturating_sub(1);
+                        }
+                    }
+                    Key::ArrowUp if typing => {
+                        if history.index.is_none() {
+                            history.prefix = text.clone();
+                        }
+                        let end = history.index.unwrap_or(history.entries.len());
+                        let prefix = &history.prefix;
+                        if let Some(i) =
+                            history.entries[..end].iter().rposition(|e| e.starts_with(prefix))
+                        {
+                            history.index = Some(i);
+                            *text = history.entries[i].clone();
+                            cursor.0 = 0;
+                        }
+                    }
+                    Key::ArrowDown if typing => {
+                        if let Some(index) = history.index {
+                            let prefix = &history.prefix;
+                            let next = history.entries[index + 1..]
+                                .iter()
+                                .position(|e| e.starts_with(prefix));
+                            if let Some(i) = next {
+                                history.index = Some(index + 1 + i);
+                                *text = history.entries[index + 1 + i].clone();
+                            } else {
+                                history.index = None;
+                                *text = history.prefix.clone();
+                            }
+                            cursor.0 = 0;
+                        }
                     }
-                    // tab completion when?
                     _ => {}
                 }
             }
@@ -203,11 +379,28 @@ pub fn command_parser(
         if text.ends_with('\t') {
             // commands starting with :
             let lines = text.as_str().split(';');
+            let mut error = None;
             for line in lines {
                 // (entity, lt) if there's a given entity
                 let mut lt_to_open = (None, None);
                 let mut command = line.split_ascii_whitespace();
                 let c0 = command.next();
+                // locked entities can't be edited, selected or by id
+                let editable: Vec<Entity> =
+                    selected_query.iter().filter(|e| !locked_query.contains(*e)).collect();
+                let id = match c0 {
+                    Some(":set") | Some("set") | Some(":delta") | Some("delta") => {
+                        line.split_ascii_whitespace().nth(2)
+                    }
+                    Some(":lt") | Some("lt") | Some(":push") | Some("push") | Some(":tsel") => {
+                        line.split_ascii_whitespace().nth(1)
+                    }
+                    _ => None,
+                };
+                if let Some(e) = id.and_then(str_to_id).filter(|e| locked_query.contains(*e)) {
+                    error = Some(format!("{} is locked (:unlock {})", e, e));
+                    continue;
+                }
                 match c0 {
                     // open scene file
                     Some(":e") => {
@@ -227,6 +420,9 @@ pub fn command_parser(
                     Some(":q") => {
                         exit_event.send_default();
                     }
+                    Some(":od") | Some(":id") if jack.enabled => {
+                        error = Some("jack is on (:jack off to use devices)".to_string());
+                    }
                     Some(":od") | Some(":id") => {
                         let h = command.next();
                         let d = command.next();
@@ -280,7 +476,7 @@ pub fn command_parser(
                                     }
                                 }
                             } else {
-                                for id in selected_query.iter() {
+                                for id in editable.iter().copied() {
                                     if let Ok(mut wh) = white_hole_query.get_mut(id) {
                                         wh.link_types.1 = str_to_lt(s);
                                         wh.open = true;
@@ -352,7 +548,7 @@ pub fn command_parser(
                                     }
                                 }
                             } else {
-                                for id in selected_query.iter() {
+                                for id in editable.iter().copied() {
                                     if let Some(t) = str_to_id(a1) {
                                         if let Ok(mut targets) = targets_query.get_mut(id) {
                                             targets.0.push(t);
@@ -385,7 +581,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut num) = num_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     num.0 = n;
@@ -426,7 +622,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut trans) = trans_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     if c1 == Some("r") {
@@ -467,7 +663,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut t) = trans_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     t.translation.x = n;
@@ -496,7 +692,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut t) = trans_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     t.translation.y = n;
@@ -525,7 +721,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut t) = trans_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     t.translation.z = n;
@@ -554,7 +750,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut color) = col_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     color.0.hue = n;
@@ -583,7 +779,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut color) = col_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     color.0.saturation = n;
@@ -612,7 +808,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut color) = col_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     color.0.lightness = n;
@@ -641,7 +837,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut color) = col_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     color.0.alpha = n;
@@ -670,7 +866,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = s.parse::<usize>() {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut vertices) = vertices_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     vertices.0 = n.max(3);
@@ -701,7 +897,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = parse_with_constants(s) {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut t) = trans_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     t.rotation = Quat::from_rotation_z(n);
@@ -728,18 +924,30 @@ pub fn command_parser(
                                             let op_str = op_str.trim_start_matches(s).trim_start();
                                             op.0 = op_str.into();
                                             op_changed_query.get_mut(e).unwrap().0 = true;
-                                            net_query.get_mut(e).unwrap().0 = str_to_net(op_str);
+                                            let net = &mut net_query.get_mut(e).unwrap().0;
+                                            *net = op_to_net(&mut commands, e, op_str, &mut error);
+                                            if profiler.enabled {
+                                                profiler.probe(e, net);
+                                            }
                                             op_num_query.get_mut(e).unwrap().0 =
                                                 str_to_op_num(op_str);
                                             lt_to_open = (Some(e), Some(0));
                                         }
                                     } else {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut op) = op_query.get_mut(id) {
                                                 op.0 = op_str.into();
                                                 op_changed_query.get_mut(id).unwrap().0 = true;
-                                                net_query.get_mut(id).unwrap().0 =
-                                                    str_to_net(op_str);
+                                                let net = &mut net_query.get_mut(id).unwrap().0;
+                                                *net = op_to_net(
+                                                    &mut commands,
+                                                    id,
+                                                    op_str,
+                                                    &mut error,
+                                                );
+                                                if profiler.enabled {
+                                                    profiler.probe(id, net);
+                                                }
                                                 op_num_query.get_mut(id).unwrap().0 =
                                                     str_to_op_num(op_str);
                                             }
@@ -748,6 +956,29 @@ pub fn command_parser(
                                     }
                                 }
                             }
+                            Some("text") => {
+                                if let Some(s) = command.next() {
+                                    let text_str = line.trim();
+                                    let text_str = if text_str.starts_with(':') {
+                                        text_str.trim_start_matches(":set text ")
+                                    } else {
+                                        text_str.trim_start_matches("set text ")
+                                    };
+                                    let id = str_to_id(s).filter(|e| comment_query.contains(*e));
+                                    if let Some(e) = id {
+                                        let text_str = text_str.trim_start_matches(s).trim_start();
+                                        comment_query.get_mut(e).unwrap().1 .0 =
+                                            text_str.replace("\\n", "\n");
+                                    } else {
+                                        for id in editable.iter().copied() {
+                                            if let Ok((_, mut comment)) = comment_query.get_mut(id)
+                                            {
+                                                comment.0 = text_str.replace("\\n", "\n");
+                                            }
+                                        }
+                                    }
+                                }
+                            }
                             Some("ord") | Some("order") => {
                                 if let Some(s) = command.next() {
                                     if let Some(e) = str_to_id(s) {
@@ -764,7 +995,7 @@ pub fn command_parser(
                                             }
                                         }
                                     } else if let Ok(n) = s.parse::<f32>() {
-                                        for id in selected_query.iter() {
+                                        for id in editable.iter().copied() {
                                             if let Ok(mut order) = order_query.get_mut(id) {
                                                 if c0 == Some(":set") || c0 == Some("set") {
                                                     order.0 = n as usize;
@@ -797,7 +1028,7 @@ pub fn command_parser(
                                         lt_to_open = (Some(id), Some(-13));
                                     }
                                 } else {
-                                    for id in selected_query.iter() {
+                                    for id in editable.iter().copied() {
                                         if let Ok(mut arr) = arr_query.get_mut(id) {
                                             arr.0.clone_from(&tmp);
                                         }
@@ -823,7 +1054,7 @@ pub fn command_parser(
                                     }
                                 } else {
                                     // all selected circles get the list of entities as targets
-                                    for e in selected_query.iter() {
+                                    for e in editable.iter().copied() {
                                         if let Ok(mut c) = targets_query.get_mut(e) {
                                             c.0.clone_from(&tmp);
                                         }
@@ -848,6 +1079,118 @@ pub fn command_parser(
                             }
                         }
                     }
+                    // a note at the cursor, `\n` starts a new line
+                    Some(":comment") => {
+                        let text_str = line.trim().trim_start_matches(":comment").trim_start();
+                        for e in selected_query.iter() {
+                            commands.entity(e).remove::<Selected>();
+                        }
+                        let size = text_size.0 * 120.;
+                        commands.spawn((
+                            Comment(text_str.replace("\\n", "\n")),
+                            Col(default_color.0),
+                            Transform {
+                                translation: cursor_info.i.extend(100.),
+                                scale: Vec3::new(size, size, 1.),
+                                ..default()
+                            },
+                            Selected,
+                        ));
+                    }
+                    Some(":source") => {
+                        if let Some(s) = command.next() {
+                            match read_commands(std::path::Path::new(s)) {
+                                Ok(lines) => {
+                                    for line in lines.into_iter().rev() {
+                                        pending.0.push_front(line);
+                                    }
+                                }
+                                Err(err) => error = Some(format!("{}: {}", s, err)),
+                            }
+                        }
+                    }
+                    // transport
+                    Some(":bpm") | Some(":swing") | Some(":meter") => {
+                        if let Some(n) = command.next().and_then(|s| s.parse::<f32>().ok()) {
+                            match c0 {
+                                Some(":bpm") => transport.0.set_bpm(n),
+                                Some(":swing") => transport.0.set_swing(n),
+                                _ => transport.0.set_meter(n),
+                            }
+                        }
+                    }
+                    Some(":latency") => {
+                        if let Some(n) = command.next().and_then(|s| s.parse::<f32>().ok()) {
+                            timing.latency = Duration::from_secs_f32(n.max(0.) / 1000.);
+                        }
+                    }
+                    Some(":in_latency") => {
+                        if let Some(n) = command.next().and_then(|s| s.parse::<f32>().ok()) {
+                            input_ring.0.set_latency(n);
+                        }
+                    }
+                    Some(":play") => transport.0.set_playing(true),
+                    Some(":stop") => {
+                        transport.0.set_playing(false);
+                        transport.0.set_position(0.);
+                    }
+                    Some(":link") => {
+                        link.enabled = match command.next() {
+                            Some("on") => true,
+                            Some("off") => false,
+                            _ => !link.enabled,
+                        };
+                    }
+                    Some(":jack") => {
+                        jack.enabled = match command.next() {
+                            Some("on") => true,
+                            Some("off") => false,
+                            _ => !jack.enabled,
+                        };
+                    }
+                    Some(":jack_connect") => {
+                        if let (Some(from), Some(to)) = (command.next(), command.next()) {
+                            jack.rules.push((from.to_string(), to.to_string()));
+                            jack.rules_changed = true;
+                        }
+                    }
+                    Some(":jack_transport") => {
+                        let follow = match command.next() {
+                            Some("on") => true,
+                            Some("off") => false,
+                            _ => !jack.following(),
+                        };
+                        jack.set_following(follow);
+                    }
+                    Some(":profile") => {
+                        let enable = match command.next() {
+                            Some("on") => true,
+                            Some("off") => false,
+                            _ => !profiler.enabled,
+                        };
+                        if !enable {
+                            profiler.stop();
+                        } else if !profiler.enabled {
+                            profiler.start();
+                            // probe the nets as they are (keeping their state),
+                            // and have the circles reading them take the probed ones
+                            for id in circle_query.iter() {
+                                if let Ok(mut net) = net_query.get_mut(id) {
+                                    profiler.probe(id, &mut net.0);
+                                }
+                                let Ok(holes) = holes_query.get(id) else { continue };
+                                for hole in &holes.0 {
+                                    if let Ok(bh) = black_hole_query.get(*hole) {
+                                        if let Ok(mut wh) = white_hole_query.get_mut(bh.wh) {
+                                            if wh.link_types.0 == 0 {
+                                                wh.open = true;
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                     Some(":reset_bloom") => {
                         *bloom.single_mut() = BloomSettings {
                             intensity: 0.5,
@@ -862,11 +1205,206 @@ pub fn command_parser(
                             Transform::from_translation(Vec3::Z * 200.);
                         ortho.single_mut().scale = 1.;
                     }
+                    Some(":lock") | Some(":unlock") => {
+                        let ids: Vec<Entity> = match command.next() {
+                            Some(s) => str_to_id(s).into_iter().collect(),
+                            None => selected_query.iter().collect(),
+                        };
+                        for e in ids {
+                            if let Some(mut e) = commands.get_entity(e) {
+                                if c0 == Some(":lock") {
+                                    e.insert(Locked);
+                                } else {
+                                    e.remove::<Locked>();
+                                }
+                            }
+                        }
+                    }
+                    // other windows
+                    Some(":window") => {
+                        let layers: Vec<usize> = command
+                            .by_ref()
+                            .filter_map(|s| s.parse::<usize>().ok())
+                            .filter(|l| *l <= 4)
+                            .collect();
+                        let layers = if layers.is_empty() {
+                            RenderLayers::from_layers(&[0, 1, 2, 3, 4])
+                        } else {
+                            RenderLayers::from_layers(&layers)
+                        };
+                        let t = *trans_query.get(cam.single()).unwrap();
+                        open_view(&mut commands, layers, t, ortho.single().scale);
+                    }
+                    Some(":window_cam") => {
+                        let t = *trans_query.get(cam.single()).unwrap();
+                        let scale = ortho.single().scale;
+                        for (e, _, mut view_ortho) in views.iter_mut() {
+                            *trans_query.get_mut(e).unwrap() = t;
+                            view_ortho.scale = scale;
+                        }
+                    }
+                    Some(":window_close") => {
+                        for (e, camera, _) in views.iter() {
+                            if let RenderTarget::Window(WindowRef::Entity(window)) = camera.target {
+                                commands.entity(window).despawn();
+                            }
+                            commands.entity(e).despawn();
+                        }
+                    }
+                    // layout
+                    Some(":grid") => match command.next() {
+                        Some("off") => grid.0 = 0.,
+                        Some(s) => {
+                            if let Ok(n) = s.parse::<f32>() {
+                                grid.0 = n.max(0.);
+                            }
+                        }
+                        None => error = Some(format!("grid: {}", grid.0)),
+                    },
+                    Some(":align") => match command.next().and_then(str_to_side) {
+                        Some(side) => {
+                            let circles = selected_circles(&editable, &trans_query, &order_query);
+                            let centers: Vec<(Vec2, f32)> =
+                                circles.iter().map(|c| (c.1, c.2)).collect();
+                            let to = align(&centers, side);
+                            let moves = circles.iter().map(|c| c.0).zip(to);
+                            move_circles(moves, &mut trans_query, &holes_query);
+                        }
+                        None => {
+                            error = Some(
+                                "align left, center, right, top, middle, or bottom".to_string(),
+                            )
+                        }
+                    },
+                    Some(":distribute") => {
+                        let vertical = matches!(command.next(), Some("v") | Some("vertical"));
+                        let circles = selected_circles(&editable, &trans_query, &order_query);
+                        let centers: Vec<(Vec2, f32)> =
+                            circles.iter().map(|c| (c.1, c.2)).collect();
+                        let to = distribute(&centers, vertical);
+                        let moves = circles.iter().map(|c| c.0).zip(to);
+                        move_circles(moves, &mut trans_query, &holes_query);
+                    }
+                    Some(":arrange") => {
+                        let gap = command.next().and_then(|s| s.parse::<f32>().ok());
+                        let gap = gap.unwrap_or(if grid.0 > 0. { grid.0 } else { 20. });
+                        let circles = selected_circles(&editable, &trans_query, &order_query);
+                        let to = columns(
+                            &circles.iter().map(|c| (c.1, c.2, c.3)).collect::<Vec<_>>(),
+                            gap,
+                        );
+                        let moves = circles.iter().map(|c| c.0).zip(to);
+                        move_circles(moves, &mut trans_query, &holes_query);
+                    }
+                    Some(":layout") => {
+                        let gap = command.next().and_then(|s| s.parse::<f32>().ok());
+                        let gap = gap.unwrap_or(if grid.0 > 0. { grid.0 } else { 20. });
+                        layout_selected(
+                            gap,
+                            &editable,
+                            &mut trans_query,
+                            &order_query,
+                            &holes_query,
+                            &black_hole_query,
+                            &white_hole_query,
+                        );
+                    }
+                    // connections
+                    Some(":connection_style") => {
+                        let style = match command.next() {
+                            Some("straight") => Some(ConnectionStyle::Straight),
+                            Some("curve") => Some(ConnectionStyle::Curve),
+                            Some("route") => Some(ConnectionStyle::Route),
+                            _ => None,
+                        };
+                        match style {
+                            Some(style) => *connection_style = style,
+                            None => error = Some("straight, curve, or route".to_string()),
+                        }
+                    }
+                    Some(":link_color") => match command.next().map(str_to_lt) {
+                        Some(lt) => {
+                            let hsla: Vec<f32> =
+                                command.by_ref().filter_map(|s| s.parse::<f32>().ok()).collect();
+                            match hsla[..] {
+                                [] => {
+                                    link_colors.0.remove(&lt);
+                                }
+                                [h, s, l] => {
+                                    link_colors.0.insert(lt, Hsla::new(h, s, l, 1.));
+                                }
+                                [h, s, l, a, ..] => {
+                                    link_colors.0.insert(lt, Hsla::new(h, s, l, a));
+                                }
+                                _ => error = Some("link_color {type} {h} {s} {l} [a]".to_string()),
+                            }
+                        }
+                        None => error = Some("link_color {type} {h} {s} {l} [a]".to_string()),
+                    },
+                    // remember where the camera is
+                    Some(":mark") => match command.next() {
+                        Some(name) if name.chars().count() == 1 => {
+                            let translation = trans_query.get(cam.single()).unwrap().translation;
+                            let scale = ortho.single().scale;
+                            let mark = Bookmark { translation: translation.xy(), scale };
+                            bookmarks.0.insert(name.to_string(), mark);
+                        }
+                        _ => error = Some("marks are one character (:mark a)".to_string()),
+                    },
+                    // select what matches, and go to the first one
+                    Some(c) if c.starts_with('/') => {
+                        let pattern = line.trim().strip_prefix('/').unwrap_or_default();
+                        // an empty pattern searches again
+                        let pattern = if pattern.is_empty() {
+                            search.pattern.clone()
+                        } else {
+                            pattern.into()
+                        };
+                        match SearchPattern::new(&pattern) {
+                            Ok(p) => {
+                                let mut found = Vec::new();
+                                for e in circle_query.iter() {
+                                    let op = op_query.get(e).ok().map(|op| op.0.as_str());
+                                    let num = num_query.get(e).ok().map(|n| n.0);
+                                    if p.matches(&e.to_string(), op, num) {
+                                        found.push(e);
+                                    }
+                                }
+                                for (e, comment) in comment_query.iter() {
+                                    if p.matches(&e.to_string(), Some(&comment.0), None) {
+                                        found.push(e);
+                                    }
+                                }
+                                // top to bottom, left to right
+                                let pos = |e: &Entity| {
+                                    trans_query.get(*e).map_or(Vec2::ZERO, |t| t.translation.xy())
+                                };
+                                found.sort_by(|a, b| {
+                                    let (a, b) = (pos(a), pos(b));
+                                    b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x))
+                                });
+                                for e in selected_query.iter() {
+                                    commands.entity(e).remove::<Selected>();
+                                }
+                                for e in &found {
+                                    commands.entity(*e).insert(Selected);
+                                }
+                                search.pattern = pattern;
+                                search.matches = found;
+                                search.index = 0;
+                                if let Some(e) = search.matches.first() {
+                                    center_camera(cam.single(), *e, &mut trans_query);
+                                }
+                                error = Some(search_status(&search));
+                            }
+                            Err(err) => error = Some(err.to_string()),
+                        }
+                    }
                     _ => {}
                 }
                 // open all white holes reading whatever changed
                 if let (None, Some(lt)) = lt_to_open {
-                    for id in selected_query.iter() {
+                    for id in editable.iter().copied() {
                         if let Ok(holes) = holes_query.get(id) {
                             for hole in &holes.0 {
                                 if let Ok(bh) = black_hole_query.get(*hole) {
@@ -894,11 +1432,30 @@ pub fn command_parser(
                 }
             }
             text.clear();
+            if let Some(err) = error {
+                *text = format!(">{}", err);
+            }
         }
         // key commands
         let mut command = text.as_str().split_ascii_whitespace();
         let c0 = command.next();
         match c0 {
+            // next/previous search match, right after a search (otherwise
+            // n starts ni, no, np, nc)
+            Some("n") | Some("N") if after_search => {
+                let len = search.matches.len();
+                if len == 0 {
+                    *text = ">no matches (search with /pattern)".to_string();
+                } else {
+                    search.index = if c0 == Some("n") {
+                        (search.index + 1) % len
+                    } else {
+                        (search.index + len - 1) % len
+                    };
+                    center_camera(cam.single(), search.matches[search.index], &mut trans_query);
+                    *text = format!(">{}", search_status(&search));
+                }
+            }
             Some("d") => {
                 next_mode.set(Mode::Draw);
                 *text = "-- DRAW --".to_string();
@@ -1012,7 +1569,7 @@ pub fn command_parser(
             }
             // increment/decrement order
             Some("]") | Some("[") => {
-                for id in selected_query.iter() {
+                for id in selected_query.iter().filter(|e| !locked_query.contains(*e)) {
                     if let Ok(mut order) = order_query.get_mut(id) {
                         if c0 == Some("]") {
                             order.0 += 1;
@@ -1026,7 +1583,7 @@ pub fn command_parser(
             }
             // increment/decrement link type
             Some("}") | Some("{") => {
-                for id in selected_query.iter() {
+                for id in selected_query.iter().filter(|e| !locked_query.contains(*e)) {
                     if let Ok(mut wh) = white_hole_query.get_mut(id) {
                         if c0 == Some("}") {
                             wh.link_types.1 = wh.link_types.1.saturating_add(1);
@@ -1078,6 +1635,24 @@ pub fn command_parser(
                     }
                 }
             }
+            Some("nc") => {
+                *text = format!(">DSP LOAD: {:.1}%\n", profiler.load * 100.);
+                if !profiler.enabled {
+                    *text += "(:profile to see what each circle costs)\n";
+                }
+                // the selected circles, or the 10 most expensive ones
+                let mut loads: Vec<_> = profiler.circles.iter().map(|(e, l)| (*e, l.1)).collect();
+                if selected_query.is_empty() {
+                    loads.sort_by(|a, b| b.1.total_cmp(&a.1));
+                    loads.truncate(10);
+                } else {
+                    loads.retain(|(e, _)| selected_query.contains(*e));
+                }
+                for (e, load) in loads {
+                    let op = op_query.get(e).map(|op| op.0.as_str()).unwrap_or_default();
+                    *text += &format!("[{}] {}  {:.2}%\n", e, op, load * 100.);
+                }
+            }
             Some("ah") => {
                 *text = ">HOSTS:\n".to_string();
                 let hosts = cpal::platform::ALL_HOSTS;
@@ -1133,6 +1708,13 @@ pub fn command_parser(
                     }
                 }
             }
+            Some("jp") => {
+                if jack.enabled {
+                    *text = ">JACK PORTS:\n".to_string() + &jack.ports.join("\n");
+                } else {
+                    *text = ">jack is off (:jack)".to_string();
+                }
+            }
             // inspect commands
             Some("ii") => {
                 let mut t = String::new();
@@ -1217,8 +1799,9 @@ pub fn command_parser(
             Some("iv") => {
                 let mut t = String::new();
                 for e in selected_query.iter() {
-                    let v = vertices_query.get(e).unwrap().0;
-                    t = t + &format!("[{}]{}  ", e, v);
+                    if let Ok(v) = vertices_query.get(e) {
+                        t = t + &format!("[{}]{}  ", e, v.0);
+                    }
                 }
                 *text = format!(">VERTICES: {}", t);
             }
@@ -1450,6 +2033,42 @@ pub fn command_parser(
                 show_info_text.1 = !show_info_text.1;
                 text.clear();
             }
+            Some("vm") => {
+                minimap.show = !minimap.show;
+                text.clear();
+            }
+            // zoom to fit the selection (or everything)
+            Some("zf") => {
+                let mut fit: Vec<Entity> = selected_query.iter().collect();
+                if fit.is_empty() {
+                    fit = circle_query.iter().chain(comment_query.iter().map(|c| c.0)).collect();
+                }
+                let transforms: Vec<Transform> =
+                    fit.iter().filter_map(|e| trans_query.get(*e).ok()).copied().collect();
+                if let Some(rect) = bounds(&transforms) {
+                    let mut ortho = ortho.single_mut();
+                    // the window size (in px)
+                    let window = ortho.area.size() / ortho.scale;
+                    let scale = (rect.size() * 1.1 / window).max_element();
+                    if scale.is_finite() {
+                        ortho.scale = scale.clamp(0.005, 80.);
+                    }
+                    let mut t = trans_query.get_mut(cam.single()).unwrap();
+                    t.translation = rect.center().extend(t.translation.z);
+                }
+                text.clear();
+            }
+            // jump to a mark
+            Some(c) if c.starts_with('\'') && c.chars().count() == 2 => {
+                if let Some(mark) = bookmarks.0.get(&c[1..]) {
+                    let mut t = trans_query.get_mut(cam.single()).unwrap();
+                    t.translation = mark.translation.extend(t.translation.z);
+                    ortho.single_mut().scale = mark.scale;
+                    text.clear();
+                } else {
+                    *text = format!(">no mark {}", c);
+                }
+            }
             // copypasting
             Some("yy") | Some("\"+y") => {
                 copy_event.send_default();
@@ -1457,8 +2076,10 @@ pub fn command_parser(
             }
             Some("p") | Some("\"+p") => {
                 #[cfg(not(target_arch = "wasm32"))]
-                if let Ok(string) = clipboard.0.get_contents() {
-                    let _ = paste_chan.0 .0.try_send(string);
+                if let Some(clipboard) = &mut clipboard {
+                    if let Ok(string) = clipboard.0.get_contents() {
+                        let _ = paste_chan.0 .0.try_send(string);
+                    }
                 }
                 #[cfg(target_arch = "wasm32")]
                 if let Some(win) = web_sys::window() {
@@ -1510,6 +2131,242 @@ never gonna tell a lie and hurt you",
     }
 }
 
+// show what tab would complete to (with usage and a description)
+pub fn completion_popup(
+    command_line_text: Query<Ref<Text>, (With<CommandText>, Without<CompletionText>)>,
+    mut popup_text: Query<&mut Text, With<CompletionText>>,
+    mode: Res<State<Mode>>,
+) {
+    let clt = command_line_text.single();
+    if !clt.is_changed() && !mode.is_changed() {
+        return;
+    }
+    let popup = &mut popup_text.single_mut();
+    popup.sections[0].style.color = clt.sections[0].style.color;
+    let popup = &mut popup.sections[0].value;
+    popup.clear();
+    if *mode.get() != Mode::Edit {
+        return;
+    }
+    let Some((ctx, _)) = context(&clt.sections[0].value) else { return };
+    let m = match ctx {
+        Context::Command("") | Context::Property("") | Context::Op("") => return,
+        // typing parameters, only show the op's usage
+        Context::Op(p) if p.contains('(') => {
+            let name = p.split('(').next().unwrap_or_default();
+            OPS.iter().filter(|e| e.0 == name).collect()
+        }
+        _ => matches(&ctx),
+    };
+    for (_, usage, desc) in m.iter().take(8).rev() {
+        popup.push_str(&format!("{:<32} {}\n", usage, desc));
+    }
+}
+
+// the underscore under the cursor (the font is monospace, so spaces line it up)
+pub fn command_cursor(
+    command_line_text: Query<Ref<Text>, (With<CommandText>, Without<CommandCursorText>)>,
+    mut cursor_text: Query<&mut Text, With<CommandCursorText>>,
+    cursor: Res<CommandCursor>,
+) {
+    let clt = command_line_text.single();
+    if !clt.is_changed() && !cursor.is_changed() {
+        return;
+    }
+    let t = &mut cursor_text.single_mut();
+    t.sections[0].style.color = clt.sections[0].style.color;
+    let text = &clt.sections[0].value;
+    t.sections[0].value = if text.starts_with([':', '/']) && !text.contains(['\n', '\t']) {
+        let col = text.chars().count().saturating_sub(cursor.0);
+        format!("{}_", " ".repeat(col))
+    } else {
+        String::new()
+    };
+}
+
+// run condition for things that use the arrow keys
+pub fn typing_command(query: Query<&Text, With<CommandText>>) -> bool {
+    query.get_single().is_ok_and(|t| t.sections[0].value.starts_with([':', '/']))
+}
+
+// feed `:source`d commands to the command line, one per frame
+pub fn run_pending_commands(
+    mut pending: ResMut<PendingCommands>,
+    mut command_line_text: Query<&mut Text, With<CommandText>>,
+    mode: Res<State<Mode>>,
+) {
+    if pending.0.is_empty() || *mode.get() != Mode::Edit {
+        return;
+    }
+    let text = &mut command_line_text.single_mut().sections[0].value;
+    // don't step on what's being typed
+    if !text.is_empty() && !text.starts_with('>') {
+        return;
+    }
+    if let Some(line) = pending.0.pop_front() {
+        // `:` commands and searches need a return, the rest run as soon as they're typed
+        if line.starts_with([':', '/']) {
+            *text = format!("{}\t", line);
+        } else {
+            *text = line;
+        }
+    }
+}
+
+pub fn load_history(mut history: ResMut<CommandHistory>) {
+    let Some(path) = state_dir().map(|d| d.join("history")) else { return };
+    let Ok(s) = std::fs::read_to_string(&path) else { return };
+    let entries: Vec<String> = s.lines().map(String::from).collect();
+    let skip = entries.len().saturating_sub(HISTORY_LEN);
+    history.entries = entries.into_iter().skip(skip).collect();
+    // don't let the file grow forever
+    if skip > 0 {
+        let _ = std::fs::write(&path, history.entries.join("\n") + "\n");
+    }
+}
+
+// run the rc file at startup (if there is one)
+pub fn source_rc(mut pending: ResMut<PendingCommands>) {
+    if let Some(path) = config_dir().map(|d| d.join("quartzrc")) {
+        if let Ok(lines) = read_commands(&path) {
+            pending.0.extend(lines);
+        }
+    }
+}
+
+const HISTORY_LEN: usize = 1000;
+
+fn remember(history: &mut CommandHistory, line: &str) {
+    history.index = None;
+    if line.is_empty() || history.entries.last().is_some_and(|l| l == line) {
+        return;
+    }
+    history.entries.push(line.to_string());
+    if history.entries.len() > HISTORY_LEN {
+        history.entries.remove(0);
+    }
+    if let Some(dir) = state_dir() {
+        let _ = std::fs::create_dir_all(&dir);
+        if let Ok(mut f) =
+            std::fs::OpenOptions::new().create(true).append(true).open(dir.join("history"))
+        {
+            let _ = writeln!(f, "{}", line);
+        }
+    }
+}
+
+// byte index of the cursor
+fn cursor_index(text: &str, cursor: usize) -> usize {
+    let n = text.chars().count().saturating_sub(cursor);
+    text.char_indices().nth(n).map_or(text.len(), |(i, _)| i)
+}
+
+// the selected circles (not holes) as (entity, center, radius, order)
+fn selected_circles(
+    selected: &[Entity],
+    trans_query: &Query<&mut Transform>,
+    order_query: &Query<&mut Order>,
+) -> Vec<(Entity, Vec2, f32, usize)> {
+    selected
+        .iter()
+        .filter_map(|e| {
+            let t = trans_query.get(*e).ok()?;
+            let order = order_query.get(*e).ok()?;
+            Some((*e, t.translation.xy(), t.scale.x, order.0))
+        })
+        .collect()
+}
+
+// move circles to new centers, their holes move with them
+fn move_circles(
+    moves: impl Iterator<Item = (Entity, Vec2)>,
+    trans_query: &mut Query<&mut Transform>,
+    holes_query: &Query<&Holes>,
+) {
+    for (e, to) in moves {
+        let Ok(mut t) = trans_query.get_mut(e) else { continue };
+        let d = to - t.translation.xy();
+        t.translation = to.extend(t.translation.z);
+        if let Ok(holes) = holes_query.get(e) {
+            for hole in &holes.0 {
+                if let Ok(mut t) = trans_query.get_mut(*hole) {
+                    t.translation += d.extend(0.);
+                }
+            }
+        }
+    }
+}
+
+// lay out the selected circles by their connections (see `layered`),
+// then put their holes on their edges, facing the other end
+fn layout_selected(
+    gap: f32,
+    selected: &[Entity],
+    trans_query: &mut Query<&mut Transform>,
+    order_query: &Query<&mut Order>,
+    holes_query: &Query<&Holes>,
+    black_hole_query: &Query<&BlackHole>,
+    white_hole_query: &Query<&mut WhiteHole>,
+) {
+    let circles = selected_circles(selected, trans_query, order_query);
+    let index: HashMap<Entity, usize> = circles.iter().enumerate().map(|(i, c)| (c.0, i)).collect();
+    let mut edges = Vec::new();
+    for (i, c) in circles.iter().enumerate() {
+        for hole in holes_query.get(c.0).map_or(&[][..], |h| &h.0) {
+            if let Ok(bh) = black_hole_query.get(*hole) {
+                if let Some(sink) = index.get(&bh.wh_parent) {
+                    edges.push((i, *sink));
+                }
+            }
+        }
+    }
+    let to = layered(&circles.iter().map(|c| (c.1, c.2, c.3)).collect::<Vec<_>>(), &edges, gap);
+    let center = |e: Entity| match index.get(&e) {
+        Some(i) => Some(to[*i]),
+        None => trans_query.get(e).ok().map(|t| t.translation.xy()),
+    };
+    let mut holes = Vec::new();
+    for (i, c) in circles.iter().enumerate() {
+        for hole in holes_query.get(c.0).map_or(&[][..], |h| &h.0) {
+            let other = if let Ok(bh) = black_hole_query.get(*hole) {
+                center(bh.wh_parent)
+            } else {
+                white_hole_query.get(*hole).ok().and_then(|wh| center(wh.bh_parent))
+            };
+            if let Some(other) = other {
+                holes.push((*hole, perimeter(to[i], c.2, other)));
+            }
+        }
+    }
+    for ((e, ..), to) in circles.iter().zip(to.iter()) {
+        if let Ok(mut t) = trans_query.get_mut(*e) {
+            t.translation = to.extend(t.translation.z);
+        }
+    }
+    for (hole, to) in holes {
+        if let Ok(mut t) = trans_query.get_mut(hole) {
+            t.translation = to.extend(t.translation.z);
+        }
+    }
+}
+
+// move the camera over an entity (keeping the zoom)
+fn center_camera(cam: Entity, e: Entity, trans_query: &mut Query<&mut Transform>) {
+    let Ok(t) = trans_query.get(e) else { return };
+    let xy = t.translation.xy();
+    let mut cam_t = trans_query.get_mut(cam).unwrap();
+    cam_t.translation = xy.extend(cam_t.translation.z);
+}
+
+fn search_status(search: &SearchResults) -> String {
+    match search.matches.get(search.index) {
+        Some(e) => {
+            format!("/{}: {}/{} [{}]", search.pattern, search.index + 1, search.matches.len(), e)
+        }
+        None => format!("/{}: no matches", search.pattern),
+    }
+}
+
 fn str_to_id(s: &str) -> Option<Entity> {
     let mut e = s.split('v');
     if let Some(i) = e.next() {
@@ -1524,3 +2381,20 @@ fn str_to_id(s: &str) -> Option<Entity> {
     }
     None
 }
+
+// get the net for an op string. if it doesn't parse, the circle gets marked
+// and the error is kept to be shown on the command line
+fn op_to_net(commands: &mut Commands, e: Entity, op: &str, error: &mut Option<String>) -> Net {
+    match str_to_net(op) {
+        Ok(net) => {
+            commands.entity(e).remove::<BadOp>();
+            net
+        }
+        Err(err) => {
+            let err = err.to_string();
+            commands.entity(e).insert(BadOp(err.clone()));
+            *error = Some(err);
+            Net::new(0, 0)
+        }
+    }
+}
@@ -0,0 +1,543 @@
+// command line completion
+// the tables here are also what the completion popup shows,
+// so keep them in sync with str_to_op_num, str_to_net, and the readme
+
+// (name, usage, description)
+pub const COMMANDS: &[(&str, &str, &str)] = &[
+    (":e", ":e {file}", "open a scene file (in the assets path)"),
+    (":w", ":w {file}", "save the scene to a file (overwrites)"),
+    (":q", ":q", "exit"),
+    (":od", ":od {host} {device} [sr] [block]", "set the output audio device"),
+    (":id", ":id {host} {device} [sr] [block]", "set the input audio device"),
+    (":nl", ":nl {int}", "max number of nodes for connective ops"),
+    (":lt", ":lt [id] {link type}", "set holes' link type"),
+    (":dv", ":dv {float}", "default vertices of drawn circles"),
+    (":dc", ":dc {h} [s] [l] [a]", "default color of drawn circles"),
+    (":ht", ":ht {id}", "toggle open a white hole"),
+    (":push", ":push {float}/{id}", "push a number to the array, or an id to the targets"),
+    (":set", ":set {property} [id] {value}", "set a property of the selected (or given) entities"),
+    (
+        ":delta",
+        ":delta {property} [id] {value}",
+        "add to a property of the selected (or given) entities",
+    ),
+    (":comment", ":comment {text}", "add a comment (a note on the canvas) at the cursor"),
+    (":tsel", ":tsel {id}", "set selected entities as targets of the given entity"),
+    (":source", ":source {file}", "run the commands in a file"),
+    (":bpm", ":bpm {float}", "set the transport tempo"),
+    (":swing", ":swing {float}", "swing amount (0..0.9, 1/3 is a triplet feel)"),
+    (":meter", ":meter {int}", "beats per bar"),
+    (":play", ":play", "start the transport"),
+    (":stop", ":stop", "stop the transport and go back to the start"),
+    (":link", ":link [on/off]", "sync the transport with ableton link peers on the network"),
+    (":jack", ":jack [on/off]", "run as a jack client (quartz) instead of using the devices"),
+    (":jack_connect", ":jack_connect {from} {to}", "add a jack auto-connect rule"),
+    (":jack_transport", ":jack_transport [on/off]", "follow the jack transport"),
+    (":profile", ":profile [on/off]", "show what each circle costs the audio thread"),
+    (":latency", ":latency {ms}", "how long after a frame its var() and trig() changes are heard"),
+    (":in_latency", ":in_latency {ms}", "input kept buffered before in() plays it"),
+    (":grid", ":grid {float}/off", "grid size for snapping translate and radius drags"),
+    (":align", ":align {side}", "align selected circles (left center right top middle bottom)"),
+    (":distribute", ":distribute [h/v]", "space selected circles evenly"),
+    (":arrange", ":arrange [gap]", "put selected circles in columns by order"),
+    (":layout", ":layout [gap]", "lay out selected circles by their connections"),
+    (
+        ":connection_style",
+        ":connection_style {style}",
+        "draw connections straight, curve, or route",
+    ),
+    (
+        ":link_color",
+        ":link_color {type} [h s l a]",
+        "color connections of a link type (none resets)",
+    ),
+    (":reset_bloom", ":reset_bloom", "reset bloom settings"),
+    (":reset_cam", ":reset_cam", "reset the camera"),
+    (":window", ":window [layers]", "open another window showing these render layers"),
+    (":window_cam", ":window_cam", "move the other windows' cameras to the main camera"),
+    (":window_close", ":window_close", "close the other windows"),
+    (":mark", ":mark {char}", "remember the camera position (go back to it with '{char})"),
+    (":delete", ":delete", "delete selected entities"),
+    (":lock", ":lock [id]", "lock selected entities (or id) against edits"),
+    (":unlock", ":unlock [id]", "unlock selected entities (or id)"),
+    (":help", ":help", "show the help text"),
+    (":version", ":version", "print version"),
+];
+
+// properties for `:set` and `:delta`
+pub const PROPERTIES: &[(&str, &str, &str)] = &[
+    ("n", "n [id] {float}", "num value"),
+    ("r", "r [id] {float}", "radius (font size of comments)"),
+    ("rx", "rx [id] {float}", "x radius"),
+    ("ry", "ry [id] {float}", "y radius"),
+    ("x", "x [id] {float}", "x position"),
+    ("y", "y [id] {float}", "y position"),
+    ("z", "z [id] {float}", "z position (depth)"),
+    ("h", "h [id] {float}", "hue [0...360]"),
+    ("s", "s [id] {float}", "saturation [0...1]"),
+    ("l", "l [id] {float}", "lightness [0...1]"),
+    ("a", "a [id] {float}", "alpha [0...1]"),
+    ("v", "v [id] {float}", "number of vertices (3 or higher)"),
+    ("o", "o [id] {float}", "rotation [-pi...pi]"),
+    ("rot", "rot [id] {float}", "rotation [-pi...pi]"),
+    ("rotation", "rotation [id] {float}", "rotation [-pi...pi]"),
+    ("op", "op [id] {string}", "the op string"),
+    ("ord", "ord [id] {float}", "order"),
+    ("order", "order [id] {float}", "order"),
+    ("arr", "arr [id] {float float ...}", "the array"),
+    ("array", "array [id] {float float ...}", "the array"),
+    ("text", "text [id] {string}", "the text of comments"),
+    ("tar", "tar {id id ...}", "targets"),
+    ("targets", "targets {id id ...}", "targets"),
+];
+
+pub const OPS: &[(&str, &str, &str)] = &[
+    // targets
+    ("process", "process", "process targets in order"),
+    ("select_target", "select_target  n -> 1", "select targets when input is non-zero"),
+    ("open_target", "open_target  n -> 1", "open target white holes when input is non-zero"),
+    ("close_target", "close_target  n -> 1", "close target white holes when input is non-zero"),
+    ("open_nth", "open_nth  n -> 1", "open nth target once if it's a white hole"),
+    ("del_target", "del_target  n -> 1", "delete targets when input is non-zero"),
+    ("spin_target", "spin_target  n, n -> 1", "rotate targets around this circle by n radians"),
+    ("reorder", "reorder  n -> 1", "set targets' order to input"),
+    ("spawn", "spawn  n -> 1", "spawn a circle similar to this one"),
+    ("distro", "distro  A -> n/r/x/y/z/o/v/h/s/l/a/-10", "distribute input array among targets"),
+    ("connect_target", "connect_target  n -> 1, [T -> 2]", "connect each target to the next"),
+    ("isolate_target", "isolate_target  n -> 1", "delete all connections of targets"),
+    ("target_lt", "target_lt  n -> 1", "set link type of hole targets"),
+    ("repeat", "repeat  n, T -> 1", "repeat input targets n times"),
+    // arrays
+    ("zip", "zip  A -> 1, A -> 2", "zip two arrays"),
+    ("unzip", "unzip  A -> 1", "unzip input array"),
+    ("push", "push  n -> 1", "push input num to the array"),
+    ("pop", "pop  n -> 1", "pop the last number of the array into num"),
+    ("len", "len  A -> 1 or T -> 1", "length of array (or targets)"),
+    ("append", "append  A -> 1", "append input array to the array"),
+    ("slice", "slice  n, A -> 1", "slice input array at index n"),
+    ("resize", "resize  n -> 1", "resize the array"),
+    ("contains", "contains  A -> 1, n -> 2", "1 if input array contains input num"),
+    ("set", "set  n -> 1, n -> 2", "set value (2) at index (1) of the array"),
+    ("get", "get  A -> 1, n -> 2", "get value at index of input array"),
+    ("collect", "collect  n -> {non-negative}", "collect connected nums into the array"),
+    // settings
+    ("clear_color", "clear_color", "color sets the background color"),
+    ("draw_verts", "draw_verts", "vertices set the default drawing vertices"),
+    ("draw_color", "draw_color", "color sets the default drawing color"),
+    ("highlight_color", "highlight_color", "color sets the selection highlight color"),
+    ("indicator_color", "indicator_color", "color sets the indicator color"),
+    ("connection_color", "connection_color", "color sets the connection arrow color"),
+    ("connection_width", "connection_width", "num sets the connection arrow width"),
+    ("command_color", "command_color", "color sets the command line color"),
+    ("text_size", "text_size", "num sets the info text size"),
+    ("tonemapping", "tonemapping  n -> 1", "set the tonemapping mode [0...7]"),
+    ("bloom", "bloom  n -> 1..7", "control bloom parameters"),
+    // utils
+    ("cam", "cam  n -> 1..5", "camera x, y, z, rotation, zoom"),
+    ("update_rate", "update_rate  n -> 1, n -> 2", "focused/unfocused refresh rate (hz)"),
+    ("command", "command  0 -> 1", "run the input circle's op string as a command"),
+    ("screenshot", "screenshot  n -> 1", "take a screenshot when input is non-zero"),
+    ("osc", "osc  n -> 1, 0 -> 2, n -> 3", "osc receiver port, host, sender port"),
+    ("osc_r", "osc_r {address ...}", "receive osc messages into the array"),
+    ("osc_s", "osc_s {address}  A -> 1", "send input array as an osc message"),
+    // input
+    ("mouse", "mouse", "mouse position into the array"),
+    ("touches", "touches", "every finger's position and pressure into the array"),
+    ("lmb_pressed", "lmb_pressed", "1 while left mouse button is pressed"),
+    ("mmb_pressed", "mmb_pressed", "1 while middle mouse button is pressed"),
+    ("rmb_pressed", "rmb_pressed", "1 while right mouse button is pressed"),
+    ("butt", "butt", "1 when clicked"),
+    ("toggle", "toggle", "toggle between 1 and 0 when clicked"),
+    ("key", "key", "pressed keys into the array"),
+    ("pressed", "pressed {chars}", "1 while one of the chars is pressed"),
+    // data
+    ("apply", "apply  0 -> 1, A -> 2", "process input array through the node"),
+    ("render", "render  n, 0 -> 1, n -> 2", "render n samples of the node into the array"),
+    ("store", "store  n -> 1", "store input num without opening white holes"),
+    ("num_push", "num_push  n -> 1", "output num when input is non-zero"),
+    ("worm", "worm{string}", "mirror num between worms with the same op"),
+    // transport
+    ("beat", "beat", "num is the beat within the bar"),
+    ("bar", "bar", "num is the bar number"),
+    ("xruns", "xruns", "arr is [underruns, dropped input, errors, disconnects]"),
+    ("phase", "phase({div})", "num is the phase of a division of the beat (swung)"),
+    ("sum", "sum  n -> 1 ...", "add nums together"),
+    ("product", "product  n -> 1 ...", "multiply nums together"),
+    // audio node management
+    ("+", "+  0 -> {non-negative} ..., n", "sum nodes"),
+    ("SUM", "SUM  0 -> {non-negative} ..., n", "sum nodes"),
+    ("*", "*  0 -> {non-negative} ..., n", "multiply nodes"),
+    ("PRO", "PRO  0 -> {non-negative} ..., n", "multiply nodes"),
+    ("-", "-  0 -> 1, 0 -> 2", "subtract node 2 from node 1"),
+    ("SUB", "SUB  0 -> 1, 0 -> 2", "subtract node 2 from node 1"),
+    (">>", ">>  0 -> {non-negative} ..., n", "pipe nodes"),
+    ("PIP", "PIP  0 -> {non-negative} ..., n", "pipe nodes"),
+    ("|", "|  0 -> {non-negative} ..., n", "stack nodes"),
+    ("STA", "STA  0 -> {non-negative} ..., n", "stack nodes"),
+    ("&", "&  0 -> {non-negative} ..., n", "bus nodes"),
+    ("BUS", "BUS  0 -> {non-negative} ..., n", "bus nodes"),
+    ("^", "^  0 -> {non-negative} ..., n", "branch nodes"),
+    ("BRA", "BRA  0 -> {non-negative} ..., n", "branch nodes"),
+    ("!", "!  0 -> 1", "pass extra inputs through"),
+    ("THR", "THR  0 -> 1", "pass extra inputs through"),
+    ("branch()", "branch()  A -> 1, 0 -> 2", "branch copies of op 2, # replaced by array values"),
+    ("bus()", "bus()  A -> 1, 0 -> 2", "bus copies of op 2, # replaced by array values"),
+    ("pipe()", "pipe()  A -> 1, 0 -> 2", "pipe copies of op 2, # replaced by array values"),
+    ("stack()", "stack()  A -> 1, 0 -> 2", "stack copies of op 2, # replaced by array values"),
+    ("sum()", "sum()  A -> 1, 0 -> 2", "sum copies of op 2, # replaced by array values"),
+    (
+        "product()",
+        "product()  A -> 1, 0 -> 2",
+        "multiply copies of op 2, # replaced by array values",
+    ),
+    ("swap()", "swap()  0 -> 1", "swap the node without resetting the graph"),
+    ("out()", "out()  0 -> 1", "output node to speakers"),
+    ("dac()", "dac()  0 -> 1", "output node to speakers"),
+    ("in()", "in()", "0 ins, 2 outs. the input device"),
+    ("adc()", "adc()", "0 ins, 2 outs. the input device"),
+    ("var()", "var()", "0 ins, 1 out. outputs this circle's num"),
+    ("trig()", "trig()", "0 ins, 1 out. a one sample trigger when num changes (to non-zero)"),
+    ("monitor()", "monitor()", "1 in, 1 out. num follows the latest sample"),
+    ("scope", "scope([size])", "1 in, 1 out. draws the waveform inside the circle"),
+    ("spectrum", "spectrum([size])", "1 in, 1 out. draws the spectrum inside the circle"),
+    ("timer()", "timer()", "num follows the time of the stacked node"),
+    ("buffin()", "buffin()", "1 in, 1 out. sends samples to a buffout()"),
+    ("buffout()", "buffout()  0 -> 1", "receives samples from a buffin()"),
+    ("get()", "get()", "1 in (index), 1 out. index this circle's array"),
+    ("quantize()", "quantize()  A -> 1", "1 in, 1 out. quantize to nearest step"),
+    ("feedback()", "feedback()  0 -> 1, [n -> 2]", "mix node outputs back into its inputs"),
+    ("kr()", "kr()  n, 0 -> 1", "process the node every n samples"),
+    ("s()", "s()  n, 0 -> 1", "like kr() but preserves time"),
+    ("sr()", "sr()  n, 0 -> 1", "set the sample rate of the node"),
+    ("reset()", "reset()  n, 0 -> 1", "reset the node every n seconds"),
+    ("reset_v()", "reset_v()  0 -> 1", "1 in (seconds). reset the node every n seconds"),
+    ("trig_reset()", "trig_reset()  0 -> 1", "1 in. reset the node when input is non-zero"),
+    (
+        "seq()",
+        "seq()  0 -> {non-negative} ...",
+        "4 ins (trig, index, delay, duration). sequence nodes",
+    ),
+    ("select()", "select()  0 -> {non-negative} ...", "1 in (index). switch between nodes"),
+    ("wave()", "wave()  A -> 1", "0 ins, 1 out. play the input array"),
+    ("clock", "clock([div])", "0 ins, 2 outs (trigger, phase). follows the transport"),
+    (
+        "poly",
+        "poly(n, [q])  0 -> 1, A -> 2, A -> 3",
+        "n voices of the node (ins: freq, gate, vel). q: steal the quietest",
+    ),
+    // sources
+    ("sine", "sine([hz])", "sine oscillator (no param: 1 in, hz)"),
+    ("saw", "saw([hz])", "saw oscillator (no param: 1 in, hz)"),
+    ("square", "square([hz])", "square oscillator (no param: 1 in, hz)"),
+    ("triangle", "triangle([hz])", "triangle oscillator (no param: 1 in, hz)"),
+    ("organ", "organ([hz])", "organ oscillator (no param: 1 in, hz)"),
+    ("hammond", "hammond([hz])", "hammond oscillator (no param: 1 in, hz)"),
+    ("soft_saw", "soft_saw([hz])", "soft saw oscillator (no param: 1 in, hz)"),
+    ("dsf_saw", "dsf_saw([roughness])", "dsf saw (ins: hz, [roughness])"),
+    ("dsf_square", "dsf_square([roughness])", "dsf square (ins: hz, [roughness])"),
+    ("pulse", "pulse()", "pulse oscillator (ins: hz, duty cycle)"),
+    ("brown", "brown()", "brown noise"),
+    ("pink", "pink()", "pink noise"),
+    ("white", "white()", "white noise"),
+    ("noise", "noise()", "white noise"),
+    ("zero", "zero()", "silence"),
+    ("impulse", "impulse()", "one sample impulse"),
+    ("lorenz", "lorenz()", "lorenz system (1 in, hz)"),
+    ("rossler", "rossler()", "rossler system (1 in, hz)"),
+    ("constant", "constant(float ...)", "constant output (up to 8 channels)"),
+    ("dc", "dc(float ...)", "constant output (up to 8 channels)"),
+    ("pluck", "pluck(hz, gain, damping)", "plucked string (1 in, excitation)"),
+    ("mls", "mls([bits])", "maximum length sequence"),
+    ("ramp", "ramp()", "ramp from 0 to 1 at input hz (phasor)"),
+    // filters
+    ("allpole", "allpole([delay])", "allpole filter (no param: ins signal, delay)"),
+    ("pinkpass", "pinkpass()", "pinking filter"),
+    ("allpass", "allpass([hz], [q])", "allpass filter (missing params are inputs)"),
+    ("bandpass", "bandpass([hz], [q])", "bandpass filter (missing params are inputs)"),
+    ("bandrez", "bandrez([hz], [q])", "resonant bandpass (missing params are inputs)"),
+    ("bell", "bell([hz], [q, gain])", "bell filter (missing params are inputs)"),
+    ("biquad", "biquad(a1, a2, b0, b1, b2)", "biquad filter"),
+    ("butterpass", "butterpass([hz])", "butterworth lowpass (no param: ins signal, hz)"),
+    ("dcblock", "dcblock([hz])", "dc blocker (default 10hz)"),
+    ("fir", "fir(weight ...)", "fir filter (up to 10 weights)"),
+    ("fir3", "fir3(gain)", "3 tap fir, param is gain at nyquist"),
+    ("follow", "follow(attack, [release])", "smoothing filter / envelope follower"),
+    ("highpass", "highpass([hz], [q])", "highpass filter (missing params are inputs)"),
+    ("highpole", "highpole([hz])", "one pole highpass (no param: ins signal, hz)"),
+    ("highshelf", "highshelf([hz], [q, gain])", "high shelf filter (missing params are inputs)"),
+    ("lowpass", "lowpass([hz], [q])", "lowpass filter (missing params are inputs)"),
+    ("lowpole", "lowpole([hz])", "one pole lowpass (no param: ins signal, hz)"),
+    ("lowrez", "lowrez([hz], [q])", "resonant lowpass (missing params are inputs)"),
+    ("lowshelf", "lowshelf([hz], [q, gain])", "low shelf filter (missing params are inputs)"),
+    ("moog", "moog([hz], [q])", "moog ladder lowpass (missing params are inputs)"),
+    ("morph", "morph([hz, q, morph])", "morphing filter (no params: 4 ins)"),
+    ("notch", "notch([hz], [q])", "notch filter (missing params are inputs)"),
+    ("peak", "peak([hz], [q])", "peak filter (missing params are inputs)"),
+    ("resonator", "resonator([hz, bandwidth])", "resonator (no params: 3 ins)"),
+    // channels
+    ("sink", "sink()", "1 in, 0 outs. eats a channel"),
+    ("pass", "pass()", "1 in, 1 out. passes a channel"),
+    ("chan", "chan(float ...)", "stack of pass (non-zero) and sink (zero)"),
+    ("pan", "pan([pan])", "mono to stereo (no param: ins signal, pan)"),
+    ("join", "join(2...8)", "average n channels into 1"),
+    ("split", "split(2...8)", "copy 1 channel into n"),
+    ("reverse", "reverse(2...8)", "reverse the order of n channels"),
+    // envelopes
+    ("adsr", "adsr(a, d, s, r)", "adsr envelope (1 in, gate)"),
+    ("xd", "xd([rate])", "exponential decay"),
+    ("xD", "xD([time], [curvature])", "decay envelope (missing params are inputs)"),
+    ("ar", "ar([a, r], [a curve, r curve])", "attack release envelope"),
+    // other
+    ("tick", "tick()", "one sample delay"),
+    ("shift_reg", "shift_reg()", "2 ins (signal, trigger), 8 outs. shift register"),
+    ("snh", "snh()", "2 ins (signal, trigger), 1 out. sample and hold"),
+    ("meter", "meter(peak/rms, smoothing)", "level meter"),
+    ("chorus", "chorus(seed, separation, variation, mod hz)", "chorus"),
+    ("clip", "clip([min, max])", "clip to [-1...1] (or the given range)"),
+    ("declick", "declick([time])", "fade in (default 10ms)"),
+    ("delay", "delay(time)", "fixed delay (seconds)"),
+    ("hold", "hold([hz], variability)", "sample and hold at hz (no hz: 2 ins)"),
+    ("limiter", "limiter(attack, release)", "look ahead limiter"),
+    ("limiter_stereo", "limiter_stereo(attack, release)", "stereo look ahead limiter"),
+    ("reverb_stereo", "reverb_stereo(size, [time], [damping])", "stereo reverb"),
+    ("reverb_mono", "reverb_mono(size, [time], [damping])", "mono reverb"),
+    ("tap", "tap(min, max)", "variable delay (ins: signal, time)"),
+    ("tap_linear", "tap_linear(min, max)", "variable delay, linear interpolation"),
+    ("samp_delay", "samp_delay(max)", "delay in samples (ins: signal, delay)"),
+    ("pdhalf_bi", "pdhalf_bi()", "phase distortion (bipolar)"),
+    ("pdhalf_uni", "pdhalf_uni()", "phase distortion (unipolar)"),
+    // math
+    ("add", "add(float ...)", "add constants (up to 8 channels)"),
+    ("sub", "sub(float ...)", "subtract constants (up to 8 channels)"),
+    ("mul", "mul(float ...)", "multiply by constants (up to 8 channels)"),
+    ("div", "div(float ...)", "divide by constants (up to 8 channels)"),
+    ("rotate", "rotate(angle, gain)", "rotate a stereo signal"),
+    ("t", "t()", "time since the node started"),
+    ("rise", "rise()", "one sample trigger on rise"),
+    ("fall", "fall()", "one sample trigger on fall"),
+    (">", ">([float])", "greater than (no param: 2 ins)"),
+    ("<", "<([float])", "less than (no param: 2 ins)"),
+    ("==", "==([float])", "equal (no param: 2 ins)"),
+    ("!=", "!=([float])", "not equal (no param: 2 ins)"),
+    (">=", ">=([float])", "greater or equal (no param: 2 ins)"),
+    ("<=", "<=([float])", "less or equal (no param: 2 ins)"),
+    ("min", "min([float])", "minimum (no param: 2 ins)"),
+    ("max", "max([float])", "maximum (no param: 2 ins)"),
+    ("pow", "pow([float])", "power (no param: 2 ins)"),
+    ("mod", "mod([float])", "remainder (no param: 2 ins)"),
+    ("rem", "rem([float])", "remainder (no param: 2 ins)"),
+    ("log", "log([float])", "logarithm with base (no param: 2 ins)"),
+    ("bitand", "bitand([float])", "bitwise and (no param: 2 ins)"),
+    ("bitor", "bitor([float])", "bitwise or (no param: 2 ins)"),
+    ("bitxor", "bitxor([float])", "bitwise xor (no param: 2 ins)"),
+    ("shl", "shl([float])", "shift left (no param: 2 ins)"),
+    ("shr", "shr([float])", "shift right (no param: 2 ins)"),
+    ("lerp", "lerp([a, b])", "linear interpolation (no params: ins a, b, t)"),
+    ("lerp11", "lerp11([a, b])", "lerp with t in [-1...1]"),
+    ("delerp", "delerp([a, b])", "inverse lerp"),
+    ("delerp11", "delerp11([a, b])", "inverse lerp11"),
+    ("xerp", "xerp([a, b])", "exponential interpolation"),
+    ("xerp11", "xerp11([a, b])", "xerp with t in [-1...1]"),
+    ("dexerp", "dexerp([a, b])", "inverse xerp"),
+    ("dexerp11", "dexerp11([a, b])", "inverse xerp11"),
+    ("abs", "abs()", "absolute value"),
+    ("signum", "signum()", "sign"),
+    ("floor", "floor()", "round down"),
+    ("fract", "fract()", "fractional part"),
+    ("ceil", "ceil()", "round up"),
+    ("round", "round()", "round to nearest"),
+    ("sqrt", "sqrt()", "square root"),
+    ("exp", "exp()", "e^x"),
+    ("exp2", "exp2()", "2^x"),
+    ("exp10", "exp10()", "10^x"),
+    ("exp_m1", "exp_m1()", "e^x - 1"),
+    ("ln_1p", "ln_1p()", "ln(1 + x)"),
+    ("ln", "ln()", "natural logarithm"),
+    ("log2", "log2()", "base 2 logarithm"),
+    ("log10", "log10()", "base 10 logarithm"),
+    ("hypot", "hypot()", "2 ins. distance from origin to (x, y)"),
+    ("atan2", "atan2()", "2 ins. angle of (x, y)"),
+    ("sin", "sin()", "sine"),
+    ("cos", "cos()", "cosine"),
+    ("tan", "tan()", "tangent"),
+    ("asin", "asin()", "arcsine"),
+    ("acos", "acos()", "arccosine"),
+    ("atan", "atan()", "arctangent"),
+    ("sinh", "sinh()", "hyperbolic sine"),
+    ("cosh", "cosh()", "hyperbolic cosine"),
+    ("tanh", "tanh()", "hyperbolic tangent"),
+    ("asinh", "asinh()", "inverse hyperbolic sine"),
+    ("acosh", "acosh()", "inverse hyperbolic cosine"),
+    ("atanh", "atanh()", "inverse hyperbolic tangent"),
+    ("squared", "squared()", "x^2"),
+    ("cubed", "cubed()", "x^3"),
+    ("dissonance", "dissonance()", "2 ins. dissonance of two frequencies"),
+    ("dissonance_max", "dissonance_max()", "frequency of max dissonance"),
+    ("db_amp", "db_amp()", "decibels to amplitude"),
+    ("amp_db", "amp_db()", "amplitude to decibels"),
+    ("a_weight", "a_weight()", "a-weighted response at frequency"),
+    ("m_weight", "m_weight()", "m-weighted response at frequency"),
+    ("spline", "spline()", "5 ins. catmull-rom spline"),
+    ("spline_mono", "spline_mono()", "5 ins. monotonic spline"),
+    ("softsign", "softsign()", "x / (1 + |x|)"),
+    ("softexp", "softexp()", "soft exponential"),
+    ("softmix", "softmix()", "3 ins. soft mix of two values"),
+    ("smooth3", "smooth3()", "smooth cubic fade curve"),
+    ("smooth5", "smooth5()", "smooth 5th degree fade curve"),
+    ("smooth7", "smooth7()", "smooth 7th degree fade curve"),
+    ("smooth9", "smooth9()", "smooth 9th degree fade curve"),
+    ("uparc", "uparc()", "convex quarter circle fade curve"),
+    ("downarc", "downarc()", "concave quarter circle fade curve"),
+    ("sine_ease", "sine_ease()", "sine fade curve"),
+    ("sin_hz", "sin_hz()", "2 ins (hz, t). sine at time t"),
+    ("cos_hz", "cos_hz()", "2 ins (hz, t). cosine at time t"),
+    ("sqr_hz", "sqr_hz()", "2 ins (hz, t). square at time t"),
+    ("tri_hz", "tri_hz()", "2 ins (hz, t). triangle at time t"),
+    ("semitone_ratio", "semitone_ratio()", "semitones to frequency ratio"),
+    ("rnd1", "rnd1()", "hashed random number"),
+    ("rnd2", "rnd2()", "hashed random number"),
+    ("spline_noise", "spline_noise()", "spline noise"),
+    ("fractal_noise", "fractal_noise()", "fractal spline noise"),
+    ("wrap", "wrap(max) or wrap(min, max)", "wrap around a range"),
+    ("mirror", "mirror(min, max)", "fold into a range"),
+    ("pol", "pol()", "cartesian to polar"),
+    ("car", "car()", "polar to cartesian"),
+    ("deg", "deg()", "radians to degrees"),
+    ("rad", "rad()", "degrees to radians"),
+    ("recip", "recip()", "1 / x"),
+    ("rfft", "rfft(n, start)", "real fft, window size n (power of 2)"),
+    ("ifft", "ifft(n, start)", "inverse fft, window size n (power of 2)"),
+    ("normal", "normal()", "filter nan and inf"),
+];
+
+// what's being completed, and where it starts in the text
+#[derive(Debug, PartialEq)]
+pub enum Context<'a> {
+    Command(&'a str),
+    Property(&'a str),
+    Op(&'a str),
+}
+
+pub fn context(text: &str) -> Option<(Context<'_>, usize)> {
+    // only the last of the `;` separated commands
+    let line_start = text.rfind(';').map_or(0, |i| i + 1);
+    let line = &text[line_start..];
+    let words: Vec<(usize, &str)> = line
+        .split(' ')
+        .scan(0, |pos, w| {
+            let start = *pos;
+            *pos += w.len() + 1;
+            Some((start + line_start, w))
+        })
+        .filter(|(_, w)| !w.is_empty())
+        .collect();
+    let trailing = line.ends_with(' ');
+    // the word being typed (empty if we're after a space)
+    let (start, partial) =
+        if trailing || words.is_empty() { (text.len(), "") } else { *words.last().unwrap() };
+    let typed = words.len() - (!trailing && !words.is_empty()) as usize;
+    match (words.first().map(|w| w.1), typed) {
+        (Some(w), 0) if w.starts_with(':') => Some((Context::Command(partial), start)),
+        (Some(":set") | Some("set") | Some(":delta") | Some("delta"), 1) => {
+            Some((Context::Property(partial), start))
+        }
+        (Some(":set") | Some("set"), _) if words.get(1).is_some_and(|w| w.1 == "op") => {
+            // the whole op string can have spaces in it
+            let op_start = words.get(2).map_or(start, |w| w.0);
+            let op = &text[op_start..];
+            // `:set op 4v0 sine()` the first word is an id
+            let op_start = match op.split_once(' ') {
+                Some((id, _)) if typed >= 3 && is_id(id) => op_start + id.len() + 1,
+                _ => op_start,
+            };
+            Some((Context::Op(text[op_start..].trim_start()), op_start))
+        }
+        _ => None,
+    }
+}
+
+fn is_id(s: &str) -> bool {
+    s.split_once('v').is_some_and(|(i, g)| i.parse::<u32>().is_ok() && g.parse::<u32>().is_ok())
+}
+
+// how well does `query` match `name`? None if it doesn't
+// prefix > substring > scattered (subsequence), closer together is better
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    if name.starts_with(query) {
+        return Some(1000 - name.len() as i32);
+    }
+    if let Some(i) = name.find(query) {
+        return Some(500 - i as i32 - name.len() as i32);
+    }
+    let lower = name.to_lowercase();
+    let mut chars = lower.chars().enumerate();
+    let mut score = 100;
+    let mut last = None;
+    for q in query.to_lowercase().chars() {
+        let (i, _) = chars.find(|(_, c)| *c == q)?;
+        if let Some(last) = last {
+            score -= (i - last - 1) as i32;
+        }
+        last = Some(i);
+    }
+    Some(score - name.len() as i32)
+}
+
+// table entries matching the partial word, best first
+pub fn matches(ctx: &Context) -> Vec<&'static (&'static str, &'static str, &'static str)> {
+    let (table, partial) = match ctx {
+        Context::Command(p) => (COMMANDS, *p),
+        Context::Property(p) => (PROPERTIES, *p),
+        // only the name part of an op string
+        Context::Op(p) => (OPS, p.split('(').next().unwrap_or_default()),
+    };
+    let mut m: Vec<_> = table
+        .iter()
+        .filter_map(|entry| fuzzy_score(partial, entry.0).map(|s| (s, entry)))
+        .collect();
+    m.sort_by_key(|a| std::cmp::Reverse(a.0));
+    m.into_iter().map(|(_, entry)| entry).collect()
+}
+
+// what tab does: extend to the longest common prefix of the matches,
+// or replace with the best match if that doesn't get us anywhere
+pub fn complete(text: &str) -> Option<String> {
+    let (ctx, start) = context(text)?;
+    let partial = match ctx {
+        Context::Command(p) | Context::Property(p) => p,
+        // already typing parameters
+        Context::Op(p) if p.contains('(') => return None,
+        Context::Op(p) => p,
+    };
+    let m = matches(&ctx);
+    let best = m.first()?;
+    let prefixed: Vec<&str> = m.iter().map(|e| e.0).filter(|n| n.starts_with(partial)).collect();
+    let common = prefixed.iter().skip(1).fold(prefixed.first().copied().unwrap_or(""), |c, n| {
+        let len = c.chars().zip(n.chars()).take_while(|(a, b)| a == b).count();
+        &c[..len]
+    });
+    let completed = if prefixed.len() > 1 && common.len() > partial.len() {
+        common.to_string()
+    } else {
+        match ctx {
+            Context::Op(_) => {
+                let (name, usage, _) = best;
+                if usage.starts_with(&format!("{}(", name)) {
+                    // ops with nothing between the parentheses are done
+                    if usage.starts_with(&format!("{}()", name)) {
+                        format!("{}()", name)
+                    } else {
+                        format!("{}(", name)
+                    }
+                } else {
+                    name.to_string()
+                }
+            }
+            _ => format!("{} ", best.0),
+        }
+    };
+    Some(format!("{}{}", &text[..start], completed))
+}
@@ -8,7 +8,11 @@ use bevy::{
     sprite::Mesh2dHandle,
 };
 
-use fundsp::{net::Net, shared::Shared, slot::Slot};
+use fundsp::{net::Net, shared::Shared, slot::Slot, snoop::Snoop};
+
+use crate::nodes::{
+    AudioClock, AudioStatus, CpuTime, DspLoad, EventQueue, InputRing, NoteQueue, Probe, Transport,
+};
 
 use crossbeam_channel::{Receiver, Sender};
 
@@ -16,6 +20,15 @@ use cpal::Stream;
 
 use copypasta::ClipboardContext;
 
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 // -------------------- components --------------------
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
@@ -58,8 +71,13 @@ pub struct NetIns(pub Vec<Shared>);
 pub struct NetChannel(pub Sender<Net>, pub Receiver<Net>);
 
 #[derive(Component)]
+#[allow(dead_code)]
 pub struct FloatChannel(pub Sender<f32>, pub Receiver<f32>);
 
+// note events (pitch, velocity, gate) for poly()
+#[derive(Component)]
+pub struct NoteChannel(pub NoteQueue);
+
 #[derive(Component, Reflect)]
 #[reflect(Component, MapEntities)]
 pub struct Holes(pub Vec<Entity>);
@@ -153,12 +171,62 @@ impl MapEntities for BlackHole {
 #[derive(Component)]
 pub struct CommandText;
 
+// matches shown above the command line while typing
+#[derive(Component)]
+pub struct CompletionText;
+
+// the underscore under the command line cursor
+#[derive(Component)]
+pub struct CommandCursorText;
+
 #[derive(Component)]
 pub struct InfoText(pub Entity);
 
+// a note on the canvas. it's not a circle (no op, order, or holes) so it's never
+// processed. its color is a Col, its translation is the top left of the text,
+// and its scale is the font size (so it's resized like a radius)
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Comment(pub String);
+
+#[derive(Component)]
+pub struct CommentText(pub Entity);
+
+// the minimap overlay, the rectangle showing what the camera sees,
+// and the dot of each circle (holding the circle)
+#[derive(Component)]
+pub struct MinimapPanel;
+
+#[derive(Component)]
+pub struct MinimapView;
+
+#[derive(Component)]
+pub struct MinimapDot(pub Entity);
+
+// can't be edited from the selection (drags, set, delete) or by id.
+// still plays (a locked butt or toggle still works)
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Locked;
+
+// the camera of the main window (the one everything is edited in)
+#[derive(Component)]
+pub struct MainCamera;
+
+// the camera of another window showing the same patch (see :window)
+#[derive(Component)]
+pub struct ViewCamera;
+
 #[derive(Component)]
 pub struct OpNum(pub u16);
 
+// the op string didn't parse (holds the error message)
+#[derive(Component)]
+pub struct BadOp(pub String);
+
+#[derive(Component)]
+pub struct BadOpMarker(pub Entity);
+
 // -------------------- states --------------------
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum Mode {
@@ -185,6 +253,106 @@ pub struct CursorInfo {
     pub d: Vec2,
 }
 
+// a finger on a touchscreen. where it went down (i), where it is (f), and how
+// hard it's pressing (1 if the screen can't tell)
+#[derive(Clone, Copy, Default)]
+pub struct Pointer {
+    pub id: u64,
+    pub i: Vec2,
+    pub f: Vec2,
+    pub pressure: f32,
+}
+
+// the fingers that are down (by id), and the ones that went down or up this frame
+#[derive(Resource, Default)]
+pub struct Pointers {
+    pub down: Vec<Pointer>,
+    pub pressed: Vec<Pointer>,
+    pub released: Vec<Pointer>,
+}
+
+// tempo, position, and play state. shared with the audio thread
+#[derive(Resource, Default)]
+pub struct TransportRes(pub Transport);
+
+// events from process are timestamped with the frame time, and applied on
+// the audio thread that long after it. trading a constant delay for jitter
+#[derive(Resource)]
+pub struct Timing {
+    pub clock: AudioClock,
+    pub latency: Duration,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing { clock: AudioClock::default(), latency: Duration::from_millis(20) }
+    }
+}
+
+// changes to a var() or trig(), see TimedVar. and the last value pushed
+#[derive(Component)]
+pub struct VarEvents(pub EventQueue, pub f32);
+
+// how busy the audio thread is. when enabled, every circle's net is
+// wrapped in a Probe so the time can be split between circles
+#[derive(Resource, Default)]
+pub struct Profiler {
+    pub enabled: bool,
+    pub dsp: DspLoad,
+    // fraction of the available time the output callback spent working
+    pub load: f32,
+    // time spent in each circle's own nodes, and the fraction of the available time that is
+    pub circles: HashMap<Entity, (CpuTime, f32)>,
+    // switches off the probes of this run when profiling stops
+    pub on: Arc<AtomicBool>,
+}
+
+impl Profiler {
+    pub fn start(&mut self) {
+        self.enabled = true;
+        self.circles.clear();
+        self.on = Arc::new(AtomicBool::new(true));
+    }
+
+    // the probes stay in the nets (passing through) until they're rebuilt
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        self.circles.clear();
+        self.on.store(false, Ordering::Relaxed);
+    }
+
+    // wrap a circle's net in a Probe timing it
+    pub fn probe(&mut self, e: Entity, net: &mut Net) {
+        let time = self.circles.entry(e).or_default().0.clone();
+        let x = std::mem::replace(net, Net::new(0, 0));
+        *net = Net::wrap(Box::new(Probe::new(x, time, self.on.clone())));
+    }
+}
+
+// underruns, dropped input, and stream errors. see report_audio_status
+#[derive(Resource, Default)]
+pub struct AudioStatusRes(pub AudioStatus);
+
+// the heat overlay of a circle (while profiling)
+#[derive(Component)]
+pub struct Heat(pub Entity);
+
+// what a scope() or spectrum() circle hears, and how much of it to show
+#[derive(Component)]
+pub struct Scope {
+    pub snoop: Snoop,
+    pub size: usize,
+    pub spectrum: bool,
+    // samples received when the trace was last drawn
+    pub drawn: u64,
+}
+
+// the line drawn inside a scope
+#[derive(Component)]
+pub struct Trace(pub Entity);
+
+/// crossfades the output to new nets. the ones it's done with come back
+/// here to be dropped, so the audio thread never frees them
 #[derive(Resource)]
 pub struct SlotRes(pub Slot);
 
@@ -269,19 +437,52 @@ pub struct ArrowHandle(pub Mesh2dHandle);
 #[derive(Resource)]
 pub struct ConnectionMat(pub Handle<ColorMaterial>);
 
+// how connections are drawn
+#[derive(Resource, Reflect, Default, PartialEq, Clone, Copy)]
+#[reflect(Resource)]
+pub enum ConnectionStyle {
+    #[default]
+    Straight,
+    Curve,
+    Route,
+}
+
+// colors for connections by the link type they read (the black hole's).
+// ones that aren't here use the connection color
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct LinkColors(pub HashMap<i8, Hsla>);
+
+// a connection that just carried something. goes from 1 to 0
+#[derive(Component)]
+pub struct Flash(pub f32);
+
 #[derive(Resource)]
 pub struct ClickedOnSpace(pub bool);
 
+// just holding on to them so they don't stop
+#[allow(dead_code)]
 pub struct OutStream(pub Stream);
 
+#[allow(dead_code)]
 pub struct InStream(pub Stream);
 
 #[derive(Resource, Reflect, Default)]
 #[reflect(Resource)]
 pub struct NodeLimit(pub usize);
 
-#[derive(Resource)]
-pub struct InputReceivers(pub Receiver<f32>, pub Receiver<f32>);
+// grid size for snapping translate and radius drags (0 is off)
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct Grid(pub f32);
+
+// the lines of the grid, and the material they use
+#[derive(Component)]
+pub struct GridLines;
+
+// from the input stream to the output stream. see InputRing
+#[derive(Resource, Default)]
+pub struct InputRingRes(pub InputRing);
 
 #[derive(Resource)]
 pub struct PasteChannel(pub (Sender<String>, Receiver<String>));
@@ -290,6 +491,49 @@ pub struct PasteChannel(pub (Sender<String>, Receiver<String>));
 #[reflect(Resource)]
 pub struct ShowInfoText(pub bool, pub bool); // (show text, show id)
 
+// cursor position in the command line (in chars from the end)
+#[derive(Resource, Default)]
+pub struct CommandCursor(pub usize);
+
+#[derive(Resource, Default)]
+pub struct CommandHistory {
+    pub entries: Vec<String>,
+    // the entry we're looking at (when pressing up/down)
+    pub index: Option<usize>,
+    // what was typed before going through history, only entries starting with it are shown
+    pub prefix: String,
+}
+
+// commands from `:source` waiting to run (one per frame)
+#[derive(Resource, Default)]
+pub struct PendingCommands(pub VecDeque<String>);
+
+// the last `/pattern` search, `n` and `N` go through its matches
+#[derive(Resource, Default)]
+pub struct SearchResults {
+    pub pattern: String,
+    pub matches: Vec<Entity>,
+    pub index: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct Minimap {
+    pub show: bool,
+    // the part of the world it shows (a square)
+    pub area: Rect,
+}
+
+// a camera position to go back to (`:mark a` then `'a`)
+#[derive(Reflect, Clone, Copy, Default)]
+pub struct Bookmark {
+    pub translation: Vec2,
+    pub scale: f32,
+}
+
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct Bookmarks(pub HashMap<String, Bookmark>);
+
 // -------------------- events --------------------
 #[derive(Event, Default)]
 pub struct OrderChange;
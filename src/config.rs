@@ -0,0 +1,436 @@
+// startup settings from `quartz.toml` in the config dir (or the file given with
+// `--config {path}`). every key is optional, missing ones keep their defaults
+
+use bevy::{color::Hsla, prelude::*};
+use std::path::PathBuf;
+use toml_edit::{DocumentMut, Item};
+
+use crate::functions::config_dir;
+
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Config {
+    /// a scene file opened at startup
+    pub scene: Option<String>,
+    pub node_limit: usize,
+    pub text_size: f32,
+    pub draw_verts: usize,
+    pub connection_width: f32,
+    pub osc: OscConfig,
+    pub colors: Colors,
+    pub bloom: Bloom,
+    /// redraw rates (frames per second) when the window is focused / unfocused
+    pub update: (f64, f64),
+    pub audio: AudioConfig,
+    pub keys: Vec<KeyBinding>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OscConfig {
+    /// where `osc_s` sends
+    pub host: String,
+    pub port: u16,
+    /// start listening on this port (otherwise it's set by the `osc` op)
+    pub listen: Option<u16>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Colors {
+    pub clear: Hsla,
+    pub draw: Hsla,
+    pub highlight: Hsla,
+    pub connection: Hsla,
+    pub command: Hsla,
+    pub indicator: Hsla,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bloom {
+    pub intensity: f32,
+    pub low_frequency_boost: f32,
+    pub low_frequency_boost_curvature: f32,
+}
+
+/// what the startup devices are (like `:od` and `:id`). unset means the default
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioConfig {
+    pub host: Option<usize>,
+    pub out_device: Option<usize>,
+    pub in_device: Option<usize>,
+    pub sample_rate: Option<u32>,
+    pub buffer: Option<u32>,
+}
+
+/// a key (with modifiers) that runs a command
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub command: String,
+}
+
+impl KeyBinding {
+    pub fn just_pressed(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        keys.just_pressed(self.key)
+            && self.ctrl == keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && self.shift == keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+            && self.alt == keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+    }
+
+    fn id(&self) -> (KeyCode, bool, bool, bool) {
+        (self.key, self.ctrl, self.shift, self.alt)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            scene: None,
+            node_limit: 500,
+            text_size: 0.1,
+            draw_verts: 4,
+            connection_width: 4.,
+            osc: OscConfig { host: "127.0.0.1".into(), port: 1729, listen: None },
+            colors: Colors {
+                clear: Hsla::new(0., 0., 0., 1.),
+                draw: Hsla::new(330., 1., 0.5, 1.),
+                highlight: Hsla::new(0.0, 1.0, 0.5, 1.),
+                connection: Hsla::new(0., 1., 1., 0.7),
+                command: Hsla::new(0., 0., 0.7, 1.),
+                indicator: Hsla::new(0., 1., 0.5, 0.3),
+            },
+            bloom: Bloom {
+                intensity: 0.2,
+                low_frequency_boost: 0.6,
+                low_frequency_boost_curvature: 0.4,
+            },
+            update: (60., 30.),
+            audio: AudioConfig::default(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// read the file given with `--config`, or the one in the config dir.
+    /// returns the defaults (and why) if it can't be read
+    pub fn load(args: impl IntoIterator<Item = String>) -> (Config, Vec<String>) {
+        let mut args = args.into_iter();
+        let mut path = None;
+        while let Some(arg) = args.next() {
+            if arg == "--config" || arg == "-c" {
+                path = args.next().map(PathBuf::from);
+            } else if let Some(p) = arg.strip_prefix("--config=") {
+                path = Some(PathBuf::from(p));
+            }
+        }
+        let given = path.is_some();
+        let Some(path) = path.or_else(|| config_dir().map(|d| d.join("quartz.toml"))) else {
+            return (Config::default(), Vec::new());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(s) => Config::parse(&s),
+            // not having one is fine, unless it was asked for
+            Err(e) if given => (Config::default(), vec![format!("{}: {}", path.display(), e)]),
+            Err(_) => (Config::default(), Vec::new()),
+        }
+    }
+
+    /// the settings in `s`, and warnings for anything that's wrong in it
+    /// (those keys are left at their defaults)
+    pub fn parse(s: &str) -> (Config, Vec<String>) {
+        let mut c = Config::default();
+        let mut w = Vec::new();
+        let doc = match s.parse::<DocumentMut>() {
+            Ok(doc) => doc,
+            Err(e) => {
+                w.push(e.to_string().trim().to_string());
+                return (c, w);
+            }
+        };
+        for (key, item) in doc.iter() {
+            match key {
+                "scene" => set(&mut c.scene, key, string(item).map(Some), "a path", &mut w),
+                "node_limit" => set(&mut c.node_limit, key, uint(item), "an integer", &mut w),
+                "text_size" => set(&mut c.text_size, key, float(item), "a number", &mut w),
+                "draw_verts" => {
+                    let n = uint(item).filter(|n| (3..=64).contains(n));
+                    set(&mut c.draw_verts, key, n, "an integer in 3..=64", &mut w);
+                }
+                "connection_width" => {
+                    set(&mut c.connection_width, key, float(item), "a number", &mut w)
+                }
+                "osc" => {
+                    for (k, item) in table(key, item, &mut w) {
+                        let key = &format!("osc.{}", k);
+                        let osc = &mut c.osc;
+                        match k {
+                            "host" => set(&mut osc.host, key, string(item), "a string", &mut w),
+                            "port" => set(&mut osc.port, key, port(item), "a port", &mut w),
+                            "listen" => {
+                                set(&mut osc.listen, key, port(item).map(Some), "a port", &mut w)
+                            }
+                            _ => w.push(format!("unknown key: {}", key)),
+                        }
+                    }
+                }
+                "colors" => {
+                    for (k, item) in table(key, item, &mut w) {
+                        let key = &format!("colors.{}", k);
+                        let colors = &mut c.colors;
+                        let slot = match k {
+                            "clear" => &mut colors.clear,
+                            "draw" => &mut colors.draw,
+                            "highlight" => &mut colors.highlight,
+                            "connection" => &mut colors.connection,
+                            "command" => &mut colors.command,
+                            "indicator" => &mut colors.indicator,
+                            _ => {
+                                w.push(format!("unknown key: {}", key));
+                                continue;
+                            }
+                        };
+                        set(slot, key, color(item), "[hue, saturation, lightness, alpha]", &mut w);
+                    }
+                }
+                "bloom" => {
+                    for (k, item) in table(key, item, &mut w) {
+                        let key = &format!("bloom.{}", k);
+                        let bloom = &mut c.bloom;
+                        let slot = match k {
+                            "intensity" => &mut bloom.intensity,
+                            "low_frequency_boost" => &mut bloom.low_frequency_boost,
+                            "low_frequency_boost_curvature" => {
+                                &mut bloom.low_frequency_boost_curvature
+                            }
+                            _ => {
+                                w.push(format!("unknown key: {}", key));
+                                continue;
+                            }
+                        };
+                        set(slot, key, float(item), "a number", &mut w);
+                    }
+                }
+                "update" => {
+                    for (k, item) in table(key, item, &mut w) {
+                        let key = &format!("update.{}", k);
+                        let rate = float(item).filter(|r| *r > 0.).map(f64::from);
+                        match k {
+                            "focused" => set(&mut c.update.0, key, rate, "a rate > 0", &mut w),
+                            "unfocused" => set(&mut c.update.1, key, rate, "a rate > 0", &mut w),
+                            _ => w.push(format!("unknown key: {}", key)),
+                        }
+                    }
+                }
+                "audio" => {
+                    for (k, item) in table(key, item, &mut w) {
+                        let key = &format!("audio.{}", k);
+                        let audio = &mut c.audio;
+                        let n = uint(item).map(Some);
+                        let n32 = uint(item).and_then(|n| u32::try_from(n).ok()).map(Some);
+                        match k {
+                            "host" => set(&mut audio.host, key, n, "an index", &mut w),
+                            "out_device" => set(&mut audio.out_device, key, n, "an index", &mut w),
+                            "in_device" => set(&mut audio.in_device, key, n, "an index", &mut w),
+                            "sample_rate" => {
+                                set(&mut audio.sample_rate, key, n32, "an integer", &mut w)
+                            }
+                            "buffer" => set(&mut audio.buffer, key, n32, "an integer", &mut w),
+                            _ => w.push(format!("unknown key: {}", key)),
+                        }
+                    }
+                }
+                "keys" => {
+                    for (k, item) in table(key, item, &mut w) {
+                        let key = &format!("keys.\"{}\"", k);
+                        match (str_to_binding(k), string(item)) {
+                            (Some(mut binding), Some(command)) => {
+                                binding.command = command;
+                                c.keys.retain(|b| b.id() != binding.id());
+                                c.keys.push(binding);
+                            }
+                            (None, _) => w.push(format!("{}: unknown key", key)),
+                            (_, None) => w.push(format!("{}: expected a command", key)),
+                        }
+                    }
+                }
+                _ => w.push(format!("unknown key: {}", key)),
+            }
+        }
+        (c, w)
+    }
+}
+
+fn set<T>(slot: &mut T, key: &str, value: Option<T>, expected: &str, warnings: &mut Vec<String>) {
+    match value {
+        Some(value) => *slot = value,
+        None => warnings.push(format!("{}: expected {}", key, expected)),
+    }
+}
+
+fn table<'a>(
+    key: &str,
+    item: &'a Item,
+    warnings: &mut Vec<String>,
+) -> Box<dyn Iterator<Item = (&'a str, &'a Item)> + 'a> {
+    match item.as_table_like() {
+        Some(t) => t.iter(),
+        None => {
+            warnings.push(format!("{}: expected a table", key));
+            Box::new(std::iter::empty())
+        }
+    }
+}
+
+fn float(item: &Item) -> Option<f32> {
+    item.as_float().or_else(|| item.as_integer().map(|n| n as f64)).map(|n| n as f32)
+}
+
+fn uint(item: &Item) -> Option<usize> {
+    item.as_integer().and_then(|n| usize::try_from(n).ok())
+}
+
+fn port(item: &Item) -> Option<u16> {
+    item.as_integer().and_then(|n| u16::try_from(n).ok())
+}
+
+fn string(item: &Item) -> Option<String> {
+    item.as_str().map(String::from)
+}
+
+fn color(item: &Item) -> Option<Hsla> {
+    let array = item.as_array()?;
+    let c: Vec<f32> = array
+        .iter()
+        .map(|v| v.as_float().or_else(|| v.as_integer().map(|n| n as f64)).map(|n| n as f32))
+        .collect::<Option<_>>()?;
+    match c[..] {
+        [h, s, l] => Some(Hsla::new(h, s, l, 1.)),
+        [h, s, l, a] => Some(Hsla::new(h, s, l, a)),
+        _ => None,
+    }
+}
+
+/// a key like `F5`, `ctrl+s`, or `ctrl+shift+left` (with an empty command)
+pub fn str_to_binding(s: &str) -> Option<KeyBinding> {
+    let mut binding =
+        KeyBinding { key: KeyCode::F35, ctrl: false, shift: false, alt: false, command: "".into() };
+    let mut parts: Vec<&str> = s.split('+').collect();
+    let key = parts.pop()?;
+    for modifier in parts {
+        match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" => binding.ctrl = true,
+            "shift" => binding.shift = true,
+            "alt" => binding.alt = true,
+            _ => return None,
+        }
+    }
+    binding.key = str_to_key(key)?;
+    Some(binding)
+}
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+];
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+const FN_KEYS: [KeyCode; 12] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+// keys by their (case insensitive) names: letters, digits, `f1`..`f12`, some
+// named keys, and the punctuation on a us layout
+pub fn str_to_key(s: &str) -> Option<KeyCode> {
+    let s = s.to_lowercase();
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_lowercase() {
+            return Some(LETTERS[(c as u8 - b'a') as usize]);
+        }
+        if c.is_ascii_digit() {
+            return Some(DIGITS[(c as u8 - b'0') as usize]);
+        }
+    }
+    if let Some(n) = s.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return FN_KEYS.get(n.checked_sub(1)?).copied();
+    }
+    let key = match s.as_str() {
+        "space" => KeyCode::Space,
+        "tab" => KeyCode::Tab,
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Escape,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::ArrowUp,
+        "down" => KeyCode::ArrowDown,
+        "left" => KeyCode::ArrowLeft,
+        "right" => KeyCode::ArrowRight,
+        "-" => KeyCode::Minus,
+        "=" => KeyCode::Equal,
+        "[" => KeyCode::BracketLeft,
+        "]" => KeyCode::BracketRight,
+        ";" => KeyCode::Semicolon,
+        "'" => KeyCode::Quote,
+        "," => KeyCode::Comma,
+        "." => KeyCode::Period,
+        "/" => KeyCode::Slash,
+        "\\" => KeyCode::Backslash,
+        "`" => KeyCode::Backquote,
+        _ => return None,
+    };
+    Some(key)
+}
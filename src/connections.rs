@@ -1,17 +1,22 @@
 use bevy::{
     prelude::*,
-    render::view::{RenderLayers, VisibleEntities},
-    sprite::WithMesh2d,
+    render::{
+        mesh::PrimitiveTopology,
+        render_asset::RenderAssetUsages,
+        view::{RenderLayers, VisibleEntities},
+    },
+    sprite::{Mesh2dHandle, WithMesh2d},
+    utils::HashMap,
 };
 
-use crate::components::*;
+use crate::{components::*, layout::*};
 
 pub fn connect(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     mut commands: Commands,
     trans_query: Query<&Transform>,
     vertices_query: Query<&Vertices>,
-    visible: Query<&VisibleEntities>,
+    visible: Query<&VisibleEntities, With<MainCamera>>,
     cursor: Res<CursorInfo>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
@@ -142,7 +147,7 @@ pub fn connect(
 pub fn target(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     circle_trans_query: Query<&Transform, With<Vertices>>,
-    visible: Query<&VisibleEntities>,
+    visible: Query<&VisibleEntities, With<MainCamera>>,
     cursor: Res<CursorInfo>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut targets_query: Query<&mut Targets>,
@@ -180,28 +185,55 @@ pub fn target(
 }
 
 pub fn update_connection_arrows(
-    bh_query: Query<(Entity, &BlackHole), (Changed<Transform>, With<Vertices>)>,
-    wh_query: Query<(Entity, &WhiteHole), (Changed<Transform>, With<Vertices>)>,
+    bh_query: Query<&BlackHole, (Changed<Transform>, With<Vertices>)>,
+    wh_query: Query<Entity, (Changed<Transform>, With<WhiteHole>, With<Vertices>)>,
+    white_holes: Query<(Entity, &WhiteHole)>,
+    black_holes: Query<&BlackHole>,
+    moved_circles: Query<(), (Changed<Transform>, With<Order>, With<Vertices>)>,
+    circles: Query<(Entity, &Transform), (With<Order>, With<Vertices>)>,
     trans_query: Query<&Transform, With<Vertices>>,
-    mut arrow_trans: Query<&mut Transform, Without<Vertices>>,
+    mut arrows: Query<(&mut Transform, &mut Mesh2dHandle), Without<Vertices>>,
     arrow_query: Query<&ConnectionArrow>,
     connection_width: Res<ConnectionWidth>,
+    style: Res<ConnectionStyle>,
+    arrow_handle: Res<ArrowHandle>,
+    mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    for (id, bh) in bh_query.iter() {
-        if wh_query.contains(bh.wh) {
-            continue;
-        }
-        if let (Ok(bh_t), Ok(wh_t)) = (trans_query.get(id), trans_query.get(bh.wh)) {
-            let bh_radius = bh_t.scale.x;
-            let wh_radius = wh_t.scale.x;
-            let bh_trans = bh_t.translation.xy();
-            let wh_trans = wh_t.translation.xy();
-            if let Ok(arrow_id) = arrow_query.get(bh.wh) {
+    // routes go around every circle, so they all change when any of them moves
+    let all = style.is_changed()
+        || connection_width.is_changed()
+        || (*style == ConnectionStyle::Route && !moved_circles.is_empty());
+    let mut changed: Vec<Entity> = if all {
+        white_holes.iter().map(|(e, _)| e).collect()
+    } else {
+        wh_query.iter().chain(bh_query.iter().map(|bh| bh.wh)).collect()
+    };
+    changed.sort_unstable();
+    changed.dedup();
+    let obstacles: Vec<(Entity, Rect)> = if *style == ConnectionStyle::Route {
+        circles
+            .iter()
+            .map(|(e, t)| (e, Rect::from_center_half_size(t.translation.xy(), t.scale.xy().abs())))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    for id in changed {
+        let Ok((_, wh)) = white_holes.get(id) else { continue };
+        let (Ok(bh_t), Ok(wh_t)) = (trans_query.get(wh.bh), trans_query.get(id)) else { continue };
+        let Ok(arrow_id) = arrow_query.get(id) else { continue };
+        let Ok((mut arrow_t, mut mesh)) = arrows.get_mut(arrow_id.0) else { continue };
+        let bh_radius = bh_t.scale.x;
+        let wh_radius = wh_t.scale.x;
+        let bh_trans = bh_t.translation.xy();
+        let wh_trans = wh_t.translation.xy();
+        let path = match *style {
+            ConnectionStyle::Straight => {
                 let perp = (bh_trans - wh_trans).perp();
                 let norm = (wh_trans - bh_trans).normalize_or_zero();
                 let i = wh_trans - wh_radius * norm;
                 let f = bh_trans + bh_radius * norm;
-                *arrow_trans.get_mut(arrow_id.0).unwrap() = Transform {
+                *arrow_t = Transform {
                     translation: ((i + f) / 2.).extend(100.),
                     scale: Vec3::new(
                         connection_width.0,
@@ -210,32 +242,91 @@ pub fn update_connection_arrows(
                     ),
                     rotation: Quat::from_rotation_z(perp.to_angle()),
                 };
+                if *mesh != arrow_handle.0 {
+                    *mesh = arrow_handle.0.clone();
+                }
+                continue;
             }
+            ConnectionStyle::Curve => curve(bh_trans, bh_radius, wh_trans, wh_radius),
+            ConnectionStyle::Route => {
+                // not through the circles it connects
+                let wh_parent = black_holes.get(wh.bh).map(|bh| bh.wh_parent).ok();
+                let around: Vec<Rect> = obstacles
+                    .iter()
+                    .filter(|(e, _)| *e != wh.bh_parent && Some(*e) != wh_parent)
+                    .map(|(_, r)| *r)
+                    .collect();
+                let margin = 2. * bh_radius.max(wh_radius);
+                let mut path = route(bh_trans, wh_trans, &around, margin);
+                trim(&mut path, bh_radius, wh_radius);
+                path
+            }
+        };
+        // its own mesh, in world space
+        let points = stroke(&path, connection_width.0, connection_width.0 / 4.);
+        *arrow_t = Transform::from_translation(Vec3::Z * 100.);
+        if *mesh == arrow_handle.0 {
+            let new = Mesh::new(PrimitiveTopology::TriangleStrip, RenderAssetUsages::default())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, points);
+            *mesh = meshes.add(new).into();
+        } else if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, points);
         }
     }
-    for (id, wh) in wh_query.iter() {
-        if let (Ok(bh_t), Ok(wh_t)) = (trans_query.get(wh.bh), trans_query.get(id)) {
-            let bh_radius = bh_t.scale.x;
-            let wh_radius = wh_t.scale.x;
-            let bh_trans = bh_t.translation.xy();
-            let wh_trans = wh_t.translation.xy();
-            if let Ok(arrow_id) = arrow_query.get(id) {
-                let perp = (bh_trans - wh_trans).perp();
-                let norm = (wh_trans - bh_trans).normalize_or_zero();
-                let i = wh_trans - wh_radius * norm;
-                let f = bh_trans + bh_radius * norm;
-                *arrow_trans.get_mut(arrow_id.0).unwrap() = Transform {
-                    translation: ((i + f) / 2.).extend(100.),
-                    scale: Vec3::new(
-                        connection_width.0,
-                        wh_trans.distance(bh_trans) - (bh_radius + wh_radius),
-                        1.,
-                    ),
-                    rotation: Quat::from_rotation_z(perp.to_angle()),
-                };
+}
+
+// color connections by their link type, and light up the ones that just carried
+// something (see Flash)
+pub fn color_connections(
+    mut commands: Commands,
+    white_holes: Query<(&WhiteHole, &ConnectionArrow)>,
+    mut arrows: Query<(&mut Handle<ColorMaterial>, Option<&mut Flash>)>,
+    link_colors: Res<LinkColors>,
+    connection_color: Res<ConnectionColor>,
+    connection_mat: Res<ConnectionMat>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+    mut link_mats: Local<HashMap<i8, Handle<ColorMaterial>>>,
+    mut flash_mats: Local<HashMap<Entity, Handle<ColorMaterial>>>,
+) {
+    if link_colors.is_changed() {
+        link_mats.retain(|lt, _| link_colors.0.contains_key(lt));
+        for (lt, color) in &link_colors.0 {
+            if let Some(mat) = link_mats.get(lt).and_then(|h| materials.get_mut(h)) {
+                mat.color = (*color).into();
+            } else {
+                link_mats.insert(*lt, materials.add(ColorMaterial::from_color(*color)));
+            }
+        }
+    }
+    for (wh, arrow) in white_holes.iter() {
+        let Ok((mut mat, flash)) = arrows.get_mut(arrow.0) else { continue };
+        let lt = wh.link_types.0;
+        let color = link_colors.0.get(&lt).copied().unwrap_or(connection_color.0);
+        if let Some(mut flash) = flash {
+            let own = flash_mats
+                .entry(arrow.0)
+                .or_insert_with(|| materials.add(ColorMaterial::from_color(color)));
+            if let Some(own_mat) = materials.get_mut(&*own) {
+                let lightness = color.lightness + (1. - color.lightness) * flash.0;
+                own_mat.color = color.with_lightness(lightness).into();
+            }
+            if *mat != *own {
+                *mat = own.clone();
+            }
+            flash.0 -= time.delta_seconds() * 4.;
+            if flash.0 > 0. {
+                continue;
             }
+            commands.entity(arrow.0).remove::<Flash>();
+            flash_mats.remove(&arrow.0);
+        }
+        let base = link_mats.get(&lt).unwrap_or(&connection_mat.0);
+        if *mat != *base {
+            *mat = base.clone();
         }
     }
+    flash_mats.retain(|e, _| arrows.contains(*e));
 }
 
 pub fn connect_targets(
@@ -252,11 +343,14 @@ pub fn connect_targets(
     white_hole_query: Query<&WhiteHole>,
 ) {
     for e in connect_command.read() {
-        let targets = &targets_query.get(e.0).unwrap().0;
+        // the connecting circle might be gone by now
+        let (Ok(targets), Ok(arr)) = (targets_query.get(e.0), arr_query.get(e.0)) else {
+            continue;
+        };
+        let (targets, arr) = (&targets.0, &arr.0);
         if targets.is_empty() {
             continue;
         }
-        let arr = &arr_query.get(e.0).unwrap().0;
         let lt = if let Some(a) = arr.get(0..2) { (a[0] as i8, a[1] as i8) } else { (0, 0) };
         let mut white_holes = Vec::new();
         for pair in targets.windows(2) {
@@ -265,13 +359,18 @@ pub fn connect_targets(
             if src == snk {
                 continue;
             }
+            // only connect circles that still exist
+            let (Ok((_, src_t, src_v)), Ok((_, snk_t, snk_v))) = (query.get(src), query.get(snk))
+            else {
+                continue;
+            };
             // get translation, radius, and vertices
-            let src_trans = query.get(src).unwrap().1.translation;
-            let snk_trans = query.get(snk).unwrap().1.translation;
-            let src_radius = query.get(src).unwrap().1.scale.x;
-            let snk_radius = query.get(snk).unwrap().1.scale.x;
-            let src_verts = query.get(src).unwrap().2 .0;
-            let snk_verts = query.get(snk).unwrap().2 .0;
+            let src_trans = src_t.translation;
+            let snk_trans = snk_t.translation;
+            let src_radius = src_t.scale.x;
+            let snk_radius = snk_t.scale.x;
+            let src_verts = src_v.0;
+            let snk_verts = snk_v.0;
             let bh_radius = src_radius * 0.15;
             let wh_radius = snk_radius * 0.15;
 
@@ -340,10 +439,13 @@ pub fn connect_targets(
 
             white_holes.push(white_hole);
         }
-        for hole in &holes_query.get(e.0).unwrap().0 {
+        let Ok(holes) = holes_query.get(e.0) else { continue };
+        for hole in &holes.0 {
             if let Ok(wh) = white_hole_query.get(*hole) {
                 if wh.link_types == (-14, 2) {
-                    targets_query.get_mut(wh.bh_parent).unwrap().0 = white_holes;
+                    if let Ok(mut targets) = targets_query.get_mut(wh.bh_parent) {
+                        targets.0 = white_holes;
+                    }
                     break;
                 }
             }
@@ -1,11 +1,15 @@
-use bevy::prelude::*;
+use bevy::{
+    input::touch::{ForceTouch, Touch},
+    prelude::*,
+    window::PrimaryWindow,
+};
 
 use crate::components::*;
 
 pub fn update_cursor_info(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     mut cursor: ResMut<CursorInfo>,
     mut last_pos: Local<Vec2>,
 ) {
@@ -32,3 +36,36 @@ pub fn update_cursor_info(
         *last_pos = -cursor.f; // so on the pressed frame we don't get a delta
     }
 }
+
+// every touch as a pointer (in world coordinates)
+pub fn update_pointers(
+    touches: Res<Touches>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut pointers: ResMut<Pointers>,
+) {
+    let (cam, cam_transform) = camera_query.single();
+    let pointer = |touch: &Touch| {
+        let i = cam.viewport_to_world_2d(cam_transform, touch.start_position())?;
+        let f = cam.viewport_to_world_2d(cam_transform, touch.position())?;
+        Some(Pointer { id: touch.id(), i, f, pressure: pressure(touch.force()) })
+    };
+    pointers.down = touches.iter().filter_map(pointer).collect();
+    pointers.down.sort_by_key(|p| p.id);
+    pointers.pressed = touches.iter_just_pressed().filter_map(pointer).collect();
+    pointers.released = touches
+        .iter_just_released()
+        .chain(touches.iter_just_canceled())
+        .filter_map(pointer)
+        .collect();
+}
+
+// from 0 to 1
+fn pressure(force: Option<ForceTouch>) -> f32 {
+    match force {
+        Some(ForceTouch::Calibrated { force, max_possible_force, .. }) => {
+            (force / max_possible_force) as f32
+        }
+        Some(ForceTouch::Normalized(force)) => force as f32,
+        None => 1.,
+    }
+}
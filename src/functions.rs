@@ -1,6 +1,9 @@
 use crate::nodes::*;
 use fundsp::hacker32::*;
-use std::num::Wrapping;
+use std::{
+    num::Wrapping,
+    path::{Path, PathBuf},
+};
 
 pub fn str_to_lt(s: &str) -> i8 {
     if let Ok(n) = s.parse::<i8>() {
@@ -108,330 +111,362 @@ pub fn parse_with_constants(s: &str) -> Result<f32, &str> {
     }
 }
 
-pub fn str_to_net(op: &str) -> Net {
+#[derive(Debug, PartialEq)]
+pub enum OpError {
+    Unknown(String),
+    Parens(String),
+    BadNumber(String, String),
+    Arity(String, usize),
+}
+
+impl std::fmt::Display for OpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OpError::Unknown(op) => write!(f, "unknown op: {}", op),
+            OpError::Parens(op) => write!(f, "mismatched parentheses: {}", op),
+            OpError::BadNumber(op, s) => write!(f, "{}: \"{}\" is not a number", op, s),
+            OpError::Arity(op, n) => write!(f, "{}: wrong number of parameters ({})", op, n),
+        }
+    }
+}
+
+pub fn str_to_net(op: &str) -> Result<Net, OpError> {
+    // ops handled in process (and the default "empty") don't have a net here
+    if str_to_op_num(op) != 0 || op == "empty" {
+        return Ok(Net::new(0, 0));
+    }
+    let raw = op;
     let op = op.replace(' ', "");
     // "cat()" -> ["cat", "", ""],  "cat(mew, mrp)" -> ["cat", "mew, mrp", ""]
     let args: Vec<&str> = op.split(['(', ')']).collect();
+    let Some(params) = args.get(1) else {
+        // no parentheses
+        return Err(OpError::Unknown(raw.to_string()));
+    };
+    if args.len() != 3 || !op.ends_with(')') {
+        return Err(OpError::Parens(raw.to_string()));
+    }
     // parse the parameters (between parentheses)
     let mut p = Vec::new();
-    if let Some(params) = args.get(1) {
-        let params = params.split(',').collect::<Vec<&str>>();
-        for s in params {
-            if let Ok(n) = parse_with_constants(s) {
-                p.push(n);
+    if !params.is_empty() {
+        for s in params.split(',') {
+            match parse_with_constants(s) {
+                Ok(n) => p.push(n),
+                // meter takes its mode as first parameter
+                Err(_) if args[0] == "meter" && p.is_empty() => {}
+                Err(_) => return Err(OpError::BadNumber(args[0].to_string(), s.to_string())),
             }
         }
-    } else {
-        // no parentheses
-        return Net::new(0, 0);
     }
     match args[0] {
         // -------------------- sources --------------------
         "sine" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(sine_hz(*p)));
+                return Ok(Net::wrap(Box::new(sine_hz(*p))));
             } else {
-                return Net::wrap(Box::new(sine()));
+                return Ok(Net::wrap(Box::new(sine())));
             }
         }
         "saw" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(saw_hz(*p)));
+                return Ok(Net::wrap(Box::new(saw_hz(*p))));
             } else {
-                return Net::wrap(Box::new(saw()));
+                return Ok(Net::wrap(Box::new(saw())));
             }
         }
         "square" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(square_hz(*p)));
+                return Ok(Net::wrap(Box::new(square_hz(*p))));
             } else {
-                return Net::wrap(Box::new(square()));
+                return Ok(Net::wrap(Box::new(square())));
             }
         }
         "triangle" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(triangle_hz(*p)));
+                return Ok(Net::wrap(Box::new(triangle_hz(*p))));
             } else {
-                return Net::wrap(Box::new(triangle()));
+                return Ok(Net::wrap(Box::new(triangle())));
             }
         }
         "organ" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(organ_hz(*p)));
+                return Ok(Net::wrap(Box::new(organ_hz(*p))));
             } else {
-                return Net::wrap(Box::new(organ()));
+                return Ok(Net::wrap(Box::new(organ())));
             }
         }
-        "pulse" => return Net::wrap(Box::new(pulse())),
-        "brown" => return Net::wrap(Box::new(brown())),
-        "pink" => return Net::wrap(Box::new(pink())),
-        "white" | "noise" => return Net::wrap(Box::new(white())),
+        "pulse" => return Ok(Net::wrap(Box::new(pulse()))),
+        "brown" => return Ok(Net::wrap(Box::new(brown()))),
+        "pink" => return Ok(Net::wrap(Box::new(pink()))),
+        "white" | "noise" => return Ok(Net::wrap(Box::new(white()))),
         "hammond" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(hammond_hz(*p)));
+                return Ok(Net::wrap(Box::new(hammond_hz(*p))));
             } else {
-                return Net::wrap(Box::new(hammond()));
+                return Ok(Net::wrap(Box::new(hammond())));
             }
         }
-        "zero" => return Net::wrap(Box::new(zero())),
-        "impulse" => return Net::wrap(Box::new(impulse::<U1>())),
-        "lorenz" => return Net::wrap(Box::new(lorenz())),
-        "rossler" => return Net::wrap(Box::new(rossler())),
+        "zero" => return Ok(Net::wrap(Box::new(zero()))),
+        "impulse" => return Ok(Net::wrap(Box::new(impulse::<U1>()))),
+        "lorenz" => return Ok(Net::wrap(Box::new(lorenz()))),
+        "rossler" => return Ok(Net::wrap(Box::new(rossler()))),
         "constant" | "dc" => match p[..] {
             [p0, p1, p2, p3, p4, p5, p6, p7, ..] => {
-                return Net::wrap(Box::new(constant((p0, p1, p2, p3, p4, p5, p6, p7))))
+                return Ok(Net::wrap(Box::new(constant((p0, p1, p2, p3, p4, p5, p6, p7)))))
             }
             [p0, p1, p2, p3, p4, p5, p6, ..] => {
-                return Net::wrap(Box::new(constant((p0, p1, p2, p3, p4, p5, p6))))
+                return Ok(Net::wrap(Box::new(constant((p0, p1, p2, p3, p4, p5, p6)))))
             }
             [p0, p1, p2, p3, p4, p5, ..] => {
-                return Net::wrap(Box::new(constant((p0, p1, p2, p3, p4, p5))))
-            }
-            [p0, p1, p2, p3, p4, ..] => return Net::wrap(Box::new(constant((p0, p1, p2, p3, p4)))),
-            [p0, p1, p2, p3, ..] => return Net::wrap(Box::new(constant((p0, p1, p2, p3)))),
-            [p0, p1, p2, ..] => return Net::wrap(Box::new(constant((p0, p1, p2)))),
-            [p0, p1, ..] => return Net::wrap(Box::new(constant((p0, p1)))),
-            [p0, ..] => return Net::wrap(Box::new(constant(p0))),
-            _ => return Net::wrap(Box::new(constant(1.))),
+                return Ok(Net::wrap(Box::new(constant((p0, p1, p2, p3, p4, p5)))))
+            }
+            [p0, p1, p2, p3, p4, ..] => {
+                return Ok(Net::wrap(Box::new(constant((p0, p1, p2, p3, p4)))))
+            }
+            [p0, p1, p2, p3, ..] => return Ok(Net::wrap(Box::new(constant((p0, p1, p2, p3))))),
+            [p0, p1, p2, ..] => return Ok(Net::wrap(Box::new(constant((p0, p1, p2))))),
+            [p0, p1, ..] => return Ok(Net::wrap(Box::new(constant((p0, p1))))),
+            [p0, ..] => return Ok(Net::wrap(Box::new(constant(p0)))),
+            _ => return Ok(Net::wrap(Box::new(constant(1.)))),
         },
         "dsf_saw" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(dsf_saw_r(*p)));
+                return Ok(Net::wrap(Box::new(dsf_saw_r(*p))));
             } else {
-                return Net::wrap(Box::new(dsf_saw()));
+                return Ok(Net::wrap(Box::new(dsf_saw())));
             }
         }
         "dsf_square" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(dsf_square_r(*p)));
+                return Ok(Net::wrap(Box::new(dsf_square_r(*p))));
             } else {
-                return Net::wrap(Box::new(dsf_square()));
+                return Ok(Net::wrap(Box::new(dsf_square())));
             }
         }
         "pluck" => {
             if let Some(p) = p.get(0..3) {
-                return Net::wrap(Box::new(pluck(p[0], p[1], p[2])));
+                return Ok(Net::wrap(Box::new(pluck(p[0], p[1], p[2]))));
             }
         }
         "mls" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(mls_bits(p.clamp(1., 31.) as u64)));
+                return Ok(Net::wrap(Box::new(mls_bits(p.clamp(1., 31.) as u64))));
             } else {
-                return Net::wrap(Box::new(mls()));
+                return Ok(Net::wrap(Box::new(mls())));
             }
         }
         "soft_saw" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(soft_saw_hz(*p)));
+                return Ok(Net::wrap(Box::new(soft_saw_hz(*p))));
             } else {
-                return Net::wrap(Box::new(soft_saw()));
+                return Ok(Net::wrap(Box::new(soft_saw())));
             }
         }
-        "ramp" => return Net::wrap(Box::new(An(Ramp::new()))),
+        "ramp" => return Ok(Net::wrap(Box::new(An(Ramp::new())))),
 
         // -------------------- filters --------------------
         "allpole" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(allpole_delay(*p)));
+                return Ok(Net::wrap(Box::new(allpole_delay(*p))));
             } else {
-                return Net::wrap(Box::new(allpole()));
+                return Ok(Net::wrap(Box::new(allpole())));
             }
         }
-        "pinkpass" => return Net::wrap(Box::new(pinkpass())),
+        "pinkpass" => return Ok(Net::wrap(Box::new(pinkpass()))),
         "allpass" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(allpass_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(allpass_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(allpass_q(*p)));
+                return Ok(Net::wrap(Box::new(allpass_q(*p))));
             } else {
-                return Net::wrap(Box::new(allpass()));
+                return Ok(Net::wrap(Box::new(allpass())));
             }
         }
         "bandpass" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(bandpass_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(bandpass_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(bandpass_q(*p)));
+                return Ok(Net::wrap(Box::new(bandpass_q(*p))));
             } else {
-                return Net::wrap(Box::new(bandpass()));
+                return Ok(Net::wrap(Box::new(bandpass())));
             }
         }
         "bandrez" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(bandrez_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(bandrez_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(bandrez_q(*p)));
+                return Ok(Net::wrap(Box::new(bandrez_q(*p))));
             } else {
-                return Net::wrap(Box::new(bandrez()));
+                return Ok(Net::wrap(Box::new(bandrez())));
             }
         }
         "bell" => {
             if let Some(p) = p.get(0..3) {
-                return Net::wrap(Box::new(bell_hz(p[0], p[1], p[2])));
+                return Ok(Net::wrap(Box::new(bell_hz(p[0], p[1], p[2]))));
             } else if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(bell_q(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(bell_q(p[0], p[1]))));
             } else {
-                return Net::wrap(Box::new(bell()));
+                return Ok(Net::wrap(Box::new(bell())));
             }
         }
         "biquad" => {
             if let Some(p) = p.get(0..5) {
-                return Net::wrap(Box::new(biquad(p[0], p[1], p[2], p[3], p[4])));
+                return Ok(Net::wrap(Box::new(biquad(p[0], p[1], p[2], p[3], p[4]))));
             }
         }
         "butterpass" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(butterpass_hz(*p)));
+                return Ok(Net::wrap(Box::new(butterpass_hz(*p))));
             } else {
-                return Net::wrap(Box::new(butterpass()));
+                return Ok(Net::wrap(Box::new(butterpass())));
             }
         }
         "dcblock" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(dcblock_hz(*p)));
+                return Ok(Net::wrap(Box::new(dcblock_hz(*p))));
             } else {
-                return Net::wrap(Box::new(dcblock()));
+                return Ok(Net::wrap(Box::new(dcblock())));
             }
         }
         "fir" => match p[..] {
             [p0, p1, p2, p3, p4, p5, p6, p7, p8, p9, ..] => {
-                return Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6, p7, p8, p9))))
+                return Ok(Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6, p7, p8, p9)))))
             }
             [p0, p1, p2, p3, p4, p5, p6, p7, p8, ..] => {
-                return Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6, p7, p8))))
+                return Ok(Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6, p7, p8)))))
             }
             [p0, p1, p2, p3, p4, p5, p6, p7, ..] => {
-                return Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6, p7))))
+                return Ok(Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6, p7)))))
             }
             [p0, p1, p2, p3, p4, p5, p6, ..] => {
-                return Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6))))
+                return Ok(Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5, p6)))))
             }
             [p0, p1, p2, p3, p4, p5, ..] => {
-                return Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5))))
+                return Ok(Net::wrap(Box::new(fir((p0, p1, p2, p3, p4, p5)))))
             }
-            [p0, p1, p2, p3, p4, ..] => return Net::wrap(Box::new(fir((p0, p1, p2, p3, p4)))),
-            [p0, p1, p2, p3, ..] => return Net::wrap(Box::new(fir((p0, p1, p2, p3)))),
-            [p0, p1, p2, ..] => return Net::wrap(Box::new(fir((p0, p1, p2)))),
-            [p0, p1, ..] => return Net::wrap(Box::new(fir((p0, p1)))),
-            [p0, ..] => return Net::wrap(Box::new(fir(p0))),
+            [p0, p1, p2, p3, p4, ..] => return Ok(Net::wrap(Box::new(fir((p0, p1, p2, p3, p4))))),
+            [p0, p1, p2, p3, ..] => return Ok(Net::wrap(Box::new(fir((p0, p1, p2, p3))))),
+            [p0, p1, p2, ..] => return Ok(Net::wrap(Box::new(fir((p0, p1, p2))))),
+            [p0, p1, ..] => return Ok(Net::wrap(Box::new(fir((p0, p1))))),
+            [p0, ..] => return Ok(Net::wrap(Box::new(fir(p0)))),
             _ => {}
         },
         "fir3" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(fir3(*p)));
+                return Ok(Net::wrap(Box::new(fir3(*p))));
             }
         }
         "follow" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(afollow(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(afollow(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(follow(*p)));
+                return Ok(Net::wrap(Box::new(follow(*p))));
             }
         }
         "highpass" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(highpass_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(highpass_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(highpass_q(*p)));
+                return Ok(Net::wrap(Box::new(highpass_q(*p))));
             } else {
-                return Net::wrap(Box::new(highpass()));
+                return Ok(Net::wrap(Box::new(highpass())));
             }
         }
         "highpole" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(highpole_hz(*p)));
+                return Ok(Net::wrap(Box::new(highpole_hz(*p))));
             } else {
-                return Net::wrap(Box::new(highpole()));
+                return Ok(Net::wrap(Box::new(highpole())));
             }
         }
         "highshelf" => {
             if let Some(p) = p.get(0..3) {
-                return Net::wrap(Box::new(highshelf_hz(p[0], p[1], p[2])));
+                return Ok(Net::wrap(Box::new(highshelf_hz(p[0], p[1], p[2]))));
             } else if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(highshelf_q(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(highshelf_q(p[0], p[1]))));
             } else {
-                return Net::wrap(Box::new(highshelf()));
+                return Ok(Net::wrap(Box::new(highshelf())));
             }
         }
         "lowpass" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(lowpass_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(lowpass_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(lowpass_q(*p)));
+                return Ok(Net::wrap(Box::new(lowpass_q(*p))));
             } else {
-                return Net::wrap(Box::new(lowpass()));
+                return Ok(Net::wrap(Box::new(lowpass())));
             }
         }
         "lowpole" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(lowpole_hz(*p)));
+                return Ok(Net::wrap(Box::new(lowpole_hz(*p))));
             } else {
-                return Net::wrap(Box::new(lowpole()));
+                return Ok(Net::wrap(Box::new(lowpole())));
             }
         }
         "lowrez" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(lowrez_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(lowrez_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(lowrez_q(*p)));
+                return Ok(Net::wrap(Box::new(lowrez_q(*p))));
             } else {
-                return Net::wrap(Box::new(lowrez()));
+                return Ok(Net::wrap(Box::new(lowrez())));
             }
         }
         "lowshelf" => {
             if let Some(p) = p.get(0..3) {
-                return Net::wrap(Box::new(lowshelf_hz(p[0], p[1], p[2])));
+                return Ok(Net::wrap(Box::new(lowshelf_hz(p[0], p[1], p[2]))));
             } else if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(lowshelf_q(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(lowshelf_q(p[0], p[1]))));
             } else {
-                return Net::wrap(Box::new(lowshelf()));
+                return Ok(Net::wrap(Box::new(lowshelf())));
             }
         }
         "moog" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(moog_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(moog_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(moog_q(*p)));
+                return Ok(Net::wrap(Box::new(moog_q(*p))));
             } else {
-                return Net::wrap(Box::new(moog()));
+                return Ok(Net::wrap(Box::new(moog())));
             }
         }
         "morph" => {
             if let Some(p) = p.get(0..3) {
-                return Net::wrap(Box::new(morph_hz(p[0], p[1], p[2])));
+                return Ok(Net::wrap(Box::new(morph_hz(p[0], p[1], p[2]))));
             } else {
-                return Net::wrap(Box::new(morph()));
+                return Ok(Net::wrap(Box::new(morph())));
             }
         }
         "notch" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(notch_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(notch_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(notch_q(*p)));
+                return Ok(Net::wrap(Box::new(notch_q(*p))));
             } else {
-                return Net::wrap(Box::new(notch()));
+                return Ok(Net::wrap(Box::new(notch())));
             }
         }
         "peak" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(peak_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(peak_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(peak_q(*p)));
+                return Ok(Net::wrap(Box::new(peak_q(*p))));
             } else {
-                return Net::wrap(Box::new(peak()));
+                return Ok(Net::wrap(Box::new(peak())));
             }
         }
         "resonator" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(resonator_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(resonator_hz(p[0], p[1]))));
             } else {
-                return Net::wrap(Box::new(resonator()));
+                return Ok(Net::wrap(Box::new(resonator())));
             }
         }
 
         // -------------------- channels --------------------
-        "sink" => return Net::wrap(Box::new(sink())),
-        "pass" => return Net::wrap(Box::new(pass())),
+        "sink" => return Ok(Net::wrap(Box::new(sink()))),
+        "pass" => return Ok(Net::wrap(Box::new(pass()))),
         "chan" => {
             let mut net = Net::new(0, 0);
             for i in p {
@@ -441,25 +476,25 @@ pub fn str_to_net(op: &str) -> Net {
                     net = net | pass();
                 }
             }
-            return net;
+            return Ok(net);
         }
         "pan" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(pan(*p)));
+                return Ok(Net::wrap(Box::new(pan(*p))));
             } else {
-                return Net::wrap(Box::new(panner()));
+                return Ok(Net::wrap(Box::new(panner())));
             }
         }
         "join" => {
             if let Some(p) = p.first() {
                 match *p as usize {
-                    2 => return Net::wrap(Box::new(join::<U2>())),
-                    3 => return Net::wrap(Box::new(join::<U3>())),
-                    4 => return Net::wrap(Box::new(join::<U4>())),
-                    5 => return Net::wrap(Box::new(join::<U5>())),
-                    6 => return Net::wrap(Box::new(join::<U6>())),
-                    7 => return Net::wrap(Box::new(join::<U7>())),
-                    8 => return Net::wrap(Box::new(join::<U8>())),
+                    2 => return Ok(Net::wrap(Box::new(join::<U2>()))),
+                    3 => return Ok(Net::wrap(Box::new(join::<U3>()))),
+                    4 => return Ok(Net::wrap(Box::new(join::<U4>()))),
+                    5 => return Ok(Net::wrap(Box::new(join::<U5>()))),
+                    6 => return Ok(Net::wrap(Box::new(join::<U6>()))),
+                    7 => return Ok(Net::wrap(Box::new(join::<U7>()))),
+                    8 => return Ok(Net::wrap(Box::new(join::<U8>()))),
                     _ => {}
                 }
             }
@@ -467,13 +502,13 @@ pub fn str_to_net(op: &str) -> Net {
         "split" => {
             if let Some(p) = p.first() {
                 match *p as usize {
-                    2 => return Net::wrap(Box::new(split::<U2>())),
-                    3 => return Net::wrap(Box::new(split::<U3>())),
-                    4 => return Net::wrap(Box::new(split::<U4>())),
-                    5 => return Net::wrap(Box::new(split::<U5>())),
-                    6 => return Net::wrap(Box::new(split::<U6>())),
-                    7 => return Net::wrap(Box::new(split::<U7>())),
-                    8 => return Net::wrap(Box::new(split::<U8>())),
+                    2 => return Ok(Net::wrap(Box::new(split::<U2>()))),
+                    3 => return Ok(Net::wrap(Box::new(split::<U3>()))),
+                    4 => return Ok(Net::wrap(Box::new(split::<U4>()))),
+                    5 => return Ok(Net::wrap(Box::new(split::<U5>()))),
+                    6 => return Ok(Net::wrap(Box::new(split::<U6>()))),
+                    7 => return Ok(Net::wrap(Box::new(split::<U7>()))),
+                    8 => return Ok(Net::wrap(Box::new(split::<U8>()))),
                     _ => {}
                 }
             }
@@ -481,13 +516,13 @@ pub fn str_to_net(op: &str) -> Net {
         "reverse" => {
             if let Some(p) = p.first() {
                 match *p as usize {
-                    2 => return Net::wrap(Box::new(reverse::<U2>())),
-                    3 => return Net::wrap(Box::new(reverse::<U3>())),
-                    4 => return Net::wrap(Box::new(reverse::<U4>())),
-                    5 => return Net::wrap(Box::new(reverse::<U5>())),
-                    6 => return Net::wrap(Box::new(reverse::<U6>())),
-                    7 => return Net::wrap(Box::new(reverse::<U7>())),
-                    8 => return Net::wrap(Box::new(reverse::<U8>())),
+                    2 => return Ok(Net::wrap(Box::new(reverse::<U2>()))),
+                    3 => return Ok(Net::wrap(Box::new(reverse::<U3>()))),
+                    4 => return Ok(Net::wrap(Box::new(reverse::<U4>()))),
+                    5 => return Ok(Net::wrap(Box::new(reverse::<U5>()))),
+                    6 => return Ok(Net::wrap(Box::new(reverse::<U6>()))),
+                    7 => return Ok(Net::wrap(Box::new(reverse::<U7>()))),
+                    8 => return Ok(Net::wrap(Box::new(reverse::<U8>()))),
                     _ => {}
                 }
             }
@@ -496,15 +531,15 @@ pub fn str_to_net(op: &str) -> Net {
         // -------------------- envelopes --------------------
         "adsr" => {
             if let Some(p) = p.get(0..4) {
-                return Net::wrap(Box::new(adsr_live(p[0], p[1], p[2], p[3])));
+                return Ok(Net::wrap(Box::new(adsr_live(p[0], p[1], p[2], p[3]))));
             }
         }
         "xd" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(lfo(move |t| exp(-t * p))));
+                return Ok(Net::wrap(Box::new(lfo(move |t| exp(-t * p)))));
             } else {
-                return Net::wrap(Box::new(lfo_in(|t, i: &Frame<f32, U1>| exp(-t * i[0]))));
+                return Ok(Net::wrap(Box::new(lfo_in(|t, i: &Frame<f32, U1>| exp(-t * i[0])))));
             }
         }
         // decay time (in seconds), decay curvature
@@ -514,37 +549,37 @@ pub fn str_to_net(op: &str) -> Net {
             if let Some(p) = p.get(0..2) {
                 let p0 = p[0];
                 let p1 = p[1];
-                return Net::wrap(Box::new(lfo(move |t| {
+                return Ok(Net::wrap(Box::new(lfo(move |t| {
                     if t < p0 {
                         ((p0 - t) / p0).powf(p1)
                     } else {
                         0.
                     }
-                })));
+                }))));
             } else if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(lfo_in(move |t, i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(lfo_in(move |t, i: &Frame<f32, U1>| {
                     if t < i[0] {
                         ((i[0] - t) / i[0]).powf(p)
                     } else {
                         0.
                     }
-                })));
+                }))));
             } else {
-                return Net::wrap(Box::new(lfo_in(|t, i: &Frame<f32, U2>| {
+                return Ok(Net::wrap(Box::new(lfo_in(|t, i: &Frame<f32, U2>| {
                     if t < i[0] {
                         ((i[0] - t) / i[0]).powf(i[1])
                     } else {
                         0.
                     }
-                })));
+                }))));
             }
         }
         // attack time, attack curvature, release time, release curvature
         "ar" => {
             if let Some(p) = p.get(0..4) {
                 let (p0, p1, p2, p3) = (p[0], p[1], p[2], p[3]);
-                return Net::wrap(Box::new(lfo(move |t| {
+                return Ok(Net::wrap(Box::new(lfo(move |t| {
                     if t < p0 {
                         (t / p0).powf(p1)
                     } else if t < p0 + p2 {
@@ -552,10 +587,10 @@ pub fn str_to_net(op: &str) -> Net {
                     } else {
                         0.
                     }
-                })));
+                }))));
             } else if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(lfo_in(move |t, i: &Frame<f32, U2>| {
+                return Ok(Net::wrap(Box::new(lfo_in(move |t, i: &Frame<f32, U2>| {
                     if t < i[0] {
                         (t / i[0]).powf(p0)
                     } else if t < i[0] + i[1] {
@@ -563,9 +598,9 @@ pub fn str_to_net(op: &str) -> Net {
                     } else {
                         0.
                     }
-                })));
+                }))));
             } else {
-                return Net::wrap(Box::new(lfo_in(|t, i: &Frame<f32, U4>| {
+                return Ok(Net::wrap(Box::new(lfo_in(|t, i: &Frame<f32, U4>| {
                     if t < i[0] {
                         (t / i[0]).powf(i[1])
                     } else if t < i[0] + i[2] {
@@ -573,109 +608,111 @@ pub fn str_to_net(op: &str) -> Net {
                     } else {
                         0.
                     }
-                })));
+                }))));
             }
         }
 
         // -------------------- other --------------------
-        "tick" => return Net::wrap(Box::new(tick())),
-        "shift_reg" => return Net::wrap(Box::new(An(ShiftReg::new()))),
-        "snh" => return Net::wrap(Box::new(An(SnH::new()))),
+        "tick" => return Ok(Net::wrap(Box::new(tick()))),
+        "shift_reg" => return Ok(Net::wrap(Box::new(An(ShiftReg::new())))),
+        "snh" => return Ok(Net::wrap(Box::new(An(SnH::new())))),
         "meter" => {
             if let (Some(arg), Some(p)) = (args.get(1), p.first()) {
                 if arg.starts_with("peak") {
-                    return Net::wrap(Box::new(meter(Meter::Peak(*p as f64))));
+                    return Ok(Net::wrap(Box::new(meter(Meter::Peak(*p as f64)))));
                 } else if arg.starts_with("rms") {
-                    return Net::wrap(Box::new(meter(Meter::Rms(*p as f64))));
+                    return Ok(Net::wrap(Box::new(meter(Meter::Rms(*p as f64)))));
                 }
             }
         }
         "chorus" => {
             if let Some(p) = p.get(0..4) {
-                return Net::wrap(Box::new(chorus(p[0] as u64, p[1], p[2], p[3])));
+                return Ok(Net::wrap(Box::new(chorus(p[0] as u64, p[1], p[2], p[3]))));
             }
         }
         "clip" => {
             if let Some(p) = p.get(0..2) {
                 if p[0] < p[1] {
-                    return Net::wrap(Box::new(clip_to(p[0], p[1])));
+                    return Ok(Net::wrap(Box::new(clip_to(p[0], p[1]))));
                 } else {
-                    return Net::wrap(Box::new(clip_to(p[1], p[0])));
+                    return Ok(Net::wrap(Box::new(clip_to(p[1], p[0]))));
                 }
             } else {
-                return Net::wrap(Box::new(clip()));
+                return Ok(Net::wrap(Box::new(clip())));
             }
         }
         "declick" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(declick_s(*p)));
+                return Ok(Net::wrap(Box::new(declick_s(*p))));
             } else {
-                return Net::wrap(Box::new(declick()));
+                return Ok(Net::wrap(Box::new(declick())));
             }
         }
         "delay" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(delay(*p)));
+                return Ok(Net::wrap(Box::new(delay(*p))));
             }
         }
         "hold" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(hold_hz(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(hold_hz(p[0], p[1]))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(hold(*p)));
+                return Ok(Net::wrap(Box::new(hold(*p))));
             }
         }
         "limiter" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(limiter(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(limiter(p[0], p[1]))));
             }
         }
         "limiter_stereo" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(limiter_stereo(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(limiter_stereo(p[0], p[1]))));
             }
         }
         "reverb_stereo" => {
             if let Some(p) = p.get(0..3) {
-                return Net::wrap(Box::new(reverb_stereo(p[0], p[1], p[2])));
+                return Ok(Net::wrap(Box::new(reverb_stereo(p[0], p[1], p[2]))));
             } else if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(reverb_stereo(p[0], p[1], 1.)));
+                return Ok(Net::wrap(Box::new(reverb_stereo(p[0], p[1], 1.))));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(reverb_stereo(*p, 5., 1.)));
+                return Ok(Net::wrap(Box::new(reverb_stereo(*p, 5., 1.))));
             }
         }
         "reverb_mono" => {
             if let Some(p) = p.get(0..3) {
-                return Net::wrap(Box::new(split() >> reverb_stereo(p[0], p[1], p[2]) >> join()));
+                return Ok(Net::wrap(Box::new(
+                    split() >> reverb_stereo(p[0], p[1], p[2]) >> join(),
+                )));
             } else if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(split() >> reverb_stereo(p[0], p[1], 1.) >> join()));
+                return Ok(Net::wrap(Box::new(split() >> reverb_stereo(p[0], p[1], 1.) >> join())));
             } else if let Some(p) = p.first() {
-                return Net::wrap(Box::new(split() >> reverb_stereo(*p, 5., 1.) >> join()));
+                return Ok(Net::wrap(Box::new(split() >> reverb_stereo(*p, 5., 1.) >> join())));
             }
         }
         "tap" => {
             if let Some(p) = p.get(0..2) {
                 let p0 = p[0].max(0.);
                 let p1 = p[1].max(0.);
-                return Net::wrap(Box::new(tap(min(p0, p1), max(p0, p1))));
+                return Ok(Net::wrap(Box::new(tap(min(p0, p1), max(p0, p1)))));
             }
         }
         "tap_linear" => {
             if let Some(p) = p.get(0..2) {
                 let p0 = p[0].max(0.);
                 let p1 = p[1].max(0.);
-                return Net::wrap(Box::new(tap_linear(min(p0, p1), max(p0, p1))));
+                return Ok(Net::wrap(Box::new(tap_linear(min(p0, p1), max(p0, p1)))));
             }
         }
         "samp_delay" => {
             if let Some(p) = p.first() {
-                return Net::wrap(Box::new(An(SampDelay::new(*p as usize))));
+                return Ok(Net::wrap(Box::new(An(SampDelay::new(*p as usize)))));
             }
         }
         // thanks to the pdhalf csound opcode
         // https://github.com/csound/csound/blob/master/Opcodes/shape.c#L299
         "pdhalf_bi" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                 let midpoint = i[1].clamp(-1., 1.);
                 if i[0] < midpoint {
                     let leftslope = if midpoint != -1. { (midpoint + 1.).recip() } else { 0. };
@@ -684,10 +721,10 @@ pub fn str_to_net(op: &str) -> Net {
                     let rightslope = if midpoint != 1. { (1. - midpoint).recip() } else { 0. };
                     rightslope * (i[0] - midpoint) + 0.5
                 }
-            })));
+            }))));
         }
         "pdhalf_uni" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                 let midpoint = if i[1] >= 1. {
                     1.
                 } else if i[1] <= -1. {
@@ -702,64 +739,64 @@ pub fn str_to_net(op: &str) -> Net {
                     let rightslope = if midpoint != 1. { 0.5 / (1. - midpoint) } else { 0. };
                     rightslope * (i[0] - midpoint) + 0.5
                 }
-            })));
+            }))));
         }
 
         // -------------------- math --------------------
         "add" => match p[..] {
             [p0, p1, p2, p3, p4, p5, p6, p7, ..] => {
-                return Net::wrap(Box::new(add((p0, p1, p2, p3, p4, p5, p6, p7))))
+                return Ok(Net::wrap(Box::new(add((p0, p1, p2, p3, p4, p5, p6, p7)))))
             }
             [p0, p1, p2, p3, p4, p5, p6, ..] => {
-                return Net::wrap(Box::new(add((p0, p1, p2, p3, p4, p5, p6))))
+                return Ok(Net::wrap(Box::new(add((p0, p1, p2, p3, p4, p5, p6)))))
             }
             [p0, p1, p2, p3, p4, p5, ..] => {
-                return Net::wrap(Box::new(add((p0, p1, p2, p3, p4, p5))))
-            }
-            [p0, p1, p2, p3, p4, ..] => return Net::wrap(Box::new(add((p0, p1, p2, p3, p4)))),
-            [p0, p1, p2, p3, ..] => return Net::wrap(Box::new(add((p0, p1, p2, p3)))),
-            [p0, p1, p2, ..] => return Net::wrap(Box::new(add((p0, p1, p2)))),
-            [p0, p1, ..] => return Net::wrap(Box::new(add((p0, p1)))),
-            [p0, ..] => return Net::wrap(Box::new(add(p0))),
-            _ => return Net::wrap(Box::new(add(1.))),
+                return Ok(Net::wrap(Box::new(add((p0, p1, p2, p3, p4, p5)))))
+            }
+            [p0, p1, p2, p3, p4, ..] => return Ok(Net::wrap(Box::new(add((p0, p1, p2, p3, p4))))),
+            [p0, p1, p2, p3, ..] => return Ok(Net::wrap(Box::new(add((p0, p1, p2, p3))))),
+            [p0, p1, p2, ..] => return Ok(Net::wrap(Box::new(add((p0, p1, p2))))),
+            [p0, p1, ..] => return Ok(Net::wrap(Box::new(add((p0, p1))))),
+            [p0, ..] => return Ok(Net::wrap(Box::new(add(p0)))),
+            _ => return Ok(Net::wrap(Box::new(add(1.)))),
         },
         "sub" => match p[..] {
             [p0, p1, p2, p3, p4, p5, p6, p7, ..] => {
-                return Net::wrap(Box::new(sub((p0, p1, p2, p3, p4, p5, p6, p7))))
+                return Ok(Net::wrap(Box::new(sub((p0, p1, p2, p3, p4, p5, p6, p7)))))
             }
             [p0, p1, p2, p3, p4, p5, p6, ..] => {
-                return Net::wrap(Box::new(sub((p0, p1, p2, p3, p4, p5, p6))))
+                return Ok(Net::wrap(Box::new(sub((p0, p1, p2, p3, p4, p5, p6)))))
             }
             [p0, p1, p2, p3, p4, p5, ..] => {
-                return Net::wrap(Box::new(sub((p0, p1, p2, p3, p4, p5))))
-            }
-            [p0, p1, p2, p3, p4, ..] => return Net::wrap(Box::new(sub((p0, p1, p2, p3, p4)))),
-            [p0, p1, p2, p3, ..] => return Net::wrap(Box::new(sub((p0, p1, p2, p3)))),
-            [p0, p1, p2, ..] => return Net::wrap(Box::new(sub((p0, p1, p2)))),
-            [p0, p1, ..] => return Net::wrap(Box::new(sub((p0, p1)))),
-            [p0, ..] => return Net::wrap(Box::new(sub(p0))),
-            _ => return Net::wrap(Box::new(sub(1.))),
+                return Ok(Net::wrap(Box::new(sub((p0, p1, p2, p3, p4, p5)))))
+            }
+            [p0, p1, p2, p3, p4, ..] => return Ok(Net::wrap(Box::new(sub((p0, p1, p2, p3, p4))))),
+            [p0, p1, p2, p3, ..] => return Ok(Net::wrap(Box::new(sub((p0, p1, p2, p3))))),
+            [p0, p1, p2, ..] => return Ok(Net::wrap(Box::new(sub((p0, p1, p2))))),
+            [p0, p1, ..] => return Ok(Net::wrap(Box::new(sub((p0, p1))))),
+            [p0, ..] => return Ok(Net::wrap(Box::new(sub(p0)))),
+            _ => return Ok(Net::wrap(Box::new(sub(1.)))),
         },
         "mul" => match p[..] {
             [p0, p1, p2, p3, p4, p5, p6, p7, ..] => {
-                return Net::wrap(Box::new(mul((p0, p1, p2, p3, p4, p5, p6, p7))))
+                return Ok(Net::wrap(Box::new(mul((p0, p1, p2, p3, p4, p5, p6, p7)))))
             }
             [p0, p1, p2, p3, p4, p5, p6, ..] => {
-                return Net::wrap(Box::new(mul((p0, p1, p2, p3, p4, p5, p6))))
+                return Ok(Net::wrap(Box::new(mul((p0, p1, p2, p3, p4, p5, p6)))))
             }
             [p0, p1, p2, p3, p4, p5, ..] => {
-                return Net::wrap(Box::new(mul((p0, p1, p2, p3, p4, p5))))
-            }
-            [p0, p1, p2, p3, p4, ..] => return Net::wrap(Box::new(mul((p0, p1, p2, p3, p4)))),
-            [p0, p1, p2, p3, ..] => return Net::wrap(Box::new(mul((p0, p1, p2, p3)))),
-            [p0, p1, p2, ..] => return Net::wrap(Box::new(mul((p0, p1, p2)))),
-            [p0, p1, ..] => return Net::wrap(Box::new(mul((p0, p1)))),
-            [p0, ..] => return Net::wrap(Box::new(mul(p0))),
-            _ => return Net::wrap(Box::new(mul(1.))),
+                return Ok(Net::wrap(Box::new(mul((p0, p1, p2, p3, p4, p5)))))
+            }
+            [p0, p1, p2, p3, p4, ..] => return Ok(Net::wrap(Box::new(mul((p0, p1, p2, p3, p4))))),
+            [p0, p1, p2, p3, ..] => return Ok(Net::wrap(Box::new(mul((p0, p1, p2, p3))))),
+            [p0, p1, p2, ..] => return Ok(Net::wrap(Box::new(mul((p0, p1, p2))))),
+            [p0, p1, ..] => return Ok(Net::wrap(Box::new(mul((p0, p1))))),
+            [p0, ..] => return Ok(Net::wrap(Box::new(mul(p0)))),
+            _ => return Ok(Net::wrap(Box::new(mul(1.)))),
         },
         "div" => match p[..] {
             [p0, p1, p2, p3, p4, p5, p6, p7, ..] => {
-                return Net::wrap(Box::new(mul((
+                return Ok(Net::wrap(Box::new(mul((
                     1. / p0,
                     1. / p1,
                     1. / p2,
@@ -768,10 +805,10 @@ pub fn str_to_net(op: &str) -> Net {
                     1. / p5,
                     1. / p6,
                     1. / p7,
-                ))))
+                )))))
             }
             [p0, p1, p2, p3, p4, p5, p6, ..] => {
-                return Net::wrap(Box::new(mul((
+                return Ok(Net::wrap(Box::new(mul((
                     1. / p0,
                     1. / p1,
                     1. / p2,
@@ -779,392 +816,412 @@ pub fn str_to_net(op: &str) -> Net {
                     1. / p4,
                     1. / p5,
                     1. / p6,
-                ))))
+                )))))
             }
             [p0, p1, p2, p3, p4, p5, ..] => {
-                return Net::wrap(Box::new(mul((
+                return Ok(Net::wrap(Box::new(mul((
                     1. / p0,
                     1. / p1,
                     1. / p2,
                     1. / p3,
                     1. / p4,
                     1. / p5,
-                ))))
+                )))))
             }
             [p0, p1, p2, p3, p4, ..] => {
-                return Net::wrap(Box::new(mul((1. / p0, 1. / p1, 1. / p2, 1. / p3, 1. / p4))))
+                return Ok(Net::wrap(Box::new(mul((1. / p0, 1. / p1, 1. / p2, 1. / p3, 1. / p4)))))
             }
             [p0, p1, p2, p3, ..] => {
-                return Net::wrap(Box::new(mul((1. / p0, 1. / p1, 1. / p2, 1. / p3))))
+                return Ok(Net::wrap(Box::new(mul((1. / p0, 1. / p1, 1. / p2, 1. / p3)))))
             }
-            [p0, p1, p2, ..] => return Net::wrap(Box::new(mul((1. / p0, 1. / p1, 1. / p2)))),
-            [p0, p1, ..] => return Net::wrap(Box::new(mul((1. / p0, 1. / p1)))),
-            [p0, ..] => return Net::wrap(Box::new(mul(1. / p0))),
-            _ => return Net::wrap(Box::new(mul(1.))),
+            [p0, p1, p2, ..] => return Ok(Net::wrap(Box::new(mul((1. / p0, 1. / p1, 1. / p2))))),
+            [p0, p1, ..] => return Ok(Net::wrap(Box::new(mul((1. / p0, 1. / p1))))),
+            [p0, ..] => return Ok(Net::wrap(Box::new(mul(1. / p0)))),
+            _ => return Ok(Net::wrap(Box::new(mul(1.)))),
         },
 
         "rotate" => {
             if let Some(p) = p.get(0..2) {
-                return Net::wrap(Box::new(rotate(p[0], p[1])));
+                return Ok(Net::wrap(Box::new(rotate(p[0], p[1]))));
             }
         }
-        "t" => return Net::wrap(Box::new(lfo(|t| t))),
+        "t" => return Ok(Net::wrap(Box::new(lfo(|t| t)))),
 
         "rise" => {
-            return Net::wrap(Box::new(
+            return Ok(Net::wrap(Box::new(
                 (pass() ^ tick()) >> map(|i: &Frame<f32, U2>| if i[0] > i[1] { 1. } else { 0. }),
-            ));
+            )));
         }
         "fall" => {
-            return Net::wrap(Box::new(
+            return Ok(Net::wrap(Box::new(
                 (pass() ^ tick()) >> map(|i: &Frame<f32, U2>| if i[0] < i[1] { 1. } else { 0. }),
-            ));
+            )));
         }
 
         ">" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     move |i: &Frame<f32, U1>| if i[0] > p { 1. } else { 0. },
-                )));
+                ))));
             } else {
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     |i: &Frame<f32, U2>| if i[0] > i[1] { 1. } else { 0. },
-                )));
+                ))));
             }
         }
         "<" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     move |i: &Frame<f32, U1>| if i[0] < p { 1. } else { 0. },
-                )));
+                ))));
             } else {
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     |i: &Frame<f32, U2>| if i[0] < i[1] { 1. } else { 0. },
-                )));
+                ))));
             }
         }
         "==" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     move |i: &Frame<f32, U1>| if i[0] == p { 1. } else { 0. },
-                )));
+                ))));
             } else {
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     |i: &Frame<f32, U2>| if i[0] == i[1] { 1. } else { 0. },
-                )));
+                ))));
             }
         }
         "!=" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     move |i: &Frame<f32, U1>| if i[0] != p { 1. } else { 0. },
-                )));
+                ))));
             } else {
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     |i: &Frame<f32, U2>| if i[0] != i[1] { 1. } else { 0. },
-                )));
+                ))));
             }
         }
         ">=" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     move |i: &Frame<f32, U1>| if i[0] >= p { 1. } else { 0. },
-                )));
+                ))));
             } else {
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     |i: &Frame<f32, U2>| if i[0] >= i[1] { 1. } else { 0. },
-                )));
+                ))));
             }
         }
         "<=" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     move |i: &Frame<f32, U1>| if i[0] <= p { 1. } else { 0. },
-                )));
+                ))));
             } else {
-                return Net::wrap(Box::new(map(
+                return Ok(Net::wrap(Box::new(map(
                     |i: &Frame<f32, U2>| if i[0] <= i[1] { 1. } else { 0. },
-                )));
+                ))));
             }
         }
         "min" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].min(p))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].min(p)))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].min(i[1]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].min(i[1])))));
             }
         }
         "max" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].max(p))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].max(p)))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].max(i[1]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].max(i[1])))));
             }
         }
         "pow" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].pow(p))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].pow(p)))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].pow(i[1]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].pow(i[1])))));
             }
         }
         "mod" | "rem" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].rem_euclid(p))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].rem_euclid(p)))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].rem_euclid(i[1]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].rem_euclid(i[1])))));
             }
         }
         "log" => {
             if let Some(p) = p.first() {
                 let p = *p;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].log(p))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| i[0].log(p)))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].log(i[1]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].log(i[1])))));
             }
         }
 
         "bitand" => {
             if let Some(p) = p.first() {
                 let p = *p as i32;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     (i[0] as i32 & p) as f32
-                })));
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                     (i[0] as i32 & i[1] as i32) as f32
-                })));
+                }))));
             }
         }
         "bitor" => {
             if let Some(p) = p.first() {
                 let p = *p as i32;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     (i[0] as i32 | p) as f32
-                })));
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                     (i[0] as i32 | i[1] as i32) as f32
-                })));
+                }))));
             }
         }
         "bitxor" => {
             if let Some(p) = p.first() {
                 let p = *p as i32;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     (i[0] as i32 ^ p) as f32
-                })));
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                     (i[0] as i32 ^ i[1] as i32) as f32
-                })));
+                }))));
             }
         }
         "shl" => {
             if let Some(p) = p.first() {
                 let p = *p as usize;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     let i = Wrapping(i[0] as i32) << p;
                     i.0 as f32
-                })));
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                     let i = Wrapping(i[0] as i32) << (i[1] as usize);
                     i.0 as f32
-                })));
+                }))));
             }
         }
         "shr" => {
             if let Some(p) = p.first() {
                 let p = *p as usize;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     let i = Wrapping(i[0] as i32) >> p;
                     i.0 as f32
-                })));
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                     let i = Wrapping(i[0] as i32) >> (i[1] as usize);
                     i.0 as f32
-                })));
+                }))));
             }
         }
 
         "lerp" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| lerp(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| lerp(p0, p1, i[0])))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| lerp(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| lerp(i[0], i[1], i[2])))));
             }
         }
         "lerp11" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| lerp11(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                    lerp11(p0, p1, i[0])
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| lerp11(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| lerp11(i[0], i[1], i[2])))));
             }
         }
         "delerp" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| delerp(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                    delerp(p0, p1, i[0])
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| delerp(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| delerp(i[0], i[1], i[2])))));
             }
         }
         "delerp11" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| delerp11(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                    delerp11(p0, p1, i[0])
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| delerp11(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| {
+                    delerp11(i[0], i[1], i[2])
+                }))));
             }
         }
         "xerp" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| xerp(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| xerp(p0, p1, i[0])))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| xerp(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| xerp(i[0], i[1], i[2])))));
             }
         }
         "xerp11" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| xerp11(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                    xerp11(p0, p1, i[0])
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| xerp11(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| xerp11(i[0], i[1], i[2])))));
             }
         }
         "dexerp" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| dexerp(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                    dexerp(p0, p1, i[0])
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| dexerp(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| dexerp(i[0], i[1], i[2])))));
             }
         }
         "dexerp11" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (p[0], p[1]);
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| dexerp11(p0, p1, i[0]))));
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                    dexerp11(p0, p1, i[0])
+                }))));
             } else {
-                return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| dexerp11(i[0], i[1], i[2]))));
+                return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| {
+                    dexerp11(i[0], i[1], i[2])
+                }))));
             }
         }
 
-        "abs" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].abs()))),
-        "signum" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].signum()))),
-        "floor" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].floor()))),
-        "fract" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].fract()))),
-        "ceil" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].ceil()))),
-        "round" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].round()))),
-        "sqrt" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].sqrt()))),
-        "exp" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].exp()))),
-        "exp2" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].exp2()))),
-        "exp10" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| (exp10(i[0]))))),
-        "exp_m1" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| (i[0].ln_1p())))),
-        "ln_1p" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| (i[0].exp_m1())))),
-        "ln" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].ln()))),
-        "log2" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].log2()))),
-        "log10" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].log10()))),
-        "hypot" => return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].hypot(i[1])))),
-        "atan2" => return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].atan2(i[1])))),
-        "sin" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].sin()))),
-        "cos" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].cos()))),
-        "tan" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].tan()))),
-        "asin" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].asin()))),
-        "acos" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].acos()))),
-        "atan" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].atan()))),
-        "sinh" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].sinh()))),
-        "cosh" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].cosh()))),
-        "tanh" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].tanh()))),
-        "asinh" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].asinh()))),
-        "acosh" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].acosh()))),
-        "atanh" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].atanh()))),
-        "squared" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0] * i[0]))),
-        "cubed" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0] * i[0] * i[0]))),
+        "abs" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].abs())))),
+        "signum" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].signum())))),
+        "floor" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].floor())))),
+        "fract" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].fract())))),
+        "ceil" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].ceil())))),
+        "round" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].round())))),
+        "sqrt" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].sqrt())))),
+        "exp" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].exp())))),
+        "exp2" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].exp2())))),
+        "exp10" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| exp10(i[0]))))),
+        "exp_m1" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].exp_m1())))),
+        "ln_1p" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].ln_1p())))),
+        "ln" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].ln())))),
+        "log2" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].log2())))),
+        "log10" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].log10())))),
+        "hypot" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].hypot(i[1]))))),
+        "atan2" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| i[0].atan2(i[1]))))),
+        "sin" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].sin())))),
+        "cos" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].cos())))),
+        "tan" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].tan())))),
+        "asin" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].asin())))),
+        "acos" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].acos())))),
+        "atan" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].atan())))),
+        "sinh" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].sinh())))),
+        "cosh" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].cosh())))),
+        "tanh" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].tanh())))),
+        "asinh" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].asinh())))),
+        "acosh" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].acosh())))),
+        "atanh" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].atanh())))),
+        "squared" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0] * i[0])))),
+        "cubed" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0] * i[0] * i[0])))),
         "dissonance" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| dissonance(i[0], i[1]))))
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| dissonance(i[0], i[1])))))
         }
         "dissonance_max" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| dissonance_max(i[0]))))
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| dissonance_max(i[0])))))
         }
-        "db_amp" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| db_amp(i[0])))),
-        "amp_db" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| amp_db(i[0])))),
-        "a_weight" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| a_weight(i[0])))),
-        "m_weight" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| m_weight(i[0])))),
+        "db_amp" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| db_amp(i[0]))))),
+        "amp_db" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| amp_db(i[0]))))),
+        "a_weight" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| a_weight(i[0]))))),
+        "m_weight" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| m_weight(i[0]))))),
         "spline" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U5>| {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U5>| {
                 spline(i[0], i[1], i[2], i[3], i[4])
-            })));
+            }))));
         }
         "spline_mono" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U5>| {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U5>| {
                 spline_mono(i[0], i[1], i[2], i[3], i[4])
-            })));
+            }))));
         }
-        "softsign" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| softsign(i[0])))),
-        "softexp" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| softexp(i[0])))),
+        "softsign" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| softsign(i[0]))))),
+        "softexp" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| softexp(i[0]))))),
         "softmix" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U3>| softmix(i[0], i[1], i[2]))))
-        }
-        "smooth3" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth3(i[0])))),
-        "smooth5" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth5(i[0])))),
-        "smooth7" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth7(i[0])))),
-        "smooth9" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth9(i[0])))),
-        "uparc" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| uparc(i[0])))),
-        "downarc" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| downarc(i[0])))),
-        "sine_ease" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| sine_ease(i[0])))),
-        "sin_hz" => return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| sin_hz(i[0], i[1])))),
-        "cos_hz" => return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| cos_hz(i[0], i[1])))),
-        "sqr_hz" => return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| sqr_hz(i[0], i[1])))),
-        "tri_hz" => return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| tri_hz(i[0], i[1])))),
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U3>| softmix(i[0], i[1], i[2])))))
+        }
+        "smooth3" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth3(i[0]))))),
+        "smooth5" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth5(i[0]))))),
+        "smooth7" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth7(i[0]))))),
+        "smooth9" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| smooth9(i[0]))))),
+        "uparc" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| uparc(i[0]))))),
+        "downarc" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| downarc(i[0]))))),
+        "sine_ease" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| sine_ease(i[0]))))),
+        "sin_hz" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| sin_hz(i[0], i[1]))))),
+        "cos_hz" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| cos_hz(i[0], i[1]))))),
+        "sqr_hz" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| sqr_hz(i[0], i[1]))))),
+        "tri_hz" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| tri_hz(i[0], i[1]))))),
         "semitone_ratio" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| semitone_ratio(i[0]))))
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| semitone_ratio(i[0])))))
+        }
+        "rnd1" => {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| rnd1(i[0] as u64) as f32))))
+        }
+        "rnd2" => {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| rnd2(i[0] as u64) as f32))))
         }
-        "rnd1" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| rnd1(i[0] as u64) as f32))),
-        "rnd2" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| rnd2(i[0] as u64) as f32))),
         "spline_noise" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                 spline_noise(i[0] as u64, i[1]) as f32
-            })));
+            }))));
         }
         "fractal_noise" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U4>| {
-                fractal_noise(i[0] as i64, i[1].min(1.) as i64, i[2], i[3]) as f32
-            })));
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U4>| {
+                fractal_noise(i[0] as i64, i[1].max(1.) as i64, i[2], i[3]) as f32
+            }))));
         }
         "wrap" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (min(p[0], p[1]), max(p[0], p[1]));
                 let r = p1 - p0;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     (((i[0] - p0) % r) + r) % r + p0
-                })));
+                }))));
             } else if let Some(p) = p.first() {
                 let x = *p;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     i[0] - x * (i[0] / x).floor()
-                })));
+                }))));
             }
         }
         "mirror" => {
             if let Some(p) = p.get(0..2) {
                 let (p0, p1) = (min(p[0], p[1]), max(p[0], p[1]));
                 let r = p1 - p0;
-                return Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
+                return Ok(Net::wrap(Box::new(map(move |i: &Frame<f32, U1>| {
                     let n = if i[0].is_normal() { i[0] } else { 0. };
                     if n >= p0 && n <= p1 {
                         n
@@ -1177,22 +1234,22 @@ pub fn str_to_net(op: &str) -> Net {
                             p1 - (distance - folds * r)
                         }
                     }
-                })));
+                }))));
             }
         }
         "pol" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                 (i[0].hypot(i[1]), i[1].atan2(i[0]))
-            })));
+            }))));
         }
         "car" => {
-            return Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
+            return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U2>| {
                 (i[0] * i[1].cos(), i[0] * i[1].sin())
-            })));
+            }))));
         }
-        "deg" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].to_degrees()))),
-        "rad" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].to_radians()))),
-        "recip" => return Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].recip()))),
+        "deg" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].to_degrees())))),
+        "rad" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].to_radians())))),
+        "recip" => return Ok(Net::wrap(Box::new(map(|i: &Frame<f32, U1>| i[0].recip())))),
         "rfft" => {
             if let Some(p) = p.get(0..2) {
                 let i = p[0] as usize;
@@ -1201,7 +1258,7 @@ pub fn str_to_net(op: &str) -> Net {
                 if i != x {
                     bevy::prelude::warn!("rfft used next power of two: {}", x);
                 }
-                return Net::wrap(Box::new(An(Rfft::new(x, start))));
+                return Ok(Net::wrap(Box::new(An(Rfft::new(x, start)))));
             }
         }
         "ifft" => {
@@ -1212,17 +1269,18 @@ pub fn str_to_net(op: &str) -> Net {
                 if i != x {
                     bevy::prelude::warn!("ifft used next power of two: {}", x);
                 }
-                return Net::wrap(Box::new(An(Ifft::new(x, start))));
+                return Ok(Net::wrap(Box::new(An(Ifft::new(x, start)))));
             }
         }
         "normal" => {
-            return Net::wrap(Box::new(map(
+            return Ok(Net::wrap(Box::new(map(
                 |i: &Frame<f32, U1>| if i[0].is_normal() { i[0] } else { 0. },
-            )));
+            ))));
         }
-        _ => {}
+        _ => return Err(OpError::Unknown(args[0].to_string())),
     }
-    Net::new(0, 0)
+    // a known op that didn't like its parameters
+    Err(OpError::Arity(args[0].to_string(), p.len()))
 }
 
 pub fn str_to_op_num(op: &str) -> u16 {
@@ -1283,7 +1341,7 @@ pub fn str_to_op_num(op: &str) -> u16 {
         "product" => 57,
         "apply" => 59,
         "render" => 60,
-        "var()" => 61,
+        "var()" | "trig()" => 61,
         "in()" | "adc()" => 62,
         "monitor()" => 63,
         "timer()" => 64,
@@ -1317,6 +1375,10 @@ pub fn str_to_op_num(op: &str) -> u16 {
         "process" => 92,
         "buffin()" => 93,
         "buffout()" => 94,
+        "beat" => 98,
+        "bar" => 99,
+        "xruns" => 102,
+        "touches" => 105,
         x => {
             if x.starts_with("osc_r") {
                 42
@@ -1328,9 +1390,108 @@ pub fn str_to_op_num(op: &str) -> u16 {
                 91
             } else if x.starts_with("worm") {
                 96
+            } else if x.starts_with("poly(") {
+                97
+            } else if x.starts_with("phase(") {
+                100
+            } else if x.starts_with("clock(") {
+                101
+            } else if x.starts_with("scope(") {
+                103
+            } else if x.starts_with("spectrum(") {
+                104
             } else {
                 0
             }
         }
     }
 }
+
+// where the command history is kept
+pub fn state_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/state")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(dir.join("quartz"))
+}
+
+// where the rc file is
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(dir.join("quartz"))
+}
+
+// commands in a file, separated by `;` or newlines (lines starting with `//` are ignored)
+pub fn read_commands(path: &Path) -> std::io::Result<Vec<String>> {
+    let s = std::fs::read_to_string(path)?;
+    Ok(s.lines()
+        .filter(|l| !l.trim_start().starts_with("//"))
+        .flat_map(|l| l.split(';'))
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect())
+}
+
+// `points` samples spread over the latest `size` a scope got (oldest first).
+// the window starts at a rising zero crossing when there's one, so periodic
+// signals stand still
+pub fn scope_trace(snoop: &Snoop, size: usize, points: usize) -> Vec<f32> {
+    let size = Ord::clamp(size, 1, snoop.capacity() / 2);
+    let start = (size - 1..snoop.capacity() - 1)
+        .find(|i| snoop.at(i + 1) <= 0. && snoop.at(*i) > 0.)
+        .unwrap_or(size - 1);
+    let points = Ord::clamp(points, 1, size);
+    (0..points).map(|k| snoop.at(start - k * size / points)).collect()
+}
+
+// levels (dB) of the latest `size` samples (a power of two) at `points`
+// frequencies, spaced logarithmically from the first bin up to nyquist.
+// each point is the loudest bin it covers
+pub fn spectrum_trace(snoop: &Snoop, size: usize, points: usize) -> Vec<f32> {
+    let size = Ord::clamp(size, 2, snoop.capacity());
+    // hann window
+    let window: Vec<f32> = (0..size)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / size as f32).cos())
+        .collect();
+    let gain = 2. / window.iter().sum::<f32>();
+    let input: Vec<f32> = (0..size).map(|i| snoop.at(size - 1 - i) * window[i]).collect();
+    let mut bins = vec![Default::default(); size / 2 + 1];
+    fundsp::fft::real_fft(&input, &mut bins);
+    let levels: Vec<f32> = bins.iter().map(|x| amp_db(x.norm() * gain)).collect();
+    let top = (size / 2) as f32;
+    (0..points)
+        .map(|k| {
+            let lo = top.powf(k as f32 / points as f32) as usize;
+            let hi = Ord::max(top.powf((k + 1) as f32 / points as f32) as usize, lo + 1);
+            levels[lo..hi].iter().fold(f32::NEG_INFINITY, |a, b| a.max(*b))
+        })
+        .collect()
+}
+
+// a `/pattern` search. the pattern is a regex over op strings (and comment text),
+// it also matches the circle with that id, or circles with that num if it's a number
+pub struct SearchPattern {
+    regex: regex::Regex,
+    id: String,
+    num: Option<f32>,
+}
+
+impl SearchPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(SearchPattern {
+            regex: regex::Regex::new(pattern)?,
+            id: pattern.to_string(),
+            num: pattern.parse().ok(),
+        })
+    }
+
+    pub fn matches(&self, id: &str, text: Option<&str>, num: Option<f32>) -> bool {
+        id == self.id
+            || text.is_some_and(|t| self.regex.is_match(t))
+            || num.is_some_and(|n| Some(n) == self.num)
+    }
+}
@@ -0,0 +1,284 @@
+use bevy::{ecs::component::Tick, prelude::*};
+
+use fundsp::{
+    hacker32::*,
+    net::{Net, NodeId},
+    realnet::NetBackend,
+};
+
+use std::collections::HashMap;
+
+/// how a connective op put its net together out of the nets of other circles.
+/// out() lays it out node by node, so the nodes keep their state when the
+/// graph changes around them
+#[derive(Clone, Debug, PartialEq)]
+pub enum Part {
+    /// a circle's own net
+    Circle(Entity),
+    Pipe(Box<Part>, Box<Part>),
+    Stack(Box<Part>, Box<Part>),
+    Bus(Box<Part>, Box<Part>),
+    Branch(Box<Part>, Box<Part>),
+    Sum(Box<Part>, Box<Part>),
+    Product(Box<Part>, Box<Part>),
+    Sub(Box<Part>, Box<Part>),
+    Thru(Box<Part>),
+}
+
+impl Part {
+    /// the nets of these two parts joined by a connective op (`+ * - >> | & ^`)
+    pub fn join(op: u16, a: Part, b: Part) -> Part {
+        let (a, b) = (Box::new(a), Box::new(b));
+        match op {
+            82 => Part::Sum(a, b),
+            83 => Part::Product(a, b),
+            84 => Part::Sub(a, b),
+            85 => Part::Pipe(a, b),
+            86 => Part::Stack(a, b),
+            87 => Part::Bus(a, b),
+            88 => Part::Branch(a, b),
+            _ => panic!("not a connective op: {}", op),
+        }
+    }
+}
+
+/// the parts connective circles (sum, -, >>, |, &, ^, !) were last built from
+#[derive(Resource, Default)]
+pub struct Parts(pub HashMap<Entity, Part>);
+
+impl Parts {
+    /// what a circle's net is made of, given its op number
+    pub fn get(&self, e: Entity, op_num: u16) -> Part {
+        match self.0.get(&e) {
+            Some(part) if (82..=89).contains(&op_num) => part.clone(),
+            _ => Part::Circle(e),
+        }
+    }
+}
+
+// where an output of a part comes from
+#[derive(Clone, Copy)]
+enum Source {
+    Node(NodeId, usize),
+    // passed through from an input of the part (`!`)
+    Input(usize),
+}
+
+// a part laid out in the frontend: the ports each of its inputs feeds,
+// and where each of its outputs comes from
+struct Ports {
+    inputs: Vec<Vec<(NodeId, usize)>>,
+    outputs: Vec<Source>,
+}
+
+/// the output graph. a fundsp frontend whose backend plays in the slot.
+/// every use of a circle's net is one node in it, so committing a change only
+/// replaces the nodes of the circles that changed, the rest carry on
+#[derive(Resource)]
+pub struct OutGraph {
+    /// the out() circle this graph is playing
+    pub owner: Option<Entity>,
+    front: Net,
+    // the node each use of a circle got, and when that circle's net last changed.
+    // keyed by circle and the number of times it was used before in the graph
+    nodes: HashMap<(Entity, usize), (NodeId, Tick)>,
+    // the nodes joining outputs of parts (sums, products..), stateless
+    joins: Vec<NodeId>,
+}
+
+impl Default for OutGraph {
+    fn default() -> Self {
+        OutGraph { owner: None, front: Net::new(0, 2), nodes: HashMap::new(), joins: Vec::new() }
+    }
+}
+
+impl OutGraph {
+    /// lay out `part` (silence if none, or if it doesn't have 1 or 2 outputs
+    /// and no inputs) and send it to the backend. `circle` gives a circle's net,
+    /// and when it last changed. returns the backend the first time
+    pub fn set(
+        &mut self,
+        part: Option<&Part>,
+        mut circle: impl FnMut(Entity) -> Option<(Net, Tick)>,
+    ) -> Option<NetBackend> {
+        for id in self.joins.drain(..) {
+            self.front.remove(id);
+        }
+        let mut nodes = std::mem::take(&mut self.nodes);
+        // cut every connection, the ones still there get made again
+        for (id, _) in nodes.values() {
+            for port in 0..self.front.node(*id).inputs() {
+                self.front.disconnect(*id, port);
+            }
+        }
+        for channel in 0..2 {
+            self.front.disconnect_output(channel);
+        }
+        let mut uses = HashMap::new();
+        let ports = part.and_then(|part| self.lay_out(part, &mut nodes, &mut uses, &mut circle));
+        // the nodes of circles that aren't used anymore (or used fewer times)
+        for (id, _) in nodes.into_values() {
+            self.front.remove(id);
+        }
+        if let Some(ports) = ports.filter(|p| p.inputs.is_empty() && p.outputs.len() <= 2) {
+            // a mono net only goes to the left
+            for (channel, source) in ports.outputs.into_iter().enumerate() {
+                if let Source::Node(id, port) = source {
+                    self.front.connect_output(id, port, channel);
+                }
+            }
+        }
+        if self.front.has_backend() {
+            self.front.commit();
+            None
+        } else {
+            Some(self.front.backend())
+        }
+    }
+
+    // add the nodes of a part (reusing the ones that didn't change) and connect them
+    fn lay_out(
+        &mut self,
+        part: &Part,
+        old: &mut HashMap<(Entity, usize), (NodeId, Tick)>,
+        uses: &mut HashMap<Entity, usize>,
+        circle: &mut impl FnMut(Entity) -> Option<(Net, Tick)>,
+    ) -> Option<Ports> {
+        let (a, b) = match part {
+            Part::Circle(e) => {
+                let use_count = uses.entry(*e).or_default();
+                let key = (*e, *use_count);
+                *use_count += 1;
+                let (net, changed) = circle(*e)?;
+                let id = match old.remove(&key) {
+                    Some((id, last)) if last == changed => id,
+                    kept => {
+                        if let Some((id, _)) = kept {
+                            self.front.remove(id);
+                        }
+                        self.front.push(Box::new(net))
+                    }
+                };
+                self.nodes.insert(key, (id, changed));
+                let unit = self.front.node(id);
+                let inputs = (0..unit.inputs()).map(|port| vec![(id, port)]).collect();
+                let outputs = (0..unit.outputs()).map(|port| Source::Node(id, port)).collect();
+                return Some(Ports { inputs, outputs });
+            }
+            Part::Thru(a) => {
+                let mut a = self.lay_out(a, old, uses, circle)?;
+                let n = a.inputs.len();
+                a.outputs.truncate(n);
+                a.outputs.extend((a.outputs.len()..n).map(Source::Input));
+                return Some(a);
+            }
+            Part::Pipe(a, b)
+            | Part::Stack(a, b)
+            | Part::Bus(a, b)
+            | Part::Branch(a, b)
+            | Part::Sum(a, b)
+            | Part::Product(a, b)
+            | Part::Sub(a, b) => (a, b),
+        };
+        let a = self.lay_out(a, old, uses, circle)?;
+        let b = self.lay_out(b, old, uses, circle)?;
+        match part {
+            Part::Pipe(..) => {
+                if a.outputs.len() != b.inputs.len() {
+                    return None;
+                }
+                let mut inputs = a.inputs;
+                for (source, targets) in a.outputs.iter().zip(b.inputs) {
+                    for target in targets {
+                        self.feed(*source, target, &mut inputs);
+                    }
+                }
+                let outputs = b
+                    .outputs
+                    .into_iter()
+                    .map(|source| match source {
+                        Source::Input(i) => a.outputs[i],
+                        source => source,
+                    })
+                    .collect();
+                Some(Ports { inputs, outputs })
+            }
+            Part::Stack(..) => Some(stack(a, b)),
+            Part::Branch(..) | Part::Bus(..) => {
+                if a.inputs.len() != b.inputs.len() {
+                    return None;
+                }
+                let n = a.inputs.len();
+                let Ports { mut inputs, mut outputs } = stack(a, b);
+                // both take the same inputs
+                let b_inputs = inputs.split_off(n);
+                for (i, targets) in b_inputs.into_iter().enumerate() {
+                    inputs[i].extend(targets);
+                }
+                for source in &mut outputs {
+                    if let Source::Input(i) = source {
+                        if *i >= n {
+                            *i -= n;
+                        }
+                    }
+                }
+                let ports = Ports { inputs, outputs };
+                if let Part::Bus(..) = part {
+                    self.combine(ports, |x, y| Box::new(x + y))
+                } else {
+                    Some(ports)
+                }
+            }
+            Part::Sum(..) => self.combine(stack(a, b), |x, y| Box::new(x + y)),
+            Part::Product(..) => self.combine(stack(a, b), |x, y| Box::new(x * y)),
+            _ => self.combine(stack(a, b), |x, y| Box::new(x - y)),
+        }
+    }
+
+    // join the first half of the outputs with the second half, output by output
+    fn combine(
+        &mut self,
+        ports: Ports,
+        op: impl Fn(An<Pass>, An<Pass>) -> Box<dyn AudioUnit>,
+    ) -> Option<Ports> {
+        let Ports { mut inputs, mut outputs } = ports;
+        if outputs.len() % 2 != 0 {
+            return None;
+        }
+        let rhs = outputs.split_off(outputs.len() / 2);
+        for (lhs, rhs) in outputs.iter_mut().zip(rhs) {
+            let id = self.front.push(op(pass(), pass()));
+            self.joins.push(id);
+            self.feed(*lhs, (id, 0), &mut inputs);
+            self.feed(rhs, (id, 1), &mut inputs);
+            *lhs = Source::Node(id, 0);
+        }
+        Some(Ports { inputs, outputs })
+    }
+
+    // connect a source to an input of a node
+    fn feed(
+        &mut self,
+        source: Source,
+        (id, port): (NodeId, usize),
+        inputs: &mut [Vec<(NodeId, usize)>],
+    ) {
+        match source {
+            Source::Node(source, source_port) => self.front.connect(source, source_port, id, port),
+            Source::Input(i) => inputs[i].push((id, port)),
+        }
+    }
+}
+
+// two parts side by side
+fn stack(a: Ports, b: Ports) -> Ports {
+    let offset = a.inputs.len();
+    let mut inputs = a.inputs;
+    inputs.extend(b.inputs);
+    let mut outputs = a.outputs;
+    outputs.extend(b.outputs.into_iter().map(|source| match source {
+        Source::Input(i) => Source::Input(i + offset),
+        source => source,
+    }));
+    Ports { inputs, outputs }
+}
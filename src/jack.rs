@@ -0,0 +1,291 @@
+// jack mode. quartz runs as a jack client with its own named ports instead of
+// cpal streams on a device. the ports match `in()` and `out()`, they're connected
+// following a list of rules (again whenever new ports show up), and the shared
+// transport can follow jack's
+
+use bevy::prelude::*;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+pub use client::*;
+
+pub const CLIENT_NAME: &str = "quartz";
+pub const IN_PORTS: [&str; 2] = ["in_1", "in_2"];
+pub const OUT_PORTS: [&str; 2] = ["out_1", "out_2"];
+
+/// jack mode settings. the client itself is a non-send resource (`JackClient`)
+#[derive(Resource)]
+pub struct Jack {
+    pub enabled: bool,
+    /// (from, to) connections made whenever both ends exist. our own ports are
+    /// given by their short names, others are full names or regex patterns
+    pub rules: Vec<(String, String)>,
+    /// set when the rules change, so they're applied again
+    pub rules_changed: bool,
+    /// follow jack's transport (play state, position, tempo, meter)
+    pub follow: Arc<AtomicBool>,
+    /// all the ports on the server (for `jp`), kept up to date while it's on
+    pub ports: Vec<String>,
+}
+
+impl Default for Jack {
+    fn default() -> Self {
+        Jack {
+            enabled: false,
+            rules: vec![
+                (OUT_PORTS[0].into(), "system:playback_1".into()),
+                (OUT_PORTS[1].into(), "system:playback_2".into()),
+                ("system:capture_1".into(), IN_PORTS[0].into()),
+                ("system:capture_2".into(), IN_PORTS[1].into()),
+            ],
+            rules_changed: false,
+            follow: Arc::new(AtomicBool::new(false)),
+            ports: Vec::new(),
+        }
+    }
+}
+
+impl Jack {
+    pub fn following(&self) -> bool {
+        self.follow.load(Ordering::Relaxed)
+    }
+
+    pub fn set_following(&self, follow: bool) {
+        self.follow.store(follow, Ordering::Relaxed);
+    }
+}
+
+/// position in beats (from 0) of a jack bar, beat, and tick (bars and beats from 1)
+pub fn bbt_beats(bar: usize, beat: usize, tick: f64, beats_per_bar: f32) -> f64 {
+    bar.saturating_sub(1) as f64 * beats_per_bar as f64 + beat.saturating_sub(1) as f64 + tick
+}
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd"))]
+mod client {
+    use super::*;
+    use crate::{audio::*, nodes::*};
+    use fundsp::hacker32::*;
+    use jack::{
+        AsyncClient, AudioIn, AudioOut, Client, ClientOptions, ClientStatus, Control,
+        NotificationHandler, Port, PortFlags, PortId, ProcessHandler, ProcessScope, TransportState,
+    };
+    use std::time::Instant;
+
+    /// a running jack client, processing the output graph and the input
+    pub struct JackClient {
+        client: AsyncClient<Notifications, Process>,
+        ports_changed: Arc<AtomicBool>,
+        shut_down: Arc<AtomicBool>,
+    }
+
+    impl JackClient {
+        /// connect to the jack server (without starting one), register the ports,
+        /// and start playing whatever is set in the slot
+        pub fn new(
+            name: &str,
+            slot: SlotBackend,
+            shared: OutShared,
+            follow: Arc<AtomicBool>,
+        ) -> Result<Self, String> {
+            let (client, _) =
+                Client::new(name, ClientOptions::NO_START_SERVER).map_err(|e| e.to_string())?;
+            let register_in = |name| client.register_port(name, AudioIn);
+            let register_out = |name| client.register_port(name, AudioOut);
+            let ins = [register_in(IN_PORTS[0]), register_in(IN_PORTS[1])];
+            let outs = [register_out(OUT_PORTS[0]), register_out(OUT_PORTS[1])];
+            let (ins, outs) = match (ins, outs) {
+                ([Ok(l), Ok(r)], [Ok(l_out), Ok(r_out)]) => ([l, r], [l_out, r_out]),
+                _ => return Err("couldn't register the ports".into()),
+            };
+            let sr = client.sample_rate() as f64;
+            let ports_changed = Arc::new(AtomicBool::new(true));
+            let shut_down = Arc::new(AtomicBool::new(false));
+            let notifications = Notifications {
+                ports_changed: ports_changed.clone(),
+                shut_down: shut_down.clone(),
+                status: shared.status.clone(),
+            };
+            let out = out_unit(slot, &shared, sr);
+            let process = Process { ins, outs, out, shared, follow, sr };
+            let client =
+                client.activate_async(notifications, process).map_err(|e| e.to_string())?;
+            Ok(JackClient { client, ports_changed, shut_down })
+        }
+
+        /// the name we got (it's changed if there's another client with the same name)
+        pub fn name(&self) -> &str {
+            self.client.as_client().name()
+        }
+
+        pub fn sample_rate(&self) -> usize {
+            self.client.as_client().sample_rate()
+        }
+
+        /// the server is gone
+        pub fn shut_down(&self) -> bool {
+            self.shut_down.load(Ordering::Relaxed)
+        }
+
+        /// ports were added or removed since the last call
+        pub fn take_ports_changed(&self) -> bool {
+            self.ports_changed.swap(false, Ordering::Relaxed)
+        }
+
+        /// the full names of all ports
+        pub fn ports(&self) -> Vec<String> {
+            self.client.as_client().ports(None, None, PortFlags::empty())
+        }
+
+        /// make the connections of the rules whose ports exist.
+        /// returns the ones that failed
+        pub fn connect(&self, rules: &[(String, String)]) -> Vec<String> {
+            let client = self.client.as_client();
+            let mut failed = Vec::new();
+            for (from, to) in rules {
+                let sources = self.resolve(from, PortFlags::IS_OUTPUT);
+                let destinations = self.resolve(to, PortFlags::IS_INPUT);
+                for source in &sources {
+                    for destination in &destinations {
+                        match client.connect_ports_by_name(source, destination) {
+                            Ok(()) | Err(jack::Error::PortAlreadyConnected(..)) => {}
+                            Err(_) => failed.push(format!("{} -> {}", source, destination)),
+                        }
+                    }
+                }
+            }
+            failed
+        }
+
+        // the full names of the ports a rule refers to
+        fn resolve(&self, port: &str, flags: PortFlags) -> Vec<String> {
+            let client = self.client.as_client();
+            if !port.contains(':') {
+                return vec![format!("{}:{}", client.name(), port)];
+            }
+            client.ports(Some(&format!("^{}$", port)), None, flags)
+        }
+    }
+
+    struct Notifications {
+        ports_changed: Arc<AtomicBool>,
+        shut_down: Arc<AtomicBool>,
+        status: AudioStatus,
+    }
+
+    impl NotificationHandler for Notifications {
+        fn shutdown(&mut self, _: ClientStatus, _reason: &str) {
+            self.shut_down.store(true, Ordering::Relaxed);
+        }
+
+        fn port_registration(&mut self, _: &Client, _: PortId, _registered: bool) {
+            self.ports_changed.store(true, Ordering::Relaxed);
+        }
+
+        fn xrun(&mut self, _: &Client) -> Control {
+            self.status.underrun();
+            Control::Continue
+        }
+    }
+
+    struct Process {
+        ins: [Port<AudioIn>; 2],
+        outs: [Port<AudioOut>; 2],
+        out: BlockRateAdapter,
+        shared: OutShared,
+        follow: Arc<AtomicBool>,
+        sr: f64,
+    }
+
+    impl ProcessHandler for Process {
+        fn process(&mut self, client: &Client, ps: &ProcessScope) -> Control {
+            let start = Instant::now();
+            let OutShared { transport, clock, load, status, input } = &self.shared;
+            clock.start_callback();
+            if self.follow.load(Ordering::Relaxed) {
+                follow(client, transport, self.sr);
+            }
+            // the input goes in first, so the output can take it in the same cycle
+            let [l_in, r_in] = &self.ins;
+            let mut dropped = input.take_skipped() * 2;
+            for (l, r) in l_in.as_slice(ps).iter().zip(r_in.as_slice(ps)) {
+                if !input.push(*l, *r) {
+                    dropped += 2;
+                }
+            }
+            if dropped > 0 {
+                status.overflow(dropped);
+            }
+            let [l_out, r_out] = &mut self.outs;
+            for (l, r) in l_out.as_mut_slice(ps).iter_mut().zip(r_out.as_mut_slice(ps)) {
+                let (a, b) = self.out.get_stereo();
+                clock.take_sample();
+                *l = clean(a);
+                *r = clean(b);
+            }
+            load.record(start.elapsed(), ps.n_frames() as usize, self.sr);
+            Control::Continue
+        }
+    }
+
+    // set the transport to jack's, at the start of a cycle
+    fn follow(client: &Client, transport: &Transport, sr: f64) {
+        let Ok(state) = client.transport().query() else { return };
+        transport.set_playing(state.state == TransportState::Rolling);
+        if let Some(bbt) = state.pos.bbt() {
+            transport.set_bpm(bbt.bpm as f32);
+            transport.set_meter(bbt.sig_num);
+            let tick = bbt.tick as f64 / bbt.ticks_per_beat;
+            transport.set_position(bbt_beats(bbt.bar, bbt.beat, tick, bbt.sig_num));
+        } else {
+            // no timebase master, count beats from the frame at our tempo
+            let seconds = state.pos.frame() as f64 / sr;
+            transport.set_position(seconds * transport.bpm() as f64 / 60.);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd")))]
+mod client {
+    use crate::audio::OutShared;
+    use fundsp::slot::SlotBackend;
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    pub struct JackClient;
+
+    impl JackClient {
+        pub fn new(
+            _name: &str,
+            _slot: SlotBackend,
+            _shared: OutShared,
+            _follow: Arc<AtomicBool>,
+        ) -> Result<Self, String> {
+            Err("not supported on this platform".into())
+        }
+
+        pub fn name(&self) -> &str {
+            ""
+        }
+
+        pub fn sample_rate(&self) -> usize {
+            0
+        }
+
+        pub fn shut_down(&self) -> bool {
+            true
+        }
+
+        pub fn take_ports_changed(&self) -> bool {
+            false
+        }
+
+        pub fn ports(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub fn connect(&self, _rules: &[(String, String)]) -> Vec<String> {
+            Vec::new()
+        }
+    }
+}
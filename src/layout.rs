@@ -0,0 +1,340 @@
+use bevy::math::{Rect, Vec2};
+
+// arranging circles. they're given as (center, radius) and the new centers
+// are returned in the same order
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom,
+}
+
+pub fn str_to_side(s: &str) -> Option<Side> {
+    match s {
+        "left" | "l" => Some(Side::Left),
+        "center" | "c" => Some(Side::Center),
+        "right" | "r" => Some(Side::Right),
+        "top" | "t" => Some(Side::Top),
+        "middle" | "m" => Some(Side::Middle),
+        "bottom" | "b" => Some(Side::Bottom),
+        _ => None,
+    }
+}
+
+// line up the edges (or centers) of the circles.
+// center is a vertical line through the middle, middle a horizontal one
+pub fn align(circles: &[(Vec2, f32)], side: Side) -> Vec<Vec2> {
+    let left = circles.iter().map(|(p, r)| p.x - r).fold(f32::INFINITY, f32::min);
+    let right = circles.iter().map(|(p, r)| p.x + r).fold(f32::NEG_INFINITY, f32::max);
+    let top = circles.iter().map(|(p, r)| p.y + r).fold(f32::NEG_INFINITY, f32::max);
+    let bottom = circles.iter().map(|(p, r)| p.y - r).fold(f32::INFINITY, f32::min);
+    circles
+        .iter()
+        .map(|(p, r)| match side {
+            Side::Left => Vec2::new(left + r, p.y),
+            Side::Center => Vec2::new((left + right) / 2., p.y),
+            Side::Right => Vec2::new(right - r, p.y),
+            Side::Top => Vec2::new(p.x, top - r),
+            Side::Middle => Vec2::new(p.x, (top + bottom) / 2.),
+            Side::Bottom => Vec2::new(p.x, bottom + r),
+        })
+        .collect()
+}
+
+// the first and last (left to right, or top to bottom) stay where they are,
+// the rest are spaced evenly between them
+pub fn distribute(circles: &[(Vec2, f32)], vertical: bool) -> Vec<Vec2> {
+    let mut out: Vec<Vec2> = circles.iter().map(|c| c.0).collect();
+    if circles.len() < 3 {
+        return out;
+    }
+    let key = |p: Vec2| if vertical { -p.y } else { p.x };
+    let mut sorted: Vec<usize> = (0..circles.len()).collect();
+    sorted.sort_by(|a, b| key(out[*a]).total_cmp(&key(out[*b])));
+    let first = out[sorted[0]];
+    let last = out[*sorted.last().unwrap()];
+    let step = (last - first) / (circles.len() - 1) as f32;
+    for (i, c) in sorted.into_iter().enumerate() {
+        let p = first + step * i as f32;
+        if vertical {
+            out[c].y = p.y;
+        } else {
+            out[c].x = p.x;
+        }
+    }
+    out
+}
+
+// a column for every order (lowest on the left), starting at the top left of the
+// selection. a column keeps its circles' top to bottom order. `gap` is the space
+// between columns and between the circles in a column
+pub fn columns(circles: &[(Vec2, f32, usize)], gap: f32) -> Vec<Vec2> {
+    let mut out = vec![Vec2::ZERO; circles.len()];
+    let left = circles.iter().map(|(p, r, _)| p.x - r).fold(f32::INFINITY, f32::min);
+    let top = circles.iter().map(|(p, r, _)| p.y + r).fold(f32::NEG_INFINITY, f32::max);
+    let mut orders: Vec<usize> = circles.iter().map(|c| c.2).collect();
+    orders.sort_unstable();
+    orders.dedup();
+    let mut x = left;
+    for order in orders {
+        let mut column: Vec<usize> =
+            (0..circles.len()).filter(|i| circles[*i].2 == order).collect();
+        column.sort_by(|a, b| circles[*b].0.y.total_cmp(&circles[*a].0.y));
+        let width = column.iter().map(|i| circles[*i].1).fold(0., f32::max);
+        let mut y = top;
+        for i in column {
+            let r = circles[i].1;
+            out[i] = Vec2::new(x + width, y - r);
+            y -= 2. * r + gap;
+        }
+        x += 2. * width + gap;
+    }
+    out
+}
+
+// round to the nearest multiple of the grid size (0 is no grid)
+pub fn snap(x: f32, grid: f32) -> f32 {
+    if grid > 0. {
+        (x / grid).round() * grid
+    } else {
+        x
+    }
+}
+
+// the ends of the lines of a grid covering `view` (none when it'd be too dense)
+pub fn grid_lines(view: Rect, size: f32) -> Vec<[f32; 3]> {
+    if size <= 0. || (view.size() / size).max_element() > 200. {
+        return Vec::new();
+    }
+    let min = (view.min / size).floor().as_ivec2();
+    let max = (view.max / size).ceil().as_ivec2();
+    let (bottom, top) = (min.y as f32 * size, max.y as f32 * size);
+    let (left, right) = (min.x as f32 * size, max.x as f32 * size);
+    let mut points = Vec::new();
+    for x in min.x..=max.x {
+        let x = x as f32 * size;
+        points.extend([[x, bottom, 0.], [x, top, 0.]]);
+    }
+    for y in min.y..=max.y {
+        let y = y as f32 * size;
+        points.extend([[left, y, 0.], [right, y, 0.]]);
+    }
+    points
+}
+
+// a layered layout of a graph. circles are (center, radius, order), edges go from
+// a source circle to a sink (indices into circles). each order gets its own layers
+// (lowest on the left), inside an order a circle is in a layer after all of its
+// sources (cycles are broken somewhere). a layer's circles are sorted by where their
+// sources are, to keep connections from crossing. it starts at the top left of
+// where the circles are now, and `gap` is the space between layers and circles
+pub fn layered(circles: &[(Vec2, f32, usize)], edges: &[(usize, usize)], gap: f32) -> Vec<Vec2> {
+    let n = circles.len();
+    let mut layer = vec![0; n];
+    let mut placed = vec![false; n];
+    let mut orders: Vec<usize> = circles.iter().map(|c| c.2).collect();
+    orders.sort_unstable();
+    orders.dedup();
+    let mut layers = 0;
+    for order in orders {
+        let base = layers;
+        let sources = |v: usize| {
+            edges.iter().filter(move |(a, b)| *b == v && *a != v && circles[*a].2 == order)
+        };
+        let mut rest: Vec<usize> = (0..n).filter(|i| circles[*i].2 == order).collect();
+        while !rest.is_empty() {
+            let mut ready: Vec<usize> =
+                rest.iter().copied().filter(|v| sources(*v).all(|(a, _)| placed[*a])).collect();
+            if ready.is_empty() {
+                ready.push(rest[0]);
+            }
+            for v in &ready {
+                layer[*v] = sources(*v)
+                    .filter(|(a, _)| placed[*a])
+                    .map(|(a, _)| layer[*a] + 1)
+                    .fold(base, usize::max);
+                layers = layers.max(layer[*v] + 1);
+            }
+            for v in ready {
+                placed[v] = true;
+            }
+            rest.retain(|v| !placed[*v]);
+        }
+    }
+    // start from how they're placed now (top to bottom)
+    let mut by_y: Vec<usize> = (0..n).collect();
+    by_y.sort_by(|a, b| circles[*b].0.y.total_cmp(&circles[*a].0.y));
+    let mut rows = vec![Vec::new(); layers];
+    for v in by_y {
+        rows[layer[v]].push(v);
+    }
+    // then sort by the average place of the sources in earlier layers
+    let mut place = vec![0.; n];
+    for row in &mut rows {
+        let len = row.len() as f32;
+        let key = |(i, v): (usize, &usize)| {
+            let (sum, count) = edges
+                .iter()
+                .filter(|(a, b)| b == v && layer[*a] < layer[*v])
+                .fold((0., 0.), |(s, c), (a, _)| (s + place[*a], c + 1.));
+            if count > 0. {
+                sum / count
+            } else {
+                (i as f32 + 0.5) / len
+            }
+        };
+        let keys: Vec<f32> = row.iter().enumerate().map(key).collect();
+        let mut sorted: Vec<(f32, usize)> = keys.into_iter().zip(row.iter().copied()).collect();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        *row = sorted.into_iter().map(|(_, v)| v).collect();
+        for (i, v) in row.iter().enumerate() {
+            place[*v] = (i as f32 + 0.5) / len;
+        }
+    }
+    // columns, centered on the tallest
+    let left = circles.iter().map(|(p, r, _)| p.x - r).fold(f32::INFINITY, f32::min);
+    let top = circles.iter().map(|(p, r, _)| p.y + r).fold(f32::NEG_INFINITY, f32::max);
+    let height = |row: &Vec<usize>| row.iter().map(|v| 2. * circles[*v].1 + gap).sum::<f32>() - gap;
+    let tallest = rows.iter().map(height).fold(0., f32::max);
+    let mut out = vec![Vec2::ZERO; n];
+    let mut x = left;
+    for row in &rows {
+        let width = row.iter().map(|v| circles[*v].1).fold(0., f32::max);
+        let mut y = top - (tallest - height(row)) / 2.;
+        for v in row {
+            let r = circles[*v].1;
+            out[*v] = Vec2::new(x + width, y - r);
+            y -= 2. * r + gap;
+        }
+        x += 2. * width + gap;
+    }
+    out
+}
+
+// where a hole goes on its circle's edge, facing `toward`
+pub fn perimeter(center: Vec2, radius: f32, toward: Vec2) -> Vec2 {
+    center + (toward - center).try_normalize().unwrap_or(Vec2::X) * radius
+}
+
+// -------------------- connections --------------------
+
+// a bezier from `from` to `to` (circles' centers and radii). it leaves the first
+// circle to the right and comes into the second from the left, like the signal flow
+pub fn curve(from: Vec2, from_r: f32, to: Vec2, to_r: f32) -> Vec<Vec2> {
+    let d = from.distance(to) / 2.;
+    let (c1, c2) = (from + Vec2::X * d, to - Vec2::X * d);
+    let (a, b) = (perimeter(from, from_r, c1), perimeter(to, to_r, c2));
+    (0..=24)
+        .map(|i| {
+            let t = i as f32 / 24.;
+            let u = 1. - t;
+            a * u * u * u + c1 * 3. * u * u * t + c2 * 3. * u * t * t + b * t * t * t
+        })
+        .collect()
+}
+
+// an orthogonal path from `from` to `to` that goes around the obstacles where it
+// can (and crosses as few as it can where it can't). it leaves and enters going
+// horizontally, and `margin` is how far it stays from the obstacles
+pub fn route(from: Vec2, to: Vec2, obstacles: &[Rect], margin: f32) -> Vec<Vec2> {
+    let mut xs = vec![(from.x + to.x) / 2.];
+    let mut ys = vec![(from.y + to.y) / 2.];
+    for r in obstacles {
+        xs.extend([r.min.x - margin, r.max.x + margin]);
+        ys.extend([r.min.y - margin, r.max.y + margin]);
+    }
+    let (out, into) = (from.x + margin, to.x - margin);
+    // across at some x, or out, over or under at some y, and back in
+    let paths = xs
+        .iter()
+        .map(|x| vec![from, Vec2::new(*x, from.y), Vec2::new(*x, to.y), to])
+        .chain(ys.iter().map(|y| {
+            vec![
+                from,
+                Vec2::new(out, from.y),
+                Vec2::new(out, *y),
+                Vec2::new(into, *y),
+                Vec2::new(into, to.y),
+                to,
+            ]
+        }));
+    let cost = |path: &Vec<Vec2>| {
+        let hits = path
+            .windows(2)
+            .map(|s| obstacles.iter().filter(|r| crosses(s[0], s[1], r)).count())
+            .sum::<usize>();
+        let length = path.windows(2).map(|s| s[0].distance(s[1])).sum::<f32>();
+        (hits, length)
+    };
+    let best = paths
+        .map(|p| (cost(&p), p))
+        .min_by(|(a, _), (b, _)| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)))
+        .unwrap()
+        .1;
+    // without the corners that aren't
+    let mut path: Vec<Vec2> = Vec::new();
+    for p in best {
+        if path.last() == Some(&p) {
+            continue;
+        }
+        if let [.., a, b] = path[..] {
+            if (a.x == b.x && b.x == p.x) || (a.y == b.y && b.y == p.y) {
+                path.pop();
+            }
+        }
+        path.push(p);
+    }
+    path
+}
+
+// does the (horizontal or vertical) segment go through the inside of the rect
+fn crosses(a: Vec2, b: Vec2, r: &Rect) -> bool {
+    let s = Rect::from_corners(a, b);
+    s.min.x < r.max.x && s.max.x > r.min.x && s.min.y < r.max.y && s.max.y > r.min.y
+}
+
+// move the ends of a path in along it (to start and end at the holes' edges)
+pub fn trim(path: &mut [Vec2], start: f32, end: f32) {
+    let n = path.len();
+    if n < 2 {
+        return;
+    }
+    let d = (path[1] - path[0]).clamp_length_max(start);
+    path[0] += d;
+    let d = (path[n - 2] - path[n - 1]).clamp_length_max(end);
+    path[n - 1] += d;
+}
+
+// the vertices of a triangle strip along the path, `from` wide at the start
+// narrowing to `to` at the end (so it's an arrow like the straight ones)
+pub fn stroke(path: &[Vec2], from: f32, to: f32) -> Vec<[f32; 3]> {
+    let length = path.windows(2).map(|s| s[0].distance(s[1])).sum::<f32>().max(f32::EPSILON);
+    let normal = |i: usize| (path[i + 1] - path[i]).perp().normalize_or_zero();
+    let mut walked = 0.;
+    let mut points = Vec::new();
+    for (i, p) in path.iter().enumerate() {
+        if i > 0 {
+            walked += path[i - 1].distance(*p);
+        }
+        let half = (from + (to - from) * walked / length) / 2.;
+        let n = if path.len() < 2 {
+            Vec2::ZERO
+        } else if i == 0 {
+            normal(0)
+        } else if i == path.len() - 1 {
+            normal(i - 1)
+        } else {
+            // mitered corners
+            let (a, b) = (normal(i - 1), normal(i));
+            let m = (a + b).normalize_or_zero();
+            m / m.dot(a).max(0.25)
+        };
+        points.push((*p + n * half).extend(0.).to_array());
+        points.push((*p - n * half).extend(0.).to_array());
+    }
+    points
+}
@@ -0,0 +1,558 @@
+// ableton link compatible tempo and beat sync over the lan
+// implements the discovery (_asdp_v1) and measurement (_link_v1) protocols. enough
+// to find peers, join the oldest session, follow its timeline, and announce ours.
+// start/stop sync isn't done, play and stop stay local
+
+use bevy::prelude::*;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hasher},
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::components::TransportRes;
+
+pub const GROUP: Ipv4Addr = Ipv4Addr::new(224, 76, 78, 75);
+pub const PORT: u16 = 20808;
+
+const DISCOVERY: &[u8; 8] = b"_asdp_v\x01";
+const MEASUREMENT: &[u8; 8] = b"_link_v\x01";
+
+pub const ALIVE: u8 = 1;
+pub const RESPONSE: u8 = 2;
+pub const BYEBYE: u8 = 3;
+pub const PING: u8 = 1;
+pub const PONG: u8 = 2;
+
+// seconds a peer is remembered without hearing from it
+const TTL: u8 = 5;
+// send alive messages this often (ttl / 20, like link does)
+const ALIVE_PERIOD: Duration = Duration::from_millis(250);
+// data points collected when measuring another session's clock
+const MEASUREMENT_POINTS: usize = 100;
+const MEASUREMENT_TIMEOUT: Duration = Duration::from_millis(50);
+const MEASUREMENT_RETRIES: u32 = 5;
+// phase difference (in beats) that's corrected
+const PHASE_TOLERANCE: f64 = 0.02;
+
+const SESSION: u32 = u32::from_be_bytes(*b"sess");
+const TIMELINE: u32 = u32::from_be_bytes(*b"tmln");
+const ENDPOINT: u32 = u32::from_be_bytes(*b"mep4");
+const HOST_TIME: u32 = u32::from_be_bytes(*b"__ht");
+const GHOST_TIME: u32 = u32::from_be_bytes(*b"__gt");
+const PREV_GHOST_TIME: u32 = u32::from_be_bytes(*b"_pgt");
+
+pub type NodeId = [u8; 8];
+
+/// tempo and beat position of a session
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timeline {
+    /// microseconds per beat
+    pub tempo: i64,
+    /// microbeats
+    pub beat_origin: i64,
+    /// ghost time (microseconds) at the beat origin
+    pub time_origin: i64,
+}
+
+impl Timeline {
+    pub fn new(bpm: f32, beat: f64, ghost: i64) -> Self {
+        Timeline {
+            tempo: (60e6 / bpm.max(1.) as f64).round() as i64,
+            beat_origin: (beat * 1e6).round() as i64,
+            time_origin: ghost,
+        }
+    }
+
+    pub fn bpm(&self) -> f32 {
+        // tempo is whole microseconds, so 90 would come back as 89.99995
+        ((60e9 / self.tempo as f64).round() / 1000.) as f32
+    }
+
+    pub fn beat_at(&self, ghost: i64) -> f64 {
+        self.beat_origin as f64 / 1e6 + (ghost - self.time_origin) as f64 / self.tempo as f64
+    }
+}
+
+/// what a peer tells us about itself in alive and response messages
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerState {
+    pub ident: NodeId,
+    pub session: NodeId,
+    pub timeline: Timeline,
+    pub endpoint: Option<SocketAddrV4>,
+}
+
+fn entry(buf: &mut Vec<u8>, key: u32, value: &[u8]) {
+    buf.extend(key.to_be_bytes());
+    buf.extend((value.len() as u32).to_be_bytes());
+    buf.extend(value);
+}
+
+fn entries(mut buf: &[u8]) -> Vec<(u32, &[u8])> {
+    let mut out = Vec::new();
+    while buf.len() >= 8 {
+        let key = u32::from_be_bytes(buf[0..4].try_into().unwrap());
+        let len = u32::from_be_bytes(buf[4..8].try_into().unwrap()) as usize;
+        let Some(value) = buf.get(8..8 + len) else { break };
+        out.push((key, value));
+        buf = &buf[8 + len..];
+    }
+    out
+}
+
+fn int(value: &[u8], at: usize) -> Option<i64> {
+    Some(i64::from_be_bytes(value.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn find(payload: &[(u32, &[u8])], key: u32) -> Option<i64> {
+    payload.iter().find(|e| e.0 == key).and_then(|e| int(e.1, 0))
+}
+
+pub fn encode_discovery(kind: u8, state: &PeerState) -> Vec<u8> {
+    let mut buf = DISCOVERY.to_vec();
+    buf.push(kind);
+    buf.push(if kind == BYEBYE { 0 } else { TTL });
+    buf.extend(0u16.to_be_bytes());
+    buf.extend(state.ident);
+    if kind != BYEBYE {
+        let t = &state.timeline;
+        let timeline = [t.tempo, t.beat_origin, t.time_origin].map(i64::to_be_bytes).concat();
+        entry(&mut buf, TIMELINE, &timeline);
+        entry(&mut buf, SESSION, &state.session);
+        if let Some(ep) = state.endpoint {
+            entry(&mut buf, ENDPOINT, &[&ep.ip().octets()[..], &ep.port().to_be_bytes()].concat());
+        }
+    }
+    buf
+}
+
+/// message type, ttl, and the sender's state
+/// (a byebye only has the ident, the rest is left empty)
+pub fn decode_discovery(buf: &[u8]) -> Option<(u8, u8, PeerState)> {
+    if buf.get(..8)? != DISCOVERY || buf.len() < 20 {
+        return None;
+    }
+    let (kind, ttl) = (buf[8], buf[9]);
+    let ident = buf[12..20].try_into().ok()?;
+    let mut state = PeerState {
+        ident,
+        session: [0; 8],
+        timeline: Timeline { tempo: 500000, beat_origin: 0, time_origin: 0 },
+        endpoint: None,
+    };
+    if kind == BYEBYE {
+        return Some((kind, ttl, state));
+    }
+    let payload = entries(&buf[20..]);
+    let mut timeline = None;
+    for (key, value) in payload {
+        match key {
+            SESSION => state.session = value.get(..8)?.try_into().ok()?,
+            TIMELINE => {
+                timeline = Some(Timeline {
+                    tempo: int(value, 0)?,
+                    beat_origin: int(value, 8)?,
+                    time_origin: int(value, 16)?,
+                })
+            }
+            ENDPOINT if value.len() == 6 => {
+                let ip = Ipv4Addr::new(value[0], value[1], value[2], value[3]);
+                let port = u16::from_be_bytes([value[4], value[5]]);
+                state.endpoint = Some(SocketAddrV4::new(ip, port));
+            }
+            _ => {}
+        }
+    }
+    state.timeline = timeline.filter(|t| t.tempo > 0)?;
+    Some((kind, ttl, state))
+}
+
+pub fn encode_ping(host: i64, prev_ghost: Option<i64>) -> Vec<u8> {
+    let mut buf = MEASUREMENT.to_vec();
+    buf.push(PING);
+    entry(&mut buf, HOST_TIME, &host.to_be_bytes());
+    if let Some(prev) = prev_ghost {
+        entry(&mut buf, PREV_GHOST_TIME, &prev.to_be_bytes());
+    }
+    buf
+}
+
+/// reply to a ping, echoing its payload
+pub fn encode_pong(session: &NodeId, ghost: i64, ping_payload: &[u8]) -> Vec<u8> {
+    let mut buf = MEASUREMENT.to_vec();
+    buf.push(PONG);
+    entry(&mut buf, SESSION, session);
+    entry(&mut buf, GHOST_TIME, &ghost.to_be_bytes());
+    buf.extend(ping_payload);
+    buf
+}
+
+/// a pong's session, ghost time, and the host time and previous ghost time we sent
+pub fn decode_pong(buf: &[u8]) -> Option<(NodeId, i64, i64, Option<i64>)> {
+    if buf.get(..8)? != MEASUREMENT || *buf.get(8)? != PONG {
+        return None;
+    }
+    let payload = entries(&buf[9..]);
+    let session = payload.iter().find(|e| e.0 == SESSION)?.1.get(..8)?.try_into().ok()?;
+    let ghost = find(&payload, GHOST_TIME)?;
+    let host = find(&payload, HOST_TIME)?;
+    Some((session, ghost, host, find(&payload, PREV_GHOST_TIME)))
+}
+
+/// samples of (ghost time - host time) from a ping/pong exchange
+pub fn measure(ghost: i64, host: i64, prev_ghost: Option<i64>, now: i64) -> Vec<f64> {
+    let mut data = vec![ghost as f64 - (host + now) as f64 * 0.5];
+    if let Some(prev) = prev_ghost {
+        data.push((ghost + prev) as f64 * 0.5 - host as f64);
+    }
+    data
+}
+
+pub fn median(data: &mut [f64]) -> f64 {
+    data.sort_by(f64::total_cmp);
+    let n = data.len();
+    if n.is_multiple_of(2) {
+        (data[n / 2 - 1] + data[n / 2]) * 0.5
+    } else {
+        data[n / 2]
+    }
+}
+
+/// should we leave our session for another one? (the older one wins,
+/// if they're close the smaller id does)
+pub fn should_join(ours: (NodeId, i64), theirs: (NodeId, i64)) -> bool {
+    let diff = theirs.1 - ours.1;
+    diff > 500000 || (diff.abs() < 500000 && theirs.0 < ours.0)
+}
+
+struct Measurement {
+    session: NodeId,
+    to: SocketAddrV4,
+    data: Vec<f64>,
+    sent: Instant,
+    retries: u32,
+}
+
+struct Peer {
+    state: PeerState,
+    expires: Instant,
+}
+
+// the running part, exists while link is enabled
+struct State {
+    discovery: Option<UdpSocket>,
+    unicast: UdpSocket,
+    endpoint: SocketAddrV4,
+    epoch: Instant,
+    ident: NodeId,
+    session: NodeId,
+    // ghost time = host time + offset
+    offset: i64,
+    timeline: Timeline,
+    // the tempo we last set on (or saw on) the transport
+    bpm: f32,
+    peers: HashMap<NodeId, Peer>,
+    // measured offsets of sessions we didn't join
+    sessions: HashMap<NodeId, i64>,
+    measurement: Option<Measurement>,
+    last_alive: Option<Instant>,
+    // when we last changed the timeline, so alives already on their way don't undo it
+    changed: Option<Instant>,
+}
+
+#[derive(Resource, Default)]
+pub struct Link {
+    pub enabled: bool,
+    state: Option<State>,
+}
+
+impl Link {
+    /// number of peers in our session
+    pub fn peers(&self) -> usize {
+        self.state
+            .as_ref()
+            .map_or(0, |s| s.peers.values().filter(|p| p.state.session == s.session).count())
+    }
+}
+
+fn random_id() -> NodeId {
+    // printable characters, like link's ids
+    let mut n = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    let mut id = [0; 8];
+    for c in &mut id {
+        *c = b'!' + (n % 94) as u8;
+        n /= 94;
+    }
+    id
+}
+
+#[cfg(unix)]
+fn bind_shared(port: u16) -> std::io::Result<UdpSocket> {
+    use std::os::fd::FromRawFd;
+    // link apps on the same machine all listen on the discovery port
+    // SAFETY: no pointers, the result is checked before it's used
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: a new socket nothing else owns. the UdpSocket closes it, also on the errors below
+    let socket = unsafe { UdpSocket::from_raw_fd(fd) };
+    let one: libc::c_int = 1;
+    let size = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let one_ptr = &one as *const libc::c_int as *const libc::c_void;
+    for option in [libc::SO_REUSEADDR, libc::SO_REUSEPORT] {
+        // SAFETY: fd is open, one_ptr points at a c_int (of the given size) that outlives the call
+        if unsafe { libc::setsockopt(fd, libc::SOL_SOCKET, option, one_ptr, size) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    // SAFETY: sockaddr_in is plain data, all zeroes is the any address
+    let mut addr: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+    addr.sin_family = libc::AF_INET as libc::sa_family_t;
+    addr.sin_port = port.to_be();
+    let addr_ptr = &addr as *const libc::sockaddr_in as *const libc::sockaddr;
+    let addr_size = std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    // SAFETY: fd is open, addr_ptr points at a sockaddr_in (of the given size) that outlives the call
+    if unsafe { libc::bind(fd, addr_ptr, addr_size) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(socket)
+}
+
+#[cfg(not(unix))]
+fn bind_shared(port: u16) -> std::io::Result<UdpSocket> {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))
+}
+
+// the address other machines reach us at
+fn local_ip() -> Ipv4Addr {
+    let ip = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|s| s.connect((GROUP, PORT)).and_then(|_| s.local_addr()));
+    match ip {
+        Ok(SocketAddr::V4(addr)) if !addr.ip().is_unspecified() => *addr.ip(),
+        _ => Ipv4Addr::LOCALHOST,
+    }
+}
+
+impl State {
+    fn new(bpm: f32, beat: f64) -> Option<Self> {
+        let unicast = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+        unicast.set_nonblocking(true).ok()?;
+        let port = unicast.local_addr().ok()?.port();
+        let discovery = bind_shared(PORT)
+            .and_then(|s| {
+                s.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED)?;
+                s.set_nonblocking(true)?;
+                Ok(s)
+            })
+            .map_err(|e| {
+                warn!("link: can't listen on port {} ({}), only responses are read", PORT, e)
+            })
+            .ok();
+        let ident = random_id();
+        Some(State {
+            discovery,
+            unicast,
+            endpoint: SocketAddrV4::new(local_ip(), port),
+            epoch: Instant::now(),
+            ident,
+            session: ident,
+            offset: 0,
+            timeline: Timeline::new(bpm, beat, 0),
+            bpm,
+            peers: HashMap::new(),
+            sessions: HashMap::new(),
+            measurement: None,
+            last_alive: None,
+            changed: None,
+        })
+    }
+
+    fn host(&self) -> i64 {
+        self.epoch.elapsed().as_micros() as i64
+    }
+
+    fn ghost(&self) -> i64 {
+        self.host() + self.offset
+    }
+
+    fn peer_state(&self) -> PeerState {
+        PeerState {
+            ident: self.ident,
+            session: self.session,
+            timeline: self.timeline,
+            endpoint: Some(self.endpoint),
+        }
+    }
+
+    fn send(&self, kind: u8, to: SocketAddr) {
+        let _ = self.unicast.send_to(&encode_discovery(kind, &self.peer_state()), to);
+    }
+
+    fn broadcast(&mut self, kind: u8) {
+        self.send(kind, SocketAddr::V4(SocketAddrV4::new(GROUP, PORT)));
+        self.last_alive = Some(Instant::now());
+    }
+
+    fn receive(&mut self) {
+        let mut buf = [0u8; 512];
+        let mut messages = Vec::new();
+        for socket in self.discovery.iter().chain([&self.unicast]) {
+            while let Ok((n, from)) = socket.recv_from(&mut buf) {
+                messages.push((buf[..n].to_vec(), from));
+            }
+        }
+        for (msg, from) in messages {
+            if let Some((kind, ttl, state)) = decode_discovery(&msg) {
+                self.on_discovery(kind, ttl, state, from);
+            } else if msg.get(..8) == Some(MEASUREMENT) {
+                self.on_measurement(&msg, from);
+            }
+        }
+    }
+
+    fn on_discovery(&mut self, kind: u8, ttl: u8, state: PeerState, from: SocketAddr) {
+        if state.ident == self.ident {
+            return;
+        }
+        if kind == BYEBYE {
+            self.peers.remove(&state.ident);
+            return;
+        }
+        if kind == ALIVE {
+            self.send(RESPONSE, from);
+        }
+        if state.session == self.session {
+            if self.changed.is_none_or(|t| t.elapsed() > ALIVE_PERIOD * 2) {
+                self.timeline = state.timeline;
+            }
+        } else if !self.sessions.contains_key(&state.session)
+            && self.measurement.as_ref().is_none_or(|m| m.session != state.session)
+        {
+            if let Some(to) = state.endpoint {
+                self.measurement = Some(Measurement {
+                    session: state.session,
+                    to,
+                    data: Vec::new(),
+                    sent: Instant::now(),
+                    retries: 0,
+                });
+                let _ = self.unicast.send_to(&encode_ping(self.host(), None), to);
+            }
+        }
+        let expires = Instant::now() + Duration::from_secs(ttl as u64);
+        self.peers.insert(state.ident, Peer { state, expires });
+    }
+
+    fn on_measurement(&mut self, msg: &[u8], from: SocketAddr) {
+        // somebody is measuring us
+        if msg.get(8) == Some(&PING) && msg.len() <= 9 + 32 {
+            let pong = encode_pong(&self.session, self.ghost(), &msg[9..]);
+            let _ = self.unicast.send_to(&pong, from);
+            return;
+        }
+        let Some((session, ghost, host, prev)) = decode_pong(msg) else { return };
+        let now = self.host();
+        let Some(m) = &mut self.measurement else { return };
+        if m.session != session {
+            return;
+        }
+        m.data.extend(measure(ghost, host, prev, now));
+        m.sent = Instant::now();
+        m.retries = 0;
+        if m.data.len() < MEASUREMENT_POINTS {
+            let _ = self.unicast.send_to(&encode_ping(now, Some(ghost)), m.to);
+            return;
+        }
+        let offset = median(&mut m.data) as i64;
+        self.measurement = None;
+        let now = self.host();
+        if should_join((self.session, now + self.offset), (session, now + offset)) {
+            // take the timeline of one of its peers
+            let timeline = self.peers.values().find(|p| p.state.session == session);
+            if let Some(timeline) = timeline.map(|p| p.state.timeline) {
+                info!("link: joined session with {} bpm", timeline.bpm());
+                self.sessions.insert(self.session, self.offset);
+                self.sessions.remove(&session);
+                self.session = session;
+                self.offset = offset;
+                self.timeline = timeline;
+                self.broadcast(ALIVE);
+            }
+        } else {
+            self.sessions.insert(session, offset);
+        }
+    }
+
+    fn update(&mut self) {
+        let now = Instant::now();
+        let host = self.host();
+        self.peers.retain(|_, p| p.expires > now);
+        if let Some(m) = &mut self.measurement {
+            if now - m.sent > MEASUREMENT_TIMEOUT {
+                if m.retries >= MEASUREMENT_RETRIES {
+                    self.measurement = None;
+                } else {
+                    m.retries += 1;
+                    m.sent = now;
+                    let _ = self.unicast.send_to(&encode_ping(host, None), m.to);
+                }
+            }
+        }
+        if self.last_alive.is_none_or(|t| now - t > ALIVE_PERIOD) {
+            self.broadcast(ALIVE);
+        }
+    }
+}
+
+pub fn link_sync(mut link: ResMut<Link>, transport: Res<TransportRes>) {
+    let transport = &transport.0;
+    if !link.enabled {
+        if let Some(mut state) = link.state.take() {
+            state.broadcast(BYEBYE);
+            info!("link: off");
+        }
+        return;
+    }
+    if link.state.is_none() {
+        link.state = State::new(transport.bpm(), transport.position());
+        if link.state.is_none() {
+            warn!("link: couldn't open a socket");
+            link.enabled = false;
+            return;
+        }
+        info!("link: on");
+    }
+    let alone = link.peers() == 0;
+    let state = link.state.as_mut().unwrap();
+    let previous = state.timeline;
+    state.receive();
+    state.update();
+    let ghost = state.ghost();
+    if transport.bpm() != state.bpm {
+        // changed here, everyone follows
+        let beat = state.timeline.beat_at(ghost);
+        state.timeline = Timeline::new(transport.bpm(), beat, ghost);
+        state.bpm = transport.bpm();
+        state.changed = Some(Instant::now());
+        state.broadcast(ALIVE);
+    } else if state.timeline != previous {
+        state.bpm = state.timeline.bpm();
+        transport.set_bpm(state.bpm);
+    }
+    if alone {
+        // nobody to follow, the timeline follows us
+        state.timeline = Timeline::new(state.bpm, transport.position(), ghost);
+    } else if transport.playing() {
+        // align the phase within a bar
+        let quantum = transport.meter() as f64;
+        let pos = transport.position();
+        let mut delta = state.timeline.beat_at(ghost) - pos;
+        delta -= (delta / quantum).round() * quantum;
+        if delta.abs() > PHASE_TOLERANCE {
+            transport.set_position(pos + delta);
+        }
+    }
+}
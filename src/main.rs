@@ -2,23 +2,24 @@
 
 use bevy::{
     asset::ron::Deserializer,
-    color::Hsla,
     core_pipeline::{
         bloom::{BloomCompositeMode, BloomSettings},
         tonemapping::Tonemapping,
     },
+    input::InputSystem,
     prelude::*,
     render::view::RenderLayers,
     scene::{serde::SceneDeserializer, SceneInstance},
     sprite::Mesh2dHandle,
     tasks::IoTaskPool,
     utils::Duration,
-    window::{FileDragAndDrop::DroppedFile, WindowMode},
+    window::{ExitCondition, FileDragAndDrop::DroppedFile, WindowMode},
     winit::{UpdateMode, WinitSettings},
 };
 
 use bevy_pancam::{PanCam, PanCamPlugin};
 use copypasta::{ClipboardContext, ClipboardProvider};
+use fundsp::net::Net;
 use serde::de::DeserializeSeed;
 use std::{fs::File, io::Write};
 
@@ -28,19 +29,31 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 mod audio;
 mod circles;
 mod commands;
+mod completion;
 mod components;
+mod config;
 mod connections;
 mod cursor;
 mod functions;
+mod graph;
+mod jack;
+mod layout;
+mod link;
+mod minimap;
 mod nodes;
 mod osc;
 mod process;
+mod profile;
+mod scope;
+mod views;
 use {
-    audio::*, circles::*, commands::*, components::*, connections::*, cursor::*, functions::*,
-    osc::*, process::*,
+    audio::*, circles::*, commands::*, components::*, config::*, connections::*, cursor::*,
+    functions::*, graph::*, jack::*, link::*, minimap::*, osc::*, process::*, profile::*, scope::*,
+    views::*,
 };
 
 fn main() {
+    let (config, warnings) = Config::load(std::env::args().skip(1));
     let mut app = App::new();
 
     app.add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -49,34 +62,56 @@ fn main() {
             title: String::from("awawawa"),
             ..default()
         }),
+        // the other windows are only views
+        exit_condition: ExitCondition::OnPrimaryClosed,
         ..default()
     }))
     .add_plugins(PanCamPlugin)
     // osc
-    .insert_resource(OscSender { host: "127.0.0.1".to_string(), port: 1729 })
+    .insert_resource(OscSender { host: config.osc.host.clone(), port: config.osc.port })
     .insert_resource(OscReceiver { socket: None })
     // settings
     .insert_resource(WinitSettings {
-        focused_mode: UpdateMode::reactive_low_power(Duration::from_secs_f64(1.0 / 60.0)),
-        unfocused_mode: UpdateMode::reactive_low_power(Duration::from_secs_f64(1.0 / 30.0)),
+        focused_mode: UpdateMode::reactive_low_power(Duration::from_secs_f64(
+            1.0 / config.update.0,
+        )),
+        unfocused_mode: UpdateMode::reactive_low_power(Duration::from_secs_f64(
+            1.0 / config.update.1,
+        )),
     })
-    .insert_resource(ClearColor(Color::hsla(0., 0., 0., 1.)))
-    .insert_resource(DefaultDrawColor(Hsla::new(330., 1., 0.5, 1.)))
-    .insert_resource(DefaultDrawVerts(4))
-    .insert_resource(HighlightColor(Hsla::new(0.0, 1.0, 0.5, 1.)))
-    .insert_resource(ConnectionColor(Hsla::new(0., 1., 1., 0.7)))
-    .insert_resource(ConnectionWidth(4.))
-    .insert_resource(CommandColor(Hsla::new(0., 0., 0.7, 1.)))
-    .insert_resource(IndicatorColor(Hsla::new(0., 1., 0.5, 0.3)))
-    .insert_resource(TextSize(0.1))
-    .insert_resource(NodeLimit(500))
+    .insert_resource(ClearColor(config.colors.clear.into()))
+    .insert_resource(DefaultDrawColor(config.colors.draw))
+    .insert_resource(DefaultDrawVerts(config.draw_verts))
+    .insert_resource(HighlightColor(config.colors.highlight))
+    .insert_resource(ConnectionColor(config.colors.connection))
+    .insert_resource(ConnectionWidth(config.connection_width))
+    .insert_resource(CommandColor(config.colors.command))
+    .insert_resource(IndicatorColor(config.colors.indicator))
+    .insert_resource(TextSize(config.text_size))
+    .insert_resource(NodeLimit(config.node_limit))
     .insert_resource(Version(format!("{} {}", env!("CARGO_PKG_VERSION"), env!("COMMIT_HASH"))))
     .insert_resource(Msaa::Sample4)
     // audio
+    .init_resource::<TransportRes>()
+    .init_resource::<Timing>()
+    .init_resource::<Link>()
+    .init_resource::<Profiler>()
+    .init_resource::<Parts>()
+    .init_resource::<OutGraph>()
+    .init_resource::<AudioStatusRes>()
+    .init_resource::<InputRingRes>()
+    .add_systems(Update, measure_load)
+    .add_systems(Update, heat_overlay.after(delete_selected))
+    .add_systems(Update, draw_scopes.after(delete_selected))
+    .add_systems(Update, link_sync)
     .add_systems(Startup, default_out_device)
     .add_systems(Update, set_out_device)
     .add_systems(Startup, default_in_device)
     .add_systems(Update, set_in_device)
+    .init_resource::<Jack>()
+    .add_systems(Update, jack_mode)
+    .add_systems(Update, reconnect)
+    .add_systems(Update, report_audio_status)
     // main
     .insert_resource(SystemClipboard(ClipboardContext::new().unwrap()))
     .insert_resource(PasteChannel(crossbeam_channel::bounded::<String>(1)))
@@ -89,10 +124,23 @@ fn main() {
     .add_systems(Update, post_load)
     .add_systems(Update, file_drag_and_drop)
     .add_systems(Update, update_indicator)
+    // minimap and bookmarks
+    .init_resource::<Minimap>()
+    .init_resource::<Bookmarks>()
+    .add_systems(Startup, spawn_minimap)
+    .add_systems(Update, update_minimap)
+    .init_resource::<Grid>()
+    .add_systems(Update, draw_grid)
+    .add_systems(PreUpdate, minimap_click.after(InputSystem))
+    // other windows
+    .add_systems(Update, sync_views)
+    .add_systems(PreUpdate, ignore_view_clicks.after(InputSystem).before(minimap_click))
     .init_state::<Mode>()
     // cursor
     .insert_resource(CursorInfo::default())
     .add_systems(Update, update_cursor_info)
+    .init_resource::<Pointers>()
+    .add_systems(Update, update_pointers)
     // circles
     .insert_resource(ClickedOnSpace(true))
     .insert_resource(ShowInfoText(true, false))
@@ -100,20 +148,52 @@ fn main() {
     .init_resource::<DragModes>()
     .add_systems(Update, spawn_circles.run_if(in_state(Mode::Draw)))
     .add_systems(Update, update_selection.after(update_cursor_info).run_if(in_state(Mode::Edit)))
-    .add_systems(Update, move_selected.after(update_selection).run_if(in_state(Mode::Edit)))
-    .add_systems(Update, update_color.after(update_selection).run_if(in_state(Mode::Edit)))
+    .add_systems(
+        Update,
+        move_selected
+            .after(update_selection)
+            .run_if(in_state(Mode::Edit).and_then(not(typing_command))),
+    )
+    .add_systems(
+        Update,
+        update_color
+            .after(update_selection)
+            .run_if(in_state(Mode::Edit).and_then(not(typing_command))),
+    )
     .add_systems(Update, update_mat)
-    .add_systems(Update, update_radius.after(update_selection).run_if(in_state(Mode::Edit)))
-    .add_systems(Update, update_vertices.after(update_selection).run_if(in_state(Mode::Edit)))
+    .add_systems(
+        Update,
+        update_radius
+            .after(update_selection)
+            .run_if(in_state(Mode::Edit).and_then(not(typing_command))),
+    )
+    .add_systems(
+        Update,
+        update_vertices
+            .after(update_selection)
+            .run_if(in_state(Mode::Edit).and_then(not(typing_command))),
+    )
     .add_systems(Update, update_mesh.after(update_vertices).after(command_parser))
-    .add_systems(Update, update_num.after(update_selection).run_if(in_state(Mode::Edit)))
+    .add_systems(
+        Update,
+        update_num
+            .after(update_selection)
+            .run_if(in_state(Mode::Edit).and_then(not(typing_command))),
+    )
     .add_systems(Update, highlight_selected.after(delete_selected))
-    .add_systems(Update, open_after_drag.run_if(in_state(Mode::Edit)))
+    .add_systems(Update, mark_bad_ops.after(delete_selected))
+    .add_systems(Update, open_after_drag.run_if(in_state(Mode::Edit).and_then(not(typing_command))))
     .add_systems(PreUpdate, transform_highlights)
-    .add_systems(Update, rotate_selected.after(update_selection).run_if(in_state(Mode::Edit)))
+    .add_systems(
+        Update,
+        rotate_selected
+            .after(update_selection)
+            .run_if(in_state(Mode::Edit).and_then(not(typing_command))),
+    )
     .add_systems(Update, delete_selected.run_if(on_event::<DeleteCommand>()))
     .add_systems(PreUpdate, update_info_text)
     .add_systems(Update, spawn_info_text)
+    .add_systems(Update, update_comments.after(delete_selected))
     // events
     .add_event::<SaveCommand>()
     .add_event::<CopyCommand>()
@@ -126,7 +206,10 @@ fn main() {
     .add_systems(Update, connect.run_if(in_state(Mode::Connect)))
     .add_systems(Update, connect_targets)
     .add_systems(Update, target.run_if(in_state(Mode::Connect)))
+    .init_resource::<ConnectionStyle>()
+    .init_resource::<LinkColors>()
     .add_systems(PreUpdate, update_connection_arrows)
+    .add_systems(Update, color_connections)
     // process
     .init_resource::<Queue>()
     .init_resource::<LoopQueue>()
@@ -135,7 +218,15 @@ fn main() {
     .add_systems(PostUpdate, prepare_loop_queue.after(sort_by_order).before(process))
     .add_systems(PostUpdate, process)
     // commands
+    .init_resource::<CommandHistory>()
+    .init_resource::<CommandCursor>()
+    .init_resource::<PendingCommands>()
+    .init_resource::<SearchResults>()
+    .add_systems(Startup, (load_history, source_rc))
+    .add_systems(Update, run_pending_commands.before(command_parser))
     .add_systems(Update, command_parser)
+    .add_systems(Update, command_cursor.after(command_parser))
+    .add_systems(Update, completion_popup.after(command_parser))
     // type registry
     .register_type::<DragModes>()
     .register_type::<Queue>()
@@ -150,6 +241,10 @@ fn main() {
     .register_type::<WhiteHole>()
     .register_type::<Vertices>()
     .register_type::<Targets>()
+    .register_type::<Comment>()
+    .register_type::<Locked>()
+    .register_type::<Bookmark>()
+    .register_type::<Bookmarks>()
     .register_type::<LostWH>()
     .register_type::<DefaultDrawColor>()
     .register_type::<DefaultDrawVerts>()
@@ -162,8 +257,19 @@ fn main() {
     .register_type::<Version>()
     .register_type::<Holes>()
     .register_type::<NodeLimit>()
+    .register_type::<Grid>()
+    .register_type::<ConnectionStyle>()
+    .register_type::<LinkColors>()
     .register_type::<ShowInfoText>();
 
+    for warning in warnings {
+        warn!("quartz.toml: {}", warning);
+    }
+    if let Some(port) = config.osc.listen {
+        app.world_mut().resource_mut::<OscReceiver>().init(port);
+    }
+    app.insert_resource(config);
+
     #[cfg(feature = "inspector")]
     app.add_plugins(WorldInspectorPlugin::new());
 
@@ -177,6 +283,8 @@ fn setup(
     command_color: Res<CommandColor>,
     connection_color: Res<ConnectionColor>,
     indicator_color: Res<IndicatorColor>,
+    config: Res<Config>,
+    asset_server: Res<AssetServer>,
 ) {
     // camera
     commands.spawn((
@@ -187,14 +295,15 @@ fn setup(
             ..default()
         },
         BloomSettings {
-            intensity: 0.2,
-            low_frequency_boost: 0.6,
-            low_frequency_boost_curvature: 0.4,
+            intensity: config.bloom.intensity,
+            low_frequency_boost: config.bloom.low_frequency_boost,
+            low_frequency_boost_curvature: config.bloom.low_frequency_boost_curvature,
             composite_mode: BloomCompositeMode::Additive,
             ..default()
         },
         PanCam { enabled: false, max_scale: Some(80.), min_scale: 0.005, ..default() },
         RenderLayers::from_layers(&[0, 1, 2, 3, 4]),
+        MainCamera,
     ));
 
     // command line
@@ -222,6 +331,32 @@ fn setup(
                 CommandText,
             ));
         });
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle { font_size: 13.0, color: command_color.0.into(), ..default() },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(25.),
+            left: Val::Px(5.),
+            ..default()
+        }),
+        CompletionText,
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle { font_size: 13.0, color: command_color.0.into(), ..default() },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(5.),
+            left: Val::Px(5.),
+            ..default()
+        }),
+        CommandCursorText,
+    ));
 
     // selecting / drawing / connecting indicator
     let id = commands
@@ -244,6 +379,11 @@ fn setup(
     commands.insert_resource(ConnectionMat(
         materials.add(ColorMaterial::from_color(connection_color.0)),
     ));
+
+    // startup scene
+    if let Some(scene) = &config.scene {
+        commands.spawn(DynamicSceneBundle { scene: asset_server.load(scene.clone()), ..default() });
+    }
 }
 
 fn toggle_pan(mut query: Query<&mut PanCam>, keyboard_input: Res<ButtonInput<KeyCode>>) {
@@ -254,12 +394,15 @@ fn toggle_pan(mut query: Query<&mut PanCam>, keyboard_input: Res<ButtonInput<Key
     }
 }
 
+// the focused window (so a view can go fullscreen on another screen)
 fn toggle_fullscreen(mut query: Query<&mut Window>, keyboard_input: Res<ButtonInput<KeyCode>>) {
     if keyboard_input.just_pressed(KeyCode::F11) {
-        if query.single().mode == WindowMode::Fullscreen {
-            query.single_mut().mode = WindowMode::Windowed;
-        } else {
-            query.single_mut().mode = WindowMode::Fullscreen;
+        for mut window in query.iter_mut().filter(|w| w.focused) {
+            if window.mode == WindowMode::Fullscreen {
+                window.mode = WindowMode::Windowed;
+            } else {
+                window.mode = WindowMode::Fullscreen;
+            }
         }
     }
 }
@@ -326,7 +469,7 @@ fn save_scene(world: &mut World) {
     let events: Vec<SaveCommand> = save_events.drain().collect();
     for event in events {
         let name = event.0;
-        let mut query = world.query_filtered::<Entity, With<Vertices>>();
+        let mut query = world.query_filtered::<Entity, Or<(With<Vertices>, With<Comment>)>>();
         let scene = DynamicSceneBuilder::from_world(world)
             .allow::<Col>()
             .allow::<Transform>()
@@ -339,6 +482,8 @@ fn save_scene(world: &mut World) {
             .allow::<Holes>()
             .allow::<Vertices>()
             .allow::<Targets>()
+            .allow::<Comment>()
+            .allow::<Locked>()
             .allow_resource::<DefaultDrawColor>()
             .allow_resource::<DefaultDrawVerts>()
             .allow_resource::<HighlightColor>()
@@ -351,6 +496,10 @@ fn save_scene(world: &mut World) {
             .allow_resource::<Version>()
             .allow_resource::<NodeLimit>()
             .allow_resource::<ShowInfoText>()
+            .allow_resource::<Bookmarks>()
+            .allow_resource::<Grid>()
+            .allow_resource::<ConnectionStyle>()
+            .allow_resource::<LinkColors>()
             .extract_entities(query.iter(world))
             .extract_resources()
             .build();
@@ -383,6 +532,8 @@ fn copy_scene(world: &mut World) {
         .allow::<Holes>()
         .allow::<Vertices>()
         .allow::<Targets>()
+        .allow::<Comment>()
+        .allow::<Locked>()
         .extract_entities(query.iter(world))
         .build();
     let serialized_scene = scene.serialize(&world.resource::<AppTypeRegistry>().read()).unwrap();
@@ -456,6 +607,8 @@ fn post_load(
         selected_query,
         indicator_color,
         indicator_id,
+        comment_query,
+        mut profiler,
     ): (
         Res<CommandColor>,
         Res<ConnectionColor>,
@@ -464,6 +617,8 @@ fn post_load(
         Query<Entity, With<Selected>>,
         Res<IndicatorColor>,
         Res<Indicator>,
+        Query<(), With<Comment>>,
+        ResMut<Profiler>,
     ),
 ) {
     for (scene_id, instance_id) in scenes.iter() {
@@ -477,6 +632,11 @@ fn post_load(
             materials.get_mut(&connection_mat.0).unwrap().color = connection_color.0.into();
             if let Ok(children) = children_query.get(scene_id) {
                 for child in children {
+                    // comments get their text from update_comments
+                    if comment_query.contains(*child) {
+                        commands.entity(*child).insert(Selected).remove_parent();
+                        continue;
+                    }
                     if let Ok((t, c, v)) = main_query.get(*child) {
                         if polygon_handles.0.len() <= v.0 {
                             polygon_handles.0.resize(v.0 + 1, None);
@@ -496,9 +656,19 @@ fn post_load(
                         ));
                         if let Ok(op) = op_query.get_mut(*child) {
                             let (s, r) = crossbeam_channel::bounded(1);
+                            let mut net = match str_to_net(&op.0) {
+                                Ok(net) => net,
+                                Err(err) => {
+                                    commands.entity(*child).insert(BadOp(err.to_string()));
+                                    Net::new(0, 0)
+                                }
+                            };
+                            if profiler.enabled {
+                                profiler.probe(*child, &mut net);
+                            }
                             commands.entity(*child).insert((
                                 OpNum(str_to_op_num(&op.0)),
-                                Network(str_to_net(&op.0)),
+                                Network(net),
                                 NetIns(Vec::new()),
                                 OpChanged(true),
                                 LostWH(false),
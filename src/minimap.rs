@@ -0,0 +1,161 @@
+use bevy::{
+    input::{mouse::MouseButtonInput, ButtonState},
+    prelude::*,
+    utils::HashSet,
+    window::PrimaryWindow,
+};
+
+use crate::components::*;
+
+// width and height of the minimap (px)
+const SIZE: f32 = 200.;
+
+pub fn spawn_minimap(mut commands: Commands, command_color: Res<CommandColor>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(5.),
+                    right: Val::Px(5.),
+                    width: Val::Px(SIZE),
+                    height: Val::Px(SIZE),
+                    border: UiRect::all(Val::Px(1.)),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: Color::srgba(0., 0., 0., 0.7).into(),
+                border_color: Color::from(command_color.0).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            MinimapPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        border: UiRect::all(Val::Px(1.)),
+                        ..default()
+                    },
+                    border_color: Color::from(command_color.0).into(),
+                    z_index: ZIndex::Local(1),
+                    ..default()
+                },
+                MinimapView,
+            ));
+        });
+}
+
+// a dot for every circle (not holes) and a rectangle for the view,
+// in a square around all of them
+pub fn update_minimap(
+    mut commands: Commands,
+    mut minimap: ResMut<Minimap>,
+    mut panel: Query<(Entity, &mut Visibility), With<MinimapPanel>>,
+    mut view: Query<&mut Style, (With<MinimapView>, Without<MinimapDot>)>,
+    mut dots: Query<(Entity, &MinimapDot, &mut Style, &mut BackgroundColor)>,
+    circles: Query<(Entity, &Transform, &Col), With<Order>>,
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+) {
+    let (panel, mut visibility) = panel.single_mut();
+    if !minimap.show {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+    let (cam, ortho) = camera.single();
+    let seen = Rect::from_center_size(cam.translation.xy(), ortho.area.size());
+    let area = circles.iter().fold(seen, |r, (_, t, _)| r.union(circle_rect(t)));
+    // square, with a little room around
+    let size = area.size().max_element() * 1.1;
+    let area = Rect::from_center_size(area.center(), Vec2::splat(size.max(1.)));
+    minimap.area = area;
+    let view_style = Style { border: UiRect::all(Val::Px(1.)), ..rect_style(seen, area) };
+    view.single_mut().set_if_neq(view_style);
+    let mut drawn = HashSet::new();
+    for (e, dot, mut style, mut color) in dots.iter_mut() {
+        let Ok((_, t, col)) = circles.get(dot.0) else {
+            commands.entity(e).despawn();
+            continue;
+        };
+        drawn.insert(dot.0);
+        style.set_if_neq(rect_style(circle_rect(t), area));
+        color.set_if_neq(Color::from(col.0).into());
+    }
+    for (e, t, col) in circles.iter() {
+        if drawn.contains(&e) {
+            continue;
+        }
+        let dot = commands
+            .spawn((
+                NodeBundle {
+                    style: rect_style(circle_rect(t), area),
+                    background_color: Color::from(col.0).into(),
+                    border_radius: BorderRadius::MAX,
+                    ..default()
+                },
+                MinimapDot(e),
+            ))
+            .id();
+        commands.entity(panel).add_child(dot);
+    }
+}
+
+// clicking (or dragging) on the minimap moves the camera there.
+// the click is taken away from everything else
+pub fn minimap_click(
+    mut mouse_button_input: ResMut<ButtonInput<MouseButton>>,
+    mut events: EventReader<MouseButtonInput>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    panel: Query<(&Node, &GlobalTransform), With<MinimapPanel>>,
+    minimap: Res<Minimap>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+    mut dragging: Local<bool>,
+) {
+    let released =
+        events.read().any(|e| e.button == MouseButton::Left && e.state == ButtonState::Released);
+    let Some(pos) = windows.single().cursor_position() else { return };
+    let (node, t) = panel.single();
+    let rect = node.logical_rect(t);
+    if minimap.show && mouse_button_input.just_pressed(MouseButton::Left) && rect.contains(pos) {
+        *dragging = true;
+    }
+    if *dragging {
+        mouse_button_input.reset(MouseButton::Left);
+        let uv = ((pos - rect.min) / rect.size()).clamp(Vec2::ZERO, Vec2::ONE);
+        let area = minimap.area;
+        let mut cam = camera.single_mut();
+        cam.translation.x = area.min.x + uv.x * area.width();
+        cam.translation.y = area.max.y - uv.y * area.height();
+    }
+    if released || !minimap.show {
+        *dragging = false;
+    }
+}
+
+// the area covered by these (circles or comments), None if there's nothing
+pub fn bounds<'a>(transforms: impl IntoIterator<Item = &'a Transform>) -> Option<Rect> {
+    transforms.into_iter().map(circle_rect).reduce(|a, b| a.union(b))
+}
+
+fn circle_rect(t: &Transform) -> Rect {
+    Rect::from_center_half_size(t.translation.xy(), t.scale.xy().abs())
+}
+
+// where a world rect is on the minimap (from the bottom left, like the world)
+fn rect_style(r: Rect, area: Rect) -> Style {
+    let min = (r.min - area.min) / area.size() * 100.;
+    let size = r.size() / area.size() * 100.;
+    Style {
+        position_type: PositionType::Absolute,
+        left: Val::Percent(min.x),
+        bottom: Val::Percent(min.y),
+        width: Val::Percent(size.x),
+        height: Val::Percent(size.y),
+        min_width: Val::Px(2.),
+        min_height: Val::Px(2.),
+        ..default()
+    }
+}
@@ -1,7 +1,13 @@
 use crossbeam_channel::{Receiver, Sender};
 use fundsp::fft::*;
 use fundsp::hacker32::*;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
+use std::sync::{
+    atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
 
 /// switch between nets based on index
 /// - input 0: index
@@ -120,6 +126,648 @@ impl AudioNode for Seq {
     }
 }
 
+#[derive(Clone)]
+struct Voice {
+    net: Net,
+    freq: f32,
+    vel: f32,
+    gate: f32,
+    // when the voice was last allocated
+    age: u64,
+    // peak follower, for stealing the quietest voice
+    level: f32,
+    // hold the gate low for a sample so envelopes retrigger
+    retrig: bool,
+}
+
+const NOTES: usize = 1024;
+
+/// note events (pitch, velocity, gate) sent from the main thread. like EventQueue,
+/// clones share the queue and each one reads it on its own
+#[derive(Clone)]
+pub struct NoteQueue(Arc<Notes>);
+
+struct Notes {
+    head: AtomicU64,
+    ring: Vec<[AtomicU32; 3]>,
+}
+
+impl Default for NoteQueue {
+    fn default() -> Self {
+        let ring = (0..NOTES).map(|_| [(); 3].map(|_| AtomicU32::new(0))).collect();
+        NoteQueue(Arc::new(Notes { head: AtomicU64::new(0), ring }))
+    }
+}
+
+impl NoteQueue {
+    pub fn push(&self, pitch: f32, vel: f32, gate: f32) {
+        let head = self.0.head.load(Ordering::Relaxed);
+        let slot = &self.0.ring[head as usize % NOTES];
+        for (x, v) in slot.iter().zip([pitch, vel, gate]) {
+            x.store(v.to_bits(), Ordering::Relaxed);
+        }
+        self.0.head.store(head + 1, Ordering::Release);
+    }
+
+    fn head(&self) -> u64 {
+        self.0.head.load(Ordering::Acquire)
+    }
+
+    fn get(&self, i: u64) -> [f32; 3] {
+        self.0.ring[i as usize % NOTES]
+            .each_ref()
+            .map(|x| f32::from_bits(x.load(Ordering::Relaxed)))
+    }
+}
+
+/// polyphonic voice allocator
+/// - note events (pitch, velocity, gate) are read from the queue
+/// - outputs: sum of all voices
+///
+/// each voice gets the inputs (frequency, gate, velocity) (as many as it has).
+/// every copy around the graph hears the notes sent after it was made
+pub struct Poly {
+    voices: Vec<Voice>,
+    notes: NoteQueue,
+    // next note to read
+    read: u64,
+    // steal the quietest voice instead of the oldest
+    quietest: bool,
+    count: u64,
+    outputs: usize,
+}
+
+impl Poly {
+    /// a voice for each net (they should all have the same outputs)
+    pub fn new(nets: Vec<Net>, notes: NoteQueue, quietest: bool) -> Self {
+        let outputs = nets.first().map_or(0, |net| net.outputs());
+        let voices = nets
+            .into_iter()
+            .map(|net| Voice { net, freq: 0., vel: 0., gate: 0., age: 0, level: 0., retrig: false })
+            .collect();
+        let read = notes.head();
+        Poly { voices, notes, read, quietest, count: 0, outputs }
+    }
+
+    fn note_on(&mut self, pitch: f32, vel: f32) {
+        let quietest = self.quietest;
+        // the same pitch is still held, retrigger it
+        let held = self.voices.iter().position(|v| v.gate != 0. && v.freq == pitch);
+        // otherwise a released voice, the one that faded the most
+        let free = || {
+            self.voices
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| v.gate == 0.)
+                .min_by(|a, b| a.1.level.total_cmp(&b.1.level))
+                .map(|(i, _)| i)
+        };
+        // otherwise steal one
+        let steal = || {
+            self.voices
+                .iter()
+                .enumerate()
+                .min_by(|a, b| {
+                    if quietest {
+                        a.1.level.total_cmp(&b.1.level)
+                    } else {
+                        a.1.age.cmp(&b.1.age)
+                    }
+                })
+                .map(|(i, _)| i)
+        };
+        if let Some(i) = held.or_else(free).or_else(steal) {
+            self.count += 1;
+            let voice = &mut self.voices[i];
+            voice.retrig = voice.gate != 0.;
+            voice.freq = pitch;
+            voice.vel = vel;
+            voice.gate = 1.;
+            voice.age = self.count;
+        }
+    }
+
+    fn note_off(&mut self, pitch: f32) {
+        for voice in &mut self.voices {
+            if voice.freq == pitch {
+                voice.gate = 0.;
+                voice.retrig = false;
+            }
+        }
+    }
+}
+
+impl Clone for Poly {
+    fn clone(&self) -> Self {
+        Poly {
+            voices: self.voices.clone(),
+            notes: self.notes.clone(),
+            // notes sent before this copy was made are old news
+            read: self.notes.head(),
+            quietest: self.quietest,
+            count: self.count,
+            outputs: self.outputs,
+        }
+    }
+}
+
+impl AudioUnit for Poly {
+    fn reset(&mut self) {
+        for voice in &mut self.voices {
+            voice.net.reset();
+            voice.gate = 0.;
+            voice.level = 0.;
+            voice.retrig = false;
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        for voice in &mut self.voices {
+            voice.net.set_sample_rate(sample_rate);
+        }
+    }
+
+    fn tick(&mut self, _input: &[f32], output: &mut [f32]) {
+        let head = self.notes.head();
+        // fell too far behind, skip what's been overwritten
+        self.read = Ord::max(self.read, head.saturating_sub(NOTES as u64));
+        while self.read < head {
+            let [pitch, vel, gate] = self.notes.get(self.read);
+            self.read += 1;
+            if gate != 0. {
+                self.note_on(pitch, vel);
+            } else {
+                self.note_off(pitch);
+            }
+        }
+        output[..self.outputs].fill(0.);
+        let mut buffer = [0.; 2];
+        for voice in &mut self.voices {
+            let gate = if voice.retrig { 0. } else { voice.gate };
+            voice.retrig = false;
+            let ins = voice.net.inputs();
+            voice.net.tick(&[voice.freq, gate, voice.vel][..ins], &mut buffer[..self.outputs]);
+            let mut peak = 0f32;
+            for (out, x) in output.iter_mut().zip(&buffer[..self.outputs]) {
+                *out += x;
+                peak = peak.max(x.abs());
+            }
+            voice.level = peak.max(voice.level * 0.999);
+        }
+    }
+
+    fn process(&mut self, size: usize, _input: &BufferRef, output: &mut BufferMut) {
+        let mut buffer = [0.; 2];
+        for i in 0..size {
+            self.tick(&[], &mut buffer[..self.outputs]);
+            for (c, x) in buffer[..self.outputs].iter().enumerate() {
+                output.set_f32(c, i, *x);
+            }
+        }
+    }
+
+    fn inputs(&self) -> usize {
+        0
+    }
+
+    fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    fn route(&mut self, input: &SignalFrame, _frequency: f64) -> SignalFrame {
+        Routing::Generator(0.0).route(input, self.outputs())
+    }
+
+    fn get_id(&self) -> u64 {
+        const ID: u64 = 1119;
+        ID
+    }
+
+    fn ping(&mut self, probe: bool, hash: AttoHash) -> AttoHash {
+        let mut hash = hash.hash(self.get_id());
+        for voice in &mut self.voices {
+            hash = voice.net.ping(probe, hash);
+        }
+        hash
+    }
+
+    fn footprint(&self) -> usize {
+        core::mem::size_of::<Self>()
+    }
+
+    fn allocate(&mut self) {
+        for voice in &mut self.voices {
+            voice.net.allocate();
+        }
+    }
+}
+
+/// musical time shared between the main thread and the audio graph.
+/// the position (in beats) is advanced by the output (see `Clocked`)
+#[derive(Clone)]
+pub struct Transport {
+    pos: Arc<AtomicU64>,
+    // beats per sample during the current block
+    inc: Arc<AtomicU64>,
+    bpm: Shared,
+    swing: Shared,
+    meter: Shared,
+    playing: Shared,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Transport {
+            pos: Arc::new(AtomicU64::new(0f64.to_bits())),
+            inc: Arc::new(AtomicU64::new(0f64.to_bits())),
+            bpm: shared(120.),
+            swing: shared(0.),
+            meter: shared(4.),
+            playing: shared(1.),
+        }
+    }
+}
+
+impl Transport {
+    /// position in beats
+    pub fn position(&self) -> f64 {
+        f64::from_bits(self.pos.load(Ordering::Relaxed))
+    }
+
+    pub fn set_position(&self, beats: f64) {
+        self.pos.store(beats.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn bpm(&self) -> f32 {
+        self.bpm.value()
+    }
+
+    pub fn set_bpm(&self, bpm: f32) {
+        self.bpm.set(bpm.max(0.));
+    }
+
+    pub fn swing(&self) -> f32 {
+        self.swing.value()
+    }
+
+    /// 0 is straight, 1/3 is a triplet feel
+    pub fn set_swing(&self, swing: f32) {
+        self.swing.set(swing.clamp(0., 0.9));
+    }
+
+    /// beats per bar
+    pub fn meter(&self) -> f32 {
+        self.meter.value()
+    }
+
+    pub fn set_meter(&self, beats: f32) {
+        self.meter.set(beats.round().max(1.));
+    }
+
+    pub fn playing(&self) -> bool {
+        self.playing.value() != 0.
+    }
+
+    pub fn set_playing(&self, playing: bool) {
+        self.playing.set(if playing { 1. } else { 0. });
+    }
+
+    /// beat within the bar
+    pub fn beat(&self) -> f32 {
+        (self.position().floor() as f32).rem_euclid(self.meter())
+    }
+
+    pub fn bar(&self) -> f32 {
+        (self.position() / self.meter() as f64).floor() as f32
+    }
+
+    /// phase of a (swung) division of the beat
+    pub fn phase(&self, div: f32) -> f32 {
+        swung(self.position() * div as f64, self.swing() as f64).fract() as f32
+    }
+
+    fn inc(&self) -> f64 {
+        f64::from_bits(self.inc.load(Ordering::Relaxed))
+    }
+
+    // called by the output at the start of a block
+    fn start_block(&self, sample_rate: f64) -> f64 {
+        let inc = if self.playing() { self.bpm() as f64 / 60. / sample_rate } else { 0. };
+        self.inc.store(inc.to_bits(), Ordering::Relaxed);
+        inc
+    }
+
+    fn advance(&self, samples: usize, inc: f64) {
+        self.set_position(self.position() + samples as f64 * inc);
+    }
+}
+
+/// delay every other pulse. pulses come in pairs, the first one lasts
+/// 1 + swing, the second 1 - swing
+pub fn swung(pulses: f64, swing: f64) -> f64 {
+    let pair = (pulses / 2.).floor() * 2.;
+    let t = pulses - pair;
+    if t < 1. + swing {
+        pair + t / (1. + swing)
+    } else {
+        pair + 1. + (t - 1. - swing) / (1. - swing)
+    }
+}
+
+/// maps samples to wall time, so events from the main thread can land on the
+/// sample they're meant for. times are in microseconds since the clock was made
+#[derive(Clone)]
+pub struct AudioClock {
+    epoch: Instant,
+    // time and sample count at the start of the current callback
+    callback_time: Arc<AtomicI64>,
+    callback_sample: Arc<AtomicU64>,
+    // samples taken by the device
+    samples: Arc<AtomicU64>,
+    // time of the block being processed
+    block_time: Arc<AtomicI64>,
+}
+
+impl Default for AudioClock {
+    fn default() -> Self {
+        AudioClock {
+            epoch: Instant::now(),
+            callback_time: Arc::new(AtomicI64::new(0)),
+            callback_sample: Arc::new(AtomicU64::new(0)),
+            samples: Arc::new(AtomicU64::new(0)),
+            block_time: Arc::new(AtomicI64::new(0)),
+        }
+    }
+}
+
+impl AudioClock {
+    pub fn micros(&self, t: Instant) -> i64 {
+        t.saturating_duration_since(self.epoch).as_micros() as i64
+    }
+
+    /// called at the start of every output callback
+    pub fn start_callback(&self) {
+        let now = self.micros(Instant::now());
+        self.callback_time.store(now, Ordering::Relaxed);
+        self.callback_sample.store(self.samples.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    /// called after every sample the device takes
+    pub fn take_sample(&self) {
+        self.samples.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn start_block(&self, sample_rate: f64) {
+        let since =
+            self.samples.load(Ordering::Relaxed) - self.callback_sample.load(Ordering::Relaxed);
+        let t =
+            self.callback_time.load(Ordering::Relaxed) + (since as f64 * 1e6 / sample_rate) as i64;
+        self.block_time.store(t, Ordering::Relaxed);
+    }
+
+    pub fn block_time(&self) -> i64 {
+        self.block_time.load(Ordering::Relaxed)
+    }
+}
+
+/// the output unit. advances the transport and the audio clock with every block,
+/// and takes the block's input from the `InputRing`
+#[derive(Clone)]
+pub struct Clocked {
+    x: SlotBackend,
+    transport: Transport,
+    clock: AudioClock,
+    input: InputRing,
+    sr: f64,
+}
+
+impl Clocked {
+    pub fn new(
+        x: SlotBackend,
+        transport: Transport,
+        clock: AudioClock,
+        input: InputRing,
+        sr: f64,
+    ) -> Self {
+        Clocked { x, transport, clock, input, sr }
+    }
+}
+
+impl AudioUnit for Clocked {
+    fn reset(&mut self) {
+        self.x.reset();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sr = sample_rate;
+        self.x.set_sample_rate(sample_rate);
+    }
+
+    fn tick(&mut self, input: &[f32], output: &mut [f32]) {
+        self.clock.start_block(self.sr);
+        self.input.start_block(1, self.sr);
+        let inc = self.transport.start_block(self.sr);
+        self.x.tick(input, output);
+        self.transport.advance(1, inc);
+    }
+
+    fn process(&mut self, size: usize, input: &BufferRef, output: &mut BufferMut) {
+        self.clock.start_block(self.sr);
+        self.input.start_block(size, self.sr);
+        let inc = self.transport.start_block(self.sr);
+        self.x.process(size, input, output);
+        self.transport.advance(size, inc);
+    }
+
+    fn inputs(&self) -> usize {
+        self.x.inputs()
+    }
+
+    fn outputs(&self) -> usize {
+        self.x.outputs()
+    }
+
+    fn route(&mut self, input: &SignalFrame, frequency: f64) -> SignalFrame {
+        self.x.route(input, frequency)
+    }
+
+    fn get_id(&self) -> u64 {
+        const ID: u64 = 1120;
+        ID
+    }
+
+    fn ping(&mut self, probe: bool, hash: AttoHash) -> AttoHash {
+        self.x.ping(probe, hash.hash(self.get_id()))
+    }
+
+    fn footprint(&self) -> usize {
+        core::mem::size_of::<Self>()
+    }
+
+    fn allocate(&mut self) {
+        self.x.allocate();
+    }
+}
+
+/// clock following the transport
+/// - output 0: trigger on every division of the beat
+/// - output 1: phase of the division
+#[derive(Clone)]
+pub struct Clock {
+    transport: Transport,
+    div: f32,
+    // position at the start of the block, and samples since
+    base: f64,
+    offset: f64,
+    // last output position (in divisions)
+    prev: f64,
+}
+
+impl Clock {
+    pub fn new(transport: Transport, div: f32) -> Self {
+        Clock { transport, div, base: f64::NAN, offset: 0., prev: f64::NAN }
+    }
+}
+
+impl AudioNode for Clock {
+    const ID: u64 = 1121;
+    type Inputs = U0;
+    type Outputs = U2;
+
+    #[inline]
+    fn tick(&mut self, _input: &Frame<f32, Self::Inputs>) -> Frame<f32, Self::Outputs> {
+        let base = self.transport.position();
+        let inc = self.transport.inc();
+        if base != self.base || inc == 0. {
+            self.base = base;
+            self.offset = 0.;
+        }
+        let pos = base + self.offset * inc;
+        self.offset += 1.;
+        let swing = self.transport.swing() as f64;
+        let div = self.div as f64;
+        let now = swung(pos * div, swing);
+        // use our last position if it's where the previous sample would be. so rounding
+        // can't trigger twice. otherwise the position jumped (or we just started)
+        let mut prev = swung((pos - inc) * div, swing);
+        if (self.prev - prev).abs() < 1e-9 {
+            prev = self.prev;
+        }
+        self.prev = now;
+        let trig = if inc > 0. && now.floor() > prev.floor() { 1. } else { 0. };
+        [trig, now.fract() as f32].into()
+    }
+}
+
+const EVENTS: usize = 64;
+
+struct Events {
+    head: AtomicU64,
+    // value (f32 bits) and time
+    ring: Vec<(AtomicU32, AtomicI64)>,
+}
+
+/// timestamped values sent from the main thread. clones share the queue and
+/// each one reads it on its own (so a net cloned around the graph sees every event)
+#[derive(Clone)]
+pub struct EventQueue(Arc<Events>);
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        let ring = (0..EVENTS).map(|_| (AtomicU32::new(0), AtomicI64::new(0))).collect();
+        EventQueue(Arc::new(Events { head: AtomicU64::new(0), ring }))
+    }
+}
+
+impl EventQueue {
+    /// times must not go backwards
+    pub fn push(&self, value: f32, time: i64) {
+        let head = self.0.head.load(Ordering::Relaxed);
+        let slot = &self.0.ring[head as usize % EVENTS];
+        slot.0.store(value.to_bits(), Ordering::Relaxed);
+        slot.1.store(time, Ordering::Relaxed);
+        self.0.head.store(head + 1, Ordering::Release);
+    }
+
+    fn head(&self) -> u64 {
+        self.0.head.load(Ordering::Acquire)
+    }
+
+    fn get(&self, i: u64) -> (f32, i64) {
+        let slot = &self.0.ring[i as usize % EVENTS];
+        (f32::from_bits(slot.0.load(Ordering::Relaxed)), slot.1.load(Ordering::Relaxed))
+    }
+}
+
+/// a var whose changes land on the sample they're timestamped for
+/// - output 0: value (or a trigger, see `TimedVar::trig`)
+#[derive(Clone)]
+pub struct TimedVar {
+    queue: EventQueue,
+    clock: AudioClock,
+    value: f32,
+    // output the value only on the sample it lands, 0 otherwise
+    trig: bool,
+    // next event to read
+    read: u64,
+    // block time, and samples since
+    base: i64,
+    offset: f64,
+    sr: f64,
+}
+
+impl TimedVar {
+    pub fn new(queue: EventQueue, clock: AudioClock, value: f32) -> Self {
+        let read = queue.head();
+        TimedVar { queue, clock, value, trig: false, read, base: 0, offset: 0., sr: 44100. }
+    }
+
+    /// outputs a non-zero value for one sample, the one it's timestamped for
+    /// (a trigger for seq() or trig_reset())
+    pub fn trig(queue: EventQueue, clock: AudioClock) -> Self {
+        TimedVar { trig: true, ..TimedVar::new(queue, clock, 0.) }
+    }
+}
+
+impl AudioNode for TimedVar {
+    const ID: u64 = 1122;
+    type Inputs = U0;
+    type Outputs = U1;
+
+    #[inline]
+    fn tick(&mut self, _input: &Frame<f32, Self::Inputs>) -> Frame<f32, Self::Outputs> {
+        let base = self.clock.block_time();
+        if base != self.base {
+            self.base = base;
+            self.offset = 0.;
+        }
+        let now = base + (self.offset * 1e6 / self.sr) as i64;
+        self.offset += 1.;
+        let head = self.queue.head();
+        // fell too far behind, skip what's been overwritten
+        self.read = Ord::max(self.read, head.saturating_sub(EVENTS as u64));
+        let mut fired = 0.;
+        while self.read < head {
+            let (value, time) = self.queue.get(self.read);
+            // apply it right away if the clock isn't running (no output device,
+            // or this is ticked on the main thread) or it's way ahead of it
+            if base != 0 && time > now && time < now + 1000000 {
+                break;
+            }
+            self.value = value;
+            self.read += 1;
+            if value != 0. {
+                fired = value;
+            }
+        }
+        [if self.trig { fired } else { self.value }].into()
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sr = sample_rate;
+    }
+}
+
 /// index an array of floats
 /// - input 0: index
 /// - output 0: value at index
@@ -491,18 +1139,214 @@ impl AudioNode for Ramp {
     }
 }
 
-/// node that receives samples from crossbeam channels
+/// stereo frames from the input stream to the output stream.
+/// single producer (the input callback), single consumer (the output, at the
+/// start of every block). the input arrives in chunks, so how full the ring is goes
+/// up and down with them. the output keeps it `latency` full at its emptiest,
+/// dropping or repeating a frame now and then to follow the drift between devices
+#[derive(Clone)]
+pub struct InputRing(Arc<Ring>);
+
+struct Ring {
+    // left and right bits packed together
+    frames: Box<[AtomicU64]>,
+    // frames written and read since the start
+    write: AtomicUsize,
+    read: AtomicUsize,
+    // target low point, in milliseconds (f32 bits)
+    latency: AtomicU32,
+    // frames thrown away by the reader, waiting to be counted by the writer
+    skipped: AtomicU64,
+    // the rest is only touched by the reader
+    // waiting for the ring to fill up to the latency
+    priming: AtomicBool,
+    // lowest fill (after taking a block) and frames taken, this second
+    low: AtomicUsize,
+    window: AtomicUsize,
+    // frames to drop (or repeat if negative), one per block
+    correction: AtomicI64,
+}
+
+// ignore drift smaller than this (in frames)
+const DRIFT_TOLERANCE: usize = 16;
+
+// the frames of the block being processed, on the output thread
+struct Reader {
+    block: u64,
+    len: usize,
+    frames: [[f32; 2]; MAX_BUFFER_SIZE],
+}
+
+thread_local! {
+    static READER: RefCell<Reader> = const {
+        RefCell::new(Reader { block: 0, len: 0, frames: [[0.; 2]; MAX_BUFFER_SIZE] })
+    };
+}
+
+impl Default for InputRing {
+    fn default() -> Self {
+        Self::new(1 << 15, 5.)
+    }
+}
+
+impl InputRing {
+    pub fn new(capacity: usize, latency: f32) -> Self {
+        let ring = InputRing(Arc::new(Ring {
+            frames: (0..capacity).map(|_| AtomicU64::new(0)).collect(),
+            write: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            latency: AtomicU32::new(0),
+            skipped: AtomicU64::new(0),
+            priming: AtomicBool::new(true),
+            low: AtomicUsize::new(usize::MAX),
+            window: AtomicUsize::new(0),
+            correction: AtomicI64::new(0),
+        }));
+        ring.set_latency(latency);
+        ring
+    }
+
+    pub fn latency(&self) -> f32 {
+        f32::from_bits(self.0.latency.load(Ordering::Relaxed))
+    }
+
+    pub fn set_latency(&self, ms: f32) {
+        self.0.latency.store(ms.max(0.).to_bits(), Ordering::Relaxed);
+    }
+
+    /// frames waiting to be read
+    pub fn available(&self) -> usize {
+        let read = self.0.read.load(Ordering::Acquire);
+        self.0.write.load(Ordering::Acquire).wrapping_sub(read)
+    }
+
+    /// called by the input callback. false if the ring is full (the frame is lost)
+    pub fn push(&self, l: f32, r: f32) -> bool {
+        let write = self.0.write.load(Ordering::Relaxed);
+        let read = self.0.read.load(Ordering::Acquire);
+        let capacity = self.0.frames.len();
+        if write.wrapping_sub(read) >= capacity {
+            return false;
+        }
+        let frame = (l.to_bits() as u64) << 32 | r.to_bits() as u64;
+        self.0.frames[write % capacity].store(frame, Ordering::Relaxed);
+        self.0.write.store(write.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    fn pop(&self) -> Option<[f32; 2]> {
+        let read = self.0.read.load(Ordering::Relaxed);
+        if read == self.0.write.load(Ordering::Acquire) {
+            return None;
+        }
+        let frame = self.0.frames[read % self.0.frames.len()].load(Ordering::Relaxed);
+        self.0.read.store(read.wrapping_add(1), Ordering::Release);
+        Some([f32::from_bits((frame >> 32) as u32), f32::from_bits(frame as u32)])
+    }
+
+    // drop frames to leave `keep`, and start measuring the drift again
+    fn skip_to(&self, keep: usize) {
+        let frames = self.available().saturating_sub(keep);
+        for _ in 0..frames {
+            self.pop();
+        }
+        self.0.skipped.fetch_add(frames as u64, Ordering::Relaxed);
+        self.0.low.store(usize::MAX, Ordering::Relaxed);
+        self.0.window.store(0, Ordering::Relaxed);
+        self.0.correction.store(0, Ordering::Relaxed);
+    }
+
+    /// frames dropped by the reader since the last call
+    pub fn take_skipped(&self) -> u64 {
+        self.0.skipped.swap(0, Ordering::Relaxed)
+    }
+
+    /// called by the output at the start of every block. takes `size` frames
+    /// for the `InputNode`s in the block to read
+    pub fn start_block(&self, size: usize, sample_rate: f64) {
+        let size = Ord::min(size, MAX_BUFFER_SIZE);
+        let capacity = self.0.frames.len();
+        let target = Ord::min((self.latency() as f64 * sample_rate / 1000.) as usize, capacity / 2);
+        READER.with(|reader| {
+            let reader = &mut *reader.borrow_mut();
+            reader.block = reader.block.wrapping_add(1);
+            reader.len = size;
+            let len = self.available();
+            if self.0.priming.load(Ordering::Relaxed) {
+                if len < target + size {
+                    reader.frames[..size].fill([0.; 2]);
+                    return;
+                }
+                self.0.priming.store(false, Ordering::Relaxed);
+            } else if len < size {
+                // the input fell behind, wait for it to catch up
+                self.0.priming.store(true, Ordering::Relaxed);
+                reader.frames[..size].fill([0.; 2]);
+                return;
+            } else if len > capacity - MAX_BUFFER_SIZE
+                || len as f64 > (target + size) as f64 + sample_rate / 10.
+            {
+                // way too full (the output stalled), jump back to the latency
+                self.skip_to(target + size);
+            }
+            let correction = self.0.correction.load(Ordering::Relaxed);
+            let take = if correction > 0 && len > size {
+                self.0.correction.store(correction - 1, Ordering::Relaxed);
+                size + 1
+            } else if correction < 0 && size > 1 {
+                self.0.correction.store(correction + 1, Ordering::Relaxed);
+                size - 1
+            } else {
+                size
+            };
+            for frame in &mut reader.frames[..Ord::min(take, size)] {
+                *frame = self.pop().unwrap_or([0.; 2]);
+            }
+            if take > size {
+                // running ahead, mix in the dropped frame
+                if let Some(next) = self.pop() {
+                    let last = &mut reader.frames[size - 1];
+                    *last = [(last[0] + next[0]) / 2., (last[1] + next[1]) / 2.];
+                }
+            } else if take < size {
+                // running behind, repeat a frame
+                reader.frames.copy_within(size / 2..size - 1, size / 2 + 1);
+            }
+            // once a second, see how far the low point is from the latency,
+            // and correct that over the next second
+            let low = Ord::min(self.0.low.load(Ordering::Relaxed), self.available());
+            let window = self.0.window.load(Ordering::Relaxed) + size;
+            if window as f64 >= sample_rate {
+                let error = low as i64 - target as i64;
+                let blocks = (window / size) as i64 / 2;
+                let correction = if error.unsigned_abs() as usize > DRIFT_TOLERANCE {
+                    error.clamp(-blocks, blocks)
+                } else {
+                    0
+                };
+                self.0.correction.store(correction, Ordering::Relaxed);
+                self.0.low.store(usize::MAX, Ordering::Relaxed);
+                self.0.window.store(0, Ordering::Relaxed);
+            } else {
+                self.0.low.store(low, Ordering::Relaxed);
+                self.0.window.store(window, Ordering::Relaxed);
+            }
+        });
+    }
+}
+
+/// node that plays the input device (through the `InputRing`)
 /// - output 0: left
 /// - output 1: right
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct InputNode {
-    lr: Receiver<f32>,
-    rr: Receiver<f32>,
+    block: u64,
+    i: usize,
 }
 
 impl InputNode {
-    pub fn new(lr: Receiver<f32>, rr: Receiver<f32>) -> Self {
-        InputNode { lr, rr }
+    pub fn new() -> Self {
+        InputNode::default()
     }
 }
 
@@ -513,9 +1357,210 @@ impl AudioNode for InputNode {
 
     #[inline]
     fn tick(&mut self, _input: &Frame<f32, Self::Inputs>) -> Frame<f32, Self::Outputs> {
-        let l = self.lr.try_recv().unwrap_or(0.);
-        let r = self.rr.try_recv().unwrap_or(0.);
-        [l, r].into()
+        READER.with(|reader| {
+            let reader = reader.borrow();
+            if reader.block != self.block {
+                self.block = reader.block;
+                self.i = 0;
+            }
+            let frame = if self.i < reader.len { reader.frames[self.i] } else { [0.; 2] };
+            self.i += 1;
+            frame.into()
+        })
+    }
+}
+
+/// nanoseconds spent somewhere, added up by the audio thread
+#[derive(Clone, Default)]
+pub struct CpuTime(Arc<AtomicU64>);
+
+impl CpuTime {
+    pub fn add(&self, nanos: u64) {
+        self.0.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    /// time added since the last take
+    pub fn take(&self) -> u64 {
+        self.0.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// time the output callback spent working, and the time it had
+#[derive(Clone, Default)]
+pub struct DspLoad {
+    busy: CpuTime,
+    budget: CpuTime,
+}
+
+impl DspLoad {
+    /// called by the output callback with how long it took to fill a buffer
+    /// of the given number of frames
+    pub fn record(&self, busy: Duration, frames: usize, sample_rate: f64) {
+        self.busy.add(busy.as_nanos() as u64);
+        self.budget.add((frames as f64 * 1e9 / sample_rate) as u64);
+    }
+
+    /// (busy, budget) in nanoseconds since the last take
+    pub fn take(&self) -> (u64, u64) {
+        (self.busy.take(), self.budget.take())
+    }
+}
+
+/// what went wrong with the audio devices. counted by the streams, reported by the ui
+#[derive(Clone, Default)]
+pub struct AudioStatus(Arc<Status>);
+
+#[derive(Default)]
+struct Status {
+    // output buffers that weren't ready in time
+    underruns: AtomicU64,
+    // input samples dropped because nothing was reading them
+    overflows: AtomicU64,
+    errors: AtomicU64,
+    disconnects: AtomicU64,
+    out_lost: AtomicBool,
+    in_lost: AtomicBool,
+    last_error: Mutex<Option<String>>,
+}
+
+impl AudioStatus {
+    /// called by the output callback. `gap` is the time since the previous callback,
+    /// `expected` is how long the previous buffer lasted, and `busy` how long this one took.
+    /// the device ran dry if we took longer than a buffer, or weren't called in time
+    pub fn callback(&self, gap: Option<Duration>, expected: Duration, busy: Duration) {
+        let late = gap.is_some_and(|gap| gap > expected * 3 / 2);
+        if late || busy > expected {
+            self.0.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// an underrun reported by the backend (jack)
+    pub fn underrun(&self) {
+        self.0.underruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn overflow(&self, samples: u64) {
+        self.0.overflows.fetch_add(samples, Ordering::Relaxed);
+    }
+
+    /// a stream error. `lost` if the device is gone
+    pub fn error(&self, msg: String, output: bool, lost: bool) {
+        self.0.errors.fetch_add(1, Ordering::Relaxed);
+        if lost {
+            self.0.disconnects.fetch_add(1, Ordering::Relaxed);
+            let flag = if output { &self.0.out_lost } else { &self.0.in_lost };
+            flag.store(true, Ordering::Relaxed);
+        }
+        *self.0.last_error.lock().unwrap() = Some(msg);
+    }
+
+    /// [underruns, overflows, errors, disconnects]
+    pub fn counts(&self) -> [u64; 4] {
+        [
+            self.0.underruns.load(Ordering::Relaxed),
+            self.0.overflows.load(Ordering::Relaxed),
+            self.0.errors.load(Ordering::Relaxed),
+            self.0.disconnects.load(Ordering::Relaxed),
+        ]
+    }
+
+    /// the error since the last call, if any
+    pub fn take_error(&self) -> Option<String> {
+        self.0.last_error.lock().unwrap().take()
+    }
+
+    pub fn lost(&self, output: bool) -> bool {
+        let flag = if output { &self.0.out_lost } else { &self.0.in_lost };
+        flag.load(Ordering::Relaxed)
+    }
+
+    pub fn found(&self, output: bool) {
+        let flag = if output { &self.0.out_lost } else { &self.0.in_lost };
+        flag.store(false, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    // time spent in the probes nested inside the one that's running
+    static NESTED_TIME: Cell<u64> = const { Cell::new(0) };
+}
+
+/// unit that adds the time spent processing it to a CpuTime.
+/// only counts its own time, not that of probes nested in it.
+/// once `on` is false it just passes through
+#[derive(Clone)]
+pub struct Probe {
+    x: Net,
+    time: CpuTime,
+    on: Arc<AtomicBool>,
+}
+
+impl Probe {
+    pub fn new(x: Net, time: CpuTime, on: Arc<AtomicBool>) -> Self {
+        Probe { x, time, on }
+    }
+
+    fn measure(&mut self, f: impl FnOnce(&mut Net)) {
+        if !self.on.load(Ordering::Relaxed) {
+            f(&mut self.x);
+            return;
+        }
+        let outer = NESTED_TIME.with(|t| t.replace(0));
+        let start = Instant::now();
+        f(&mut self.x);
+        let total = start.elapsed().as_nanos() as u64;
+        let nested = NESTED_TIME.with(|t| t.replace(outer + total));
+        self.time.add(total.saturating_sub(nested));
+    }
+}
+
+impl AudioUnit for Probe {
+    fn reset(&mut self) {
+        self.x.reset();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.x.set_sample_rate(sample_rate);
+    }
+
+    fn tick(&mut self, input: &[f32], output: &mut [f32]) {
+        self.measure(|x| x.tick(input, output));
+    }
+
+    fn process(&mut self, size: usize, input: &BufferRef, output: &mut BufferMut) {
+        self.measure(|x| x.process(size, input, output));
+    }
+
+    fn set(&mut self, setting: Setting) {
+        self.x.set(setting);
+    }
+
+    fn inputs(&self) -> usize {
+        self.x.inputs()
+    }
+
+    fn outputs(&self) -> usize {
+        self.x.outputs()
+    }
+
+    fn route(&mut self, input: &SignalFrame, frequency: f64) -> SignalFrame {
+        self.x.route(input, frequency)
+    }
+
+    fn get_id(&self) -> u64 {
+        self.x.get_id()
+    }
+
+    fn ping(&mut self, probe: bool, hash: AttoHash) -> AttoHash {
+        self.x.ping(probe, hash)
+    }
+
+    fn footprint(&self) -> usize {
+        self.x.footprint()
+    }
+
+    fn allocate(&mut self) {
+        self.x.allocate();
     }
 }
 
@@ -7,14 +7,15 @@ use bevy::{
     prelude::*,
     render::view::{screenshot::ScreenshotManager, RenderLayers},
     utils::Duration,
+    window::PrimaryWindow,
     winit::{UpdateMode, WinitSettings},
 };
 
-use std::str::FromStr;
+use std::{str::FromStr, time::Instant};
 
 use fundsp::hacker32::*;
 
-use crate::{components::*, functions::*, nodes::*, osc::*};
+use crate::{components::*, functions::*, graph::*, nodes::*, osc::*};
 
 pub fn sort_by_order(query: Query<(Entity, &Order), With<Network>>, mut queue: ResMut<Queue>) {
     let mut max_order: usize = 1;
@@ -39,8 +40,9 @@ pub fn prepare_loop_queue(
 ) {
     loopq.0.clear();
     for id in queue.0.iter().flatten() {
-        if op_num_query.get(*id).unwrap().0 == 92 {
-            for t in &targets_query.get(*id).unwrap().0 {
+        if op_num_query.get(*id).is_ok_and(|n| n.0 == 92) {
+            let Ok(targets) = targets_query.get(*id) else { continue };
+            for t in &targets.0 {
                 // only add existing circles (that aren't holes)
                 if op_num_query.contains(*t) {
                     loopq.0.push(*t);
@@ -59,10 +61,20 @@ pub fn process(
     black_hole_query: Query<&BlackHole>,
     cursor: Res<CursorInfo>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
-    camera_query: Query<(Entity, &Camera, &GlobalTransform)>,
-    windows: Query<(Entity, &Window)>,
+    camera_query: Query<(Entity, &Camera, &GlobalTransform), With<MainCamera>>,
+    windows: Query<(Entity, &Window), With<PrimaryWindow>>,
     mut commands: Commands,
     mut slot: ResMut<SlotRes>,
+    transport: Res<TransportRes>,
+    (real_time, timing, mut profiler, audio_status, pointers, mut parts, mut out_graph): (
+        Res<Time<bevy::time::Real>>,
+        Res<Timing>,
+        ResMut<Profiler>,
+        Res<AudioStatusRes>,
+        Res<Pointers>,
+        ResMut<Parts>,
+        ResMut<OutGraph>,
+    ),
     (
         mut order_query,
         op_query,
@@ -83,11 +95,11 @@ pub fn process(
     ): (
         Query<&mut Order>,
         Query<&mut Op>,
-        Query<&mut BloomSettings, With<Camera>>,
+        Query<&mut BloomSettings, With<MainCamera>>,
         Query<&mut Number>,
         Query<&mut Transform>,
         Query<&mut Arr>,
-        Query<&mut Tonemapping, With<Camera>>,
+        Query<&mut Tonemapping, With<MainCamera>>,
         Query<&mut Network>,
         Query<&mut NetIns>,
         Query<&NetChannel>,
@@ -143,11 +155,12 @@ pub fn process(
         mut osc_receiver,
         mut osc_messages,
         node_limit,
-        input_receivers,
         op_num_query,
         mut key_event,
         mut ortho,
         float_chan_query,
+        note_chan_query,
+        mut var_events_query,
     ): (
         ResMut<Assets<ColorMaterial>>,
         ResMut<ConnectionMat>,
@@ -158,17 +171,41 @@ pub fn process(
         ResMut<OscReceiver>,
         Local<Vec<rosc::OscMessage>>,
         Res<NodeLimit>,
-        Res<InputReceivers>,
         Query<&OpNum>,
         EventReader<KeyboardInput>,
-        Query<&mut OrthographicProjection>,
+        Query<&mut OrthographicProjection, With<MainCamera>>,
         Query<&FloatChannel>,
+        Query<&NoteChannel>,
+        Query<&mut VarEvents>,
     ),
 ) {
     let key_event = key_event.read().collect::<Vec<_>>();
+    // when changes made this frame should reach the audio thread
+    let frame_start = real_time.last_update().unwrap_or_else(Instant::now);
+    let event_time = timing.clock.micros(frame_start + timing.latency);
     let mut worm: Vec<(String, f32)> = Vec::new();
     'entity: for id in queue.0.iter().flatten().chain(loopq.0.iter()) {
-        let holes = &holes_query.get(*id).unwrap().0;
+        let Ok(holes) = holes_query.get(*id) else { continue };
+        let mut holes = holes.0.clone();
+        // a white hole whose black hole (or the black hole's parent) is gone.
+        // skip it instead of panicking on the lookups below, and clean it up
+        holes.retain(|hole| {
+            let Ok(wh) = white_hole_query.get(*hole) else { return true };
+            if black_hole_query.contains(wh.bh) && op_num_query.contains(wh.bh_parent) {
+                return true;
+            }
+            let msg = format!("dangling connection: {} (in {})", hole, id);
+            warn!("{}", msg);
+            if let Ok(mut text) = command_line_text.get_single_mut() {
+                text.sections[0].value = format!(">{}", msg);
+            }
+            if let Ok(mut lost) = lost_wh_query.get_mut(*id) {
+                lost.0 = true;
+            }
+            remove_dangling(&mut commands, *id, *hole, wh.bh, wh.bh_parent);
+            false
+        });
+        let holes = &holes;
         for hole in holes {
             let mut lt_to_open = 0;
             if let Ok(wh) = white_hole_query.get(*hole) {
@@ -249,8 +286,8 @@ pub fn process(
             }
         }
         let mut lt_to_open = None;
-        let op = op_query.get(*id).unwrap().0.as_str();
-        let op_num = op_num_query.get(*id).unwrap().0;
+        let (Ok(op), Ok(op_num)) = (op_query.get(*id), op_num_query.get(*id)) else { continue };
+        let (op, op_num) = (op.0.as_str(), op_num.0);
         match op_num {
             0 => {}
             // -------------------- targets --------------------
@@ -447,7 +484,9 @@ pub fn process(
                             for t in &targets_query.get(*id).unwrap().0 {
                                 if let Ok(holes) = holes_query.get(*t) {
                                     for hole in &holes.0 {
-                                        commands.entity(*hole).insert(Selected);
+                                        if vertices_query.contains(*hole) {
+                                            commands.entity(*hole).insert(Selected);
+                                        }
                                     }
                                 }
                             }
@@ -466,7 +505,9 @@ pub fn process(
                                 if let Ok(mut wh) = white_hole_query.get_mut(*t) {
                                     wh.link_types.1 = n as i8;
                                 } else if let Ok(bh) = black_hole_query.get(*t) {
-                                    white_hole_query.get_mut(bh.wh).unwrap().link_types.0 = n as i8;
+                                    if let Ok(mut wh) = white_hole_query.get_mut(bh.wh) {
+                                        wh.link_types.0 = n as i8;
+                                    }
                                 }
                             }
                         }
@@ -883,10 +924,8 @@ pub fn process(
             33 => {
                 let n = num_query.get_mut(*id).unwrap();
                 if n.is_changed() {
+                    // the arrows are redrawn in update_connection_arrows
                     connection_width.0 = n.0;
-                    for e in arrow_query.iter() {
-                        trans_query.get_mut(e.0).unwrap().scale.x = n.0;
-                    }
                 }
             }
             // text_size
@@ -896,9 +935,10 @@ pub fn process(
                     let size = n.0.max(0.1) / 120.;
                     text_size.0 = size;
                     for e in info_text_query.iter() {
-                        let scale = &mut trans_query.get_mut(e.0).unwrap().scale;
-                        scale.x = size;
-                        scale.y = size;
+                        if let Ok(mut t) = trans_query.get_mut(e.0) {
+                            t.scale.x = size;
+                            t.scale.y = size;
+                        }
                     }
                 }
             }
@@ -1088,6 +1128,15 @@ pub fn process(
                     }
                 }
             }
+            // touches
+            105 => {
+                let touches: Vec<f32> =
+                    pointers.down.iter().flat_map(|p| [p.f.x, p.f.y, p.pressure]).collect();
+                if arr_query.get(*id).unwrap().0 != touches {
+                    arr_query.get_mut(*id).unwrap().0 = touches;
+                    lt_to_open = Some(-13);
+                }
+            }
             // lmb_pressed
             45 => {
                 if mouse_button_input.pressed(MouseButton::Left) {
@@ -1120,29 +1169,38 @@ pub fn process(
             }
             // butt
             48 => {
-                if mouse_button_input.just_pressed(MouseButton::Left) {
-                    let t = trans_query.get(*id).unwrap().translation.xy();
-                    let r = trans_query.get(*id).unwrap().scale.x;
-                    if cursor.i.distance_squared(t) < r * r {
-                        num_query.get_mut(*id).unwrap().0 = 1.;
-                        lt_to_open = Some(-1);
-                    }
+                let t = trans_query.get(*id).unwrap().translation.xy();
+                let r = trans_query.get(*id).unwrap().scale.x;
+                let on = |p: Vec2| p.distance_squared(t) < r * r;
+                let clicked = mouse_button_input.just_pressed(MouseButton::Left) && on(cursor.i);
+                if clicked || pointers.pressed.iter().any(|p| on(p.i)) {
+                    num_query.get_mut(*id).unwrap().0 = 1.;
+                    lt_to_open = Some(-1);
                 }
-                if mouse_button_input.just_released(MouseButton::Left) {
+                // up when the mouse is released or a finger on it is lifted,
+                // unless another finger is still on it
+                let lifted = pointers.released.iter().any(|p| on(p.i));
+                let held = pointers.down.iter().any(|p| on(p.i));
+                if (mouse_button_input.just_released(MouseButton::Left) || lifted) && !held {
                     num_query.get_mut(*id).unwrap().0 = 0.;
                     lt_to_open = Some(-1);
                 }
             }
             // toggle
             49 => {
-                if mouse_button_input.just_pressed(MouseButton::Left) {
-                    let t = trans_query.get(*id).unwrap().translation.xy();
-                    let r = trans_query.get(*id).unwrap().scale.x;
-                    if cursor.i.distance_squared(t) < r * r {
-                        let n = &mut num_query.get_mut(*id).unwrap().0;
+                let t = trans_query.get(*id).unwrap().translation.xy();
+                let r = trans_query.get(*id).unwrap().scale.x;
+                let on = |p: Vec2| p.distance_squared(t) < r * r;
+                let clicked = mouse_button_input.just_pressed(MouseButton::Left) && on(cursor.i);
+                // every click and touch on it flips it
+                let presses =
+                    clicked as usize + pointers.pressed.iter().filter(|p| on(p.i)).count();
+                if presses > 0 {
+                    let n = &mut num_query.get_mut(*id).unwrap().0;
+                    if presses % 2 == 1 {
                         *n = if *n == 0. { 1. } else { 0. };
-                        lt_to_open = Some(-1);
                     }
+                    lt_to_open = Some(-1);
                 }
             }
             // key
@@ -1368,31 +1426,76 @@ pub fn process(
                     }
                 }
             }
+            // -------------------- transport --------------------
+            // beat | bar
+            98 | 99 => {
+                let n = if op_num == 98 { transport.0.beat() } else { transport.0.bar() };
+                let num = &mut num_query.get_mut(*id).unwrap().0;
+                if *num != n {
+                    *num = n;
+                    lt_to_open = Some(-1);
+                }
+            }
+            // xruns
+            102 => {
+                let counts = audio_status.0.counts().map(|n| n as f32);
+                let arr = &mut arr_query.get_mut(*id).unwrap().0;
+                if *arr != counts {
+                    *arr = counts.to_vec();
+                    num_query.get_mut(*id).unwrap().0 = counts[0] + counts[1];
+                    // both num and arr changed
+                    for hole in holes {
+                        if let Ok(bh) = black_hole_query.get(*hole) {
+                            if let Ok(mut wh) = white_hole_query.get_mut(bh.wh) {
+                                if wh.link_types.0 == -1 {
+                                    wh.open = true;
+                                }
+                            }
+                        }
+                    }
+                    lt_to_open = Some(-13);
+                }
+            }
+            // phase(div)
+            100 => {
+                let div = op.trim_start_matches("phase(").trim_end_matches(')').trim();
+                let div = parse_with_constants(div).unwrap_or(1.);
+                let n = transport.0.phase(div);
+                let num = &mut num_query.get_mut(*id).unwrap().0;
+                if *num != n {
+                    *num = n;
+                    lt_to_open = Some(-1);
+                }
+            }
             // -------------------- audio nodes --------------------
-            // var()
+            // var() | trig()
             61 => {
+                let num = num_query.get(*id).unwrap().0;
                 if op_changed_query.get(*id).unwrap().0 {
-                    let net = &mut net_query.get_mut(*id).unwrap().0;
-                    let inputs = &mut net_ins_query.get_mut(*id).unwrap().0;
-                    let input = shared(0.);
-                    *net = Net::wrap(Box::new(var(&input)));
-                    inputs.push(input);
+                    let queue = EventQueue::default();
+                    let clock = timing.clock.clone();
+                    let var = if op == "trig()" {
+                        TimedVar::trig(queue.clone(), clock)
+                    } else {
+                        TimedVar::new(queue.clone(), clock, num)
+                    };
+                    net_query.get_mut(*id).unwrap().0 = Net::wrap(Box::new(An(var)));
+                    commands.entity(*id).insert(VarEvents(queue, num));
                     lt_to_open = Some(0);
-                }
-                let num = num_query.get_mut(*id).unwrap();
-                if let Some(var) = &net_ins_query.get(*id).unwrap().0.first() {
-                    var.set_value(num.0);
+                } else if let Ok(mut events) = var_events_query.get_mut(*id) {
+                    // compared to what was pushed, not change ticks. those miss
+                    // numbers changed after this circle ran in the same frame
+                    if events.1 != num {
+                        events.0.push(num, event_time);
+                        events.1 = num;
+                    }
                 }
             }
             // in() | adc()
-            62 => {
-                if op_changed_query.get(*id).unwrap().0 {
-                    let net = &mut net_query.get_mut(*id).unwrap().0;
-                    let lr = input_receivers.0.clone();
-                    let rr = input_receivers.1.clone();
-                    *net = Net::wrap(Box::new(An(InputNode::new(lr, rr))));
-                    lt_to_open = Some(0);
-                }
+            62 if op_changed_query.get(*id).unwrap().0 => {
+                let net = &mut net_query.get_mut(*id).unwrap().0;
+                *net = Net::wrap(Box::new(An(InputNode::new())));
+                lt_to_open = Some(0);
             }
             // monitor() | timer()
             63 | 64 => {
@@ -1414,17 +1517,15 @@ pub fn process(
                 }
             }
             // buffin()
-            93 => {
-                if op_changed_query.get(*id).unwrap().0
-                    || num_query.get_mut(*id).unwrap().is_changed()
-                {
-                    let n = num_query.get(*id).unwrap().0 as usize;
-                    let (s, r) = crossbeam_channel::bounded(n.clamp(1, 100000));
-                    let buffin = Net::wrap(Box::new(An(BuffIn::new(s.clone()))));
-                    net_query.get_mut(*id).unwrap().0 = buffin;
-                    commands.entity(*id).insert(FloatChannel(s, r));
-                    lt_to_open = Some(0);
-                }
+            93 if op_changed_query.get(*id).unwrap().0
+                || num_query.get_mut(*id).unwrap().is_changed() =>
+            {
+                let n = num_query.get(*id).unwrap().0 as usize;
+                let (s, r) = crossbeam_channel::bounded(n.clamp(1, 100000));
+                let buffin = Net::wrap(Box::new(An(BuffIn::new(s.clone()))));
+                net_query.get_mut(*id).unwrap().0 = buffin;
+                commands.entity(*id).insert(FloatChannel(s, r));
+                lt_to_open = Some(0);
             }
             // buffout()
             94 => {
@@ -1648,6 +1749,110 @@ pub fn process(
                     lt_to_open = Some(0);
                 }
             }
+            // poly()
+            // uses the array to store the held notes
+            97 => {
+                let op_changed = op_changed_query.get(*id).unwrap().0;
+                let lost = lost_wh_query.get(*id).unwrap().0;
+                let mut changed = false;
+                let mut input = None;
+                for hole in holes {
+                    if let Ok(wh) = white_hole_query.get(*hole) {
+                        if wh.link_types == (0, 1) {
+                            input = Some(wh.bh_parent);
+                            if wh.open {
+                                changed = true;
+                            }
+                        }
+                        if wh.link_types == (-13, 2) && wh.open {
+                            if let Ok(NoteChannel(s)) = note_chan_query.get(*id) {
+                                let arr = &arr_query.get(wh.bh_parent).unwrap().0;
+                                for event in arr.chunks_exact(3) {
+                                    s.push(event[0], event[1], event[2]);
+                                }
+                            }
+                        }
+                        // held notes, diffed against the ones stored in our array
+                        if wh.link_types == (-13, 3) && wh.open {
+                            if let Ok(NoteChannel(s)) = note_chan_query.get(*id) {
+                                let held = arr_query.get(wh.bh_parent).unwrap().0.clone();
+                                let prev = &mut arr_query.get_mut(*id).unwrap().0;
+                                for p in prev.iter().filter(|p| !held.contains(p)) {
+                                    s.push(*p, 0., 0.);
+                                }
+                                for p in held.iter().filter(|p| !prev.contains(p)) {
+                                    s.push(*p, 1., 1.);
+                                }
+                                *prev = held;
+                            }
+                        }
+                    }
+                }
+                if changed || lost || op_changed {
+                    if let Some(input) = input {
+                        let net = net_query.get(input).unwrap().0.clone();
+                        if (1..=3).contains(&net.inputs()) && (1..=2).contains(&net.outputs()) {
+                            // poly(n) or poly(n, q)
+                            let args = op.trim_start_matches("poly(").trim_end_matches(')');
+                            let mut args = args.split(',').map(str::trim);
+                            let n = args.next().and_then(|n| n.parse::<usize>().ok());
+                            let quietest = args.next() == Some("q");
+                            let n = n.unwrap_or(4).clamp(1, 64);
+                            let notes = NoteQueue::default();
+                            // a net of its own for every voice, made from scratch
+                            // if it's an op (a copy of the input's net otherwise)
+                            let input_op = &op_query.get(input).unwrap().0;
+                            let fresh = op_num_query.get(input).unwrap().0 == 0;
+                            let same = |v: &Net| {
+                                v.inputs() == net.inputs() && v.outputs() == net.outputs()
+                            };
+                            let voices = (0..n)
+                                .map(|_| {
+                                    let voice =
+                                        if fresh { str_to_net(input_op).ok() } else { None };
+                                    voice.filter(same).unwrap_or_else(|| net.clone())
+                                })
+                                .collect();
+                            let poly = Poly::new(voices, notes.clone(), quietest);
+                            net_query.get_mut(*id).unwrap().0 = Net::wrap(Box::new(poly));
+                            commands.entity(*id).insert(NoteChannel(notes));
+                            // the new voices hold nothing. forget what was held so
+                            // the next change sends the held notes again
+                            arr_query.get_mut(*id).unwrap().0.clear();
+                            lt_to_open = Some(0);
+                        }
+                    } else {
+                        net_query.get_mut(*id).unwrap().0 = Net::new(0, 0);
+                    }
+                }
+            }
+            // clock(div)
+            101 if op_changed_query.get(*id).unwrap().0 => {
+                let div = op.trim_start_matches("clock(").trim_end_matches(')').trim();
+                let div = parse_with_constants(div).unwrap_or(1.);
+                let clock = Clock::new(transport.0.clone(), div);
+                net_query.get_mut(*id).unwrap().0 = Net::wrap(Box::new(An(clock)));
+                lt_to_open = Some(0);
+            }
+            // scope(size) | spectrum(size)
+            103 | 104 if op_changed_query.get(*id).unwrap().0 => {
+                let size = op.split(['(', ')']).nth(1).unwrap_or("").trim();
+                let size = parse_with_constants(size).map_or(1024, |n| n as usize);
+                let size = if op_num == 103 {
+                    size.clamp(2, 1 << 15)
+                } else {
+                    size.clamp(64, 1 << 15).next_power_of_two()
+                };
+                let (snoop, backend) = snoop(size * 2);
+                net_query.get_mut(*id).unwrap().0 = Net::wrap(Box::new(backend));
+                commands.entity(*id).insert(Scope {
+                    snoop,
+                    size,
+                    spectrum: op_num == 104,
+                    drawn: 0,
+                });
+                lt_to_open = Some(0);
+            }
             // wave()
             75 => {
                 for hole in holes {
@@ -1692,7 +1897,8 @@ pub fn process(
                         let arr = &arr_query.get(arr).unwrap().0;
                         let op_str = &op_query.get(op_str).unwrap().0;
                         for i in arr {
-                            let net = str_to_net(&op_str.replace('#', &format!("{}", i)));
+                            let op_str = op_str.replace('#', &format!("{}", i));
+                            let Ok(net) = str_to_net(&op_str) else { continue };
                             if empty {
                                 graph = net;
                                 empty = false;
@@ -1740,13 +1946,16 @@ pub fn process(
                 }
                 if changed || lost || op_changed || num_changed {
                     let mut graph = Net::new(0, 0);
+                    let mut part = Part::Circle(*id);
                     let mut empty = true;
                     let n = num_query.get(*id).unwrap().0.max(1.) as i32;
                     for _ in 0..n {
                         for i in inputs.iter().flatten() {
                             let net = net_query.get(*i).unwrap().0.clone();
+                            let net_part = parts.get(*i, op_num_query.get(*i).unwrap().0);
                             if empty {
                                 graph = net;
+                                part = net_part;
                                 empty = false;
                             } else if graph.outputs() == net.outputs() {
                                 if graph.size() >= node_limit.0 {
@@ -1757,10 +1966,12 @@ pub fn process(
                                 } else {
                                     graph = graph * net;
                                 }
+                                part = Part::join(op_num, part, net_part);
                             }
                         }
                     }
                     net_query.get_mut(*id).unwrap().0 = graph;
+                    parts.0.insert(*id, part);
                     lt_to_open = Some(0);
                 }
             }
@@ -1785,13 +1996,16 @@ pub fn process(
                     }
                 }
                 if changed || lost || op_changed {
-                    if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
-                        let lhs = net_query.get(lhs).unwrap().0.clone();
-                        let rhs = net_query.get(rhs).unwrap().0.clone();
+                    if let (Some(lhs_id), Some(rhs_id)) = (lhs, rhs) {
+                        let lhs = net_query.get(lhs_id).unwrap().0.clone();
+                        let rhs = net_query.get(rhs_id).unwrap().0.clone();
                         if lhs.outputs() == rhs.outputs() {
                             let graph = lhs - rhs;
                             if graph.size() < node_limit.0 {
                                 net_query.get_mut(*id).unwrap().0 = graph;
+                                let lhs = parts.get(lhs_id, op_num_query.get(lhs_id).unwrap().0);
+                                let rhs = parts.get(rhs_id, op_num_query.get(rhs_id).unwrap().0);
+                                parts.0.insert(*id, Part::join(op_num, lhs, rhs));
                             }
                         }
                     }
@@ -1821,13 +2035,16 @@ pub fn process(
                 }
                 if changed || lost || op_changed || num_changed {
                     let mut graph = Net::new(0, 0);
+                    let mut part = Part::Circle(*id);
                     let mut empty = true;
                     let n = num_query.get(*id).unwrap().0.max(1.) as i32;
                     for _ in 0..n {
                         for i in inputs.iter().flatten() {
                             let net = net_query.get(*i).unwrap().0.clone();
+                            let net_part = parts.get(*i, op_num_query.get(*i).unwrap().0);
                             if empty {
                                 graph = net;
+                                part = net_part;
                                 empty = false;
                             } else {
                                 if graph.size() >= node_limit.0 {
@@ -1840,12 +2057,14 @@ pub fn process(
                                     86 => graph = graph | net,
                                     87 if gi == ni && go == no => graph = graph & net,
                                     88 if gi == ni => graph = graph ^ net,
-                                    _ => {}
+                                    _ => continue,
                                 }
+                                part = Part::join(op_num, part, net_part);
                             }
                         }
                     }
                     net_query.get_mut(*id).unwrap().0 = graph;
+                    parts.0.insert(*id, part);
                     lt_to_open = Some(0);
                 }
             }
@@ -1867,10 +2086,14 @@ pub fn process(
                 }
                 if changed || lost || op_changed {
                     let mut graph = Net::new(0, 0);
+                    let mut part = Part::Circle(*id);
                     if let Some(input) = input {
                         graph = net_query.get(input).unwrap().0.clone();
+                        let input_part = parts.get(input, op_num_query.get(input).unwrap().0);
+                        part = Part::Thru(Box::new(input_part));
                     }
                     net_query.get_mut(*id).unwrap().0 = !graph;
+                    parts.0.insert(*id, part);
                     lt_to_open = Some(0);
                 }
             }
@@ -1891,22 +2114,29 @@ pub fn process(
                     }
                 }
                 if changed || lost || op_changed {
-                    if let Some(input) = input {
-                        let net = net_query.get(input).unwrap().0.clone();
-                        if net.inputs() == 0 && net.outputs() == 1 {
-                            slot.0.set(Fade::Smooth, 0.01, Box::new(net | dc(0.)));
-                        } else if net.inputs() == 0 && net.outputs() == 2 {
-                            slot.0.set(Fade::Smooth, 0.01, Box::new(net));
-                        } else {
-                            slot.0.set(Fade::Smooth, 0.01, Box::new(dc(0.) | dc(0.)));
-                        }
-                    } else {
-                        slot.0.set(Fade::Smooth, 0.01, Box::new(dc(0.) | dc(0.)));
+                    // start over (fading to it) when this out() takes the output,
+                    // or the output was reopened. otherwise commit the changes
+                    if op_changed || slot.is_changed() || out_graph.owner != Some(*id) {
+                        *out_graph = OutGraph::default();
+                        out_graph.owner = Some(*id);
+                    }
+                    parts.0.retain(|e, _| op_num_query.contains(*e));
+                    let part = input.map(|i| parts.get(i, op_num_query.get(i).unwrap().0));
+                    let backend = out_graph.set(part.as_ref(), |e| {
+                        let net = net_query.get_mut(e).ok()?;
+                        Some((net.0.clone(), net.last_changed()))
+                    });
+                    if let Some(backend) = backend {
+                        slot.0.set(Fade::Smooth, 0.01, Box::new(backend));
                     }
                 }
             }
             _ => {}
         }
+        // the net was rebuilt, time it
+        if lt_to_open == Some(0) && profiler.enabled {
+            profiler.probe(*id, &mut net_query.get_mut(*id).unwrap().0);
+        }
         // open all white holes reading whatever changed
         if let Some(lt) = lt_to_open {
             for hole in holes {
@@ -1921,11 +2151,52 @@ pub fn process(
         }
         op_changed_query.get_mut(*id).unwrap().0 = false;
         lost_wh_query.get_mut(*id).unwrap().0 = false;
-        // close the white holes we just read
+        // close the white holes we just read (and light up their connections)
         for hole in holes {
             if let Ok(mut wh) = white_hole_query.get_mut(*hole) {
-                wh.open = false;
+                if wh.open {
+                    wh.open = false;
+                    if let Ok(arrow) = arrow_query.get(*hole) {
+                        commands.entity(arrow.0).try_insert(Flash(1.));
+                    }
+                }
             }
         }
     }
 }
+
+// remove a white hole that lost its black hole (or its black hole's parent)
+// from its parent's holes, and despawn whatever is left of the connection
+fn remove_dangling(
+    commands: &mut Commands,
+    parent: Entity,
+    wh: Entity,
+    bh: Entity,
+    bh_parent: Entity,
+) {
+    commands.add(move |world: &mut World| {
+        if let Some(mut holes) = world.get_mut::<Holes>(parent) {
+            holes.0.retain(|h| *h != wh);
+        }
+        if let Some(mut holes) = world.get_mut::<Holes>(bh_parent) {
+            holes.0.retain(|h| *h != bh);
+        }
+        let mut despawn = vec![wh, bh];
+        for hole in [wh, bh] {
+            if let Some(arrow) = world.get::<ConnectionArrow>(hole) {
+                despawn.push(arrow.0);
+            }
+            if let Some(text) = world.get::<InfoText>(hole) {
+                despawn.push(text.0);
+            }
+            if let Some(highlight) = world.get::<Highlight>(hole) {
+                despawn.push(highlight.0);
+            }
+        }
+        for e in despawn {
+            if let Some(e) = world.get_entity_mut(e) {
+                e.despawn();
+            }
+        }
+    });
+}
@@ -0,0 +1,96 @@
+use bevy::{prelude::*, sprite::Mesh2dHandle, utils::HashSet};
+
+use crate::components::*;
+
+// seconds between measurements
+const PERIOD: f32 = 0.5;
+
+pub fn measure_load(
+    time: Res<Time>,
+    mut profiler: ResMut<Profiler>,
+    mut elapsed: Local<f32>,
+    circles: Query<(), With<Vertices>>,
+) {
+    *elapsed += time.delta_seconds();
+    if *elapsed < PERIOD {
+        return;
+    }
+    *elapsed = 0.;
+    let (busy, budget) = profiler.dsp.take();
+    // no output device (or it's stuck), nothing to compare against
+    if budget == 0 {
+        profiler.load = 0.;
+        for (_, load) in profiler.circles.values_mut() {
+            *load = 0.;
+        }
+        return;
+    }
+    profiler.load = busy as f32 / budget as f32;
+    profiler.circles.retain(|e, _| circles.contains(*e));
+    for (time, load) in profiler.circles.values_mut() {
+        *load = time.take() as f32 / budget as f32;
+    }
+}
+
+// a ring behind every profiled circle, from blue to red by its share of the load
+pub fn heat_overlay(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    profiler: Res<Profiler>,
+    circles: Query<(&Vertices, &Transform)>,
+    mut markers: Query<
+        (Entity, &Heat, &mut Transform, &mut Mesh2dHandle, &Handle<ColorMaterial>),
+        Without<Vertices>,
+    >,
+    polygon_handles: Res<PolygonHandles>,
+) {
+    let mut marked = HashSet::new();
+    for (e, heat, mut trans, mut mesh, mat) in markers.iter_mut() {
+        let load = profiler.circles.get(&heat.0).filter(|_| profiler.enabled);
+        let (Some((_, load)), Ok((v, t))) = (load, circles.get(heat.0)) else {
+            commands.entity(e).despawn();
+            continue;
+        };
+        marked.insert(heat.0);
+        *trans = heat_transform(t);
+        if let Some(handle) = &polygon_handles.0[v.0] {
+            if mesh.0 != handle.0 {
+                *mesh = handle.clone();
+            }
+        }
+        if let Some(mat) = materials.get_mut(mat) {
+            mat.color = heat_color(*load, profiler.load);
+        }
+    }
+    if !profiler.enabled {
+        return;
+    }
+    for (circle, (_, load)) in &profiler.circles {
+        if marked.contains(circle) {
+            continue;
+        }
+        let Ok((v, t)) = circles.get(*circle) else { continue };
+        commands.spawn((
+            ColorMesh2dBundle {
+                mesh: polygon_handles.0[v.0].clone().unwrap(),
+                material: materials.add(ColorMaterial::from(heat_color(*load, profiler.load))),
+                transform: heat_transform(t),
+                ..default()
+            },
+            Heat(*circle),
+        ));
+    }
+}
+
+fn heat_transform(t: &Transform) -> Transform {
+    Transform {
+        translation: t.translation.xy().extend(t.translation.z - 0.00002),
+        scale: Vec3::new(t.scale.x + 12., t.scale.y + 12., 1.),
+        rotation: t.rotation,
+    }
+}
+
+fn heat_color(load: f32, total: f32) -> Color {
+    let share = if total > 0. { (load / total).clamp(0., 1.) } else { 0. };
+    Color::hsla(240. * (1. - share), 1., 0.5, 0.3 + 0.5 * share)
+}
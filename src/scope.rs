@@ -0,0 +1,105 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::PrimitiveTopology, render_asset::RenderAssetUsages},
+    sprite::Mesh2dHandle,
+    utils::HashSet,
+};
+
+use crate::{components::*, functions::*};
+
+// vertices in a trace
+const POINTS: usize = 256;
+// how much of the circle's radius a trace covers
+const EXTENT: f32 = 0.7;
+// the bottom of a spectrum (dB)
+const FLOOR: f32 = -90.;
+
+// a line inside every scope() and spectrum() circle, redrawn when new samples
+// come in. the waveform is between -1 and 1, the spectrum between FLOOR and 0 dB
+pub fn draw_scopes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut scopes: Query<(Entity, &mut Scope, &OpNum, &Transform, &Col)>,
+    mut traces: Query<
+        (Entity, &Trace, &mut Transform, &Mesh2dHandle, &Handle<ColorMaterial>),
+        Without<Scope>,
+    >,
+) {
+    let mut drawn = HashSet::new();
+    for (e, trace, mut trans, mesh, mat) in traces.iter_mut() {
+        let Ok((_, mut scope, op_num, t, col)) = scopes.get_mut(trace.0) else {
+            commands.entity(e).despawn();
+            continue;
+        };
+        // the op changed to something else
+        if !matches!(op_num.0, 103 | 104) {
+            commands.entity(trace.0).remove::<Scope>();
+            commands.entity(e).despawn();
+            continue;
+        }
+        drawn.insert(trace.0);
+        *trans = trace_transform(t);
+        if let Some(mat) = materials.get_mut(mat) {
+            mat.color = trace_color(col);
+        }
+        scope.snoop.update();
+        if scope.snoop.total() == scope.drawn {
+            continue;
+        }
+        scope.drawn = scope.snoop.total();
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, trace_points(&scope));
+        }
+    }
+    for (e, mut scope, op_num, t, col) in scopes.iter_mut() {
+        if drawn.contains(&e) || !matches!(op_num.0, 103 | 104) {
+            continue;
+        }
+        scope.snoop.update();
+        scope.drawn = scope.snoop.total();
+        let mesh = Mesh::new(PrimitiveTopology::LineStrip, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, trace_points(&scope));
+        commands.spawn((
+            ColorMesh2dBundle {
+                mesh: meshes.add(mesh).into(),
+                material: materials.add(ColorMaterial::from(trace_color(col))),
+                transform: trace_transform(t),
+                ..default()
+            },
+            Trace(e),
+        ));
+    }
+}
+
+fn trace_points(scope: &Scope) -> Vec<[f32; 3]> {
+    let ys: Vec<f32> = if scope.spectrum {
+        let levels = spectrum_trace(&scope.snoop, scope.size, POINTS);
+        levels.into_iter().map(|db| (db.max(FLOOR) - FLOOR) / -FLOOR * 2. - 1.).collect()
+    } else {
+        scope_trace(&scope.snoop, scope.size, POINTS)
+    };
+    let last = ys.len().saturating_sub(1).max(1) as f32;
+    ys.iter()
+        .enumerate()
+        .map(|(i, y)| {
+            let y = if y.is_finite() { y.clamp(-1., 1.) } else { 0. };
+            [(i as f32 / last * 2. - 1.) * EXTENT, y * EXTENT, 0.]
+        })
+        .collect()
+}
+
+// upright, in front of the circle
+fn trace_transform(t: &Transform) -> Transform {
+    Transform {
+        translation: t.translation.xy().extend(t.translation.z + 0.00001),
+        scale: Vec3::new(t.scale.x, t.scale.y, 1.),
+        ..default()
+    }
+}
+
+// dark on light circles, light on dark ones
+fn trace_color(col: &Col) -> Color {
+    let lightness = if col.0.lightness > 0.6 { 0.1 } else { 0.9 };
+    Color::hsla(col.0.hue, col.0.saturation, lightness, 1.)
+}
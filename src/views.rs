@@ -0,0 +1,64 @@
+use bevy::{
+    core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
+    prelude::*,
+    render::{camera::RenderTarget, view::RenderLayers},
+    window::{PrimaryWindow, WindowRef},
+};
+
+use crate::components::*;
+
+// another window with its own camera, starting where the main camera is.
+// it only shows these render layers (and has no ui)
+pub fn open_view(commands: &mut Commands, layers: RenderLayers, transform: Transform, scale: f32) {
+    let window = commands.spawn(Window { title: String::from("awawawa (view)"), ..default() }).id();
+    let mut bundle = Camera2dBundle {
+        camera: Camera {
+            hdr: true,
+            target: RenderTarget::Window(WindowRef::Entity(window)),
+            ..default()
+        },
+        transform,
+        ..default()
+    };
+    bundle.projection.scale = scale;
+    commands.spawn((bundle, BloomSettings::default(), layers, ViewCamera));
+}
+
+// views look like the main window (bloom and tonemapping follow it),
+// and go away with their windows
+pub fn sync_views(
+    mut commands: Commands,
+    main: Query<(Ref<BloomSettings>, Ref<Tonemapping>), With<MainCamera>>,
+    mut views: Query<
+        (Entity, &Camera, Ref<ViewCamera>, &mut BloomSettings, &mut Tonemapping),
+        Without<MainCamera>,
+    >,
+    windows: Query<(), With<Window>>,
+) {
+    let Ok((main_bloom, main_tonemapping)) = main.get_single() else { return };
+    for (e, camera, view, mut bloom, mut tonemapping) in views.iter_mut() {
+        if let RenderTarget::Window(WindowRef::Entity(window)) = camera.target {
+            if !windows.contains(window) {
+                commands.entity(e).despawn();
+                continue;
+            }
+        }
+        if view.is_added() || main_bloom.is_changed() {
+            *bloom = main_bloom.clone();
+        }
+        if view.is_added() || main_tonemapping.is_changed() {
+            *tonemapping = *main_tonemapping;
+        }
+    }
+}
+
+// the mouse only does things in the main window. clicks on the others are
+// taken away (everything reads the mouse as if it was on the main window)
+pub fn ignore_view_clicks(
+    mut mouse_button_input: ResMut<ButtonInput<MouseButton>>,
+    windows: Query<&Window, Without<PrimaryWindow>>,
+) {
+    if windows.iter().any(|w| w.cursor_position().is_some()) {
+        mouse_button_input.reset_all();
+    }
+}
@@ -0,0 +1,59 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+#[path = "../src/functions.rs"]
+mod functions;
+
+#[path = "../src/completion.rs"]
+mod completion;
+
+use completion::*;
+use functions::{str_to_net, str_to_op_num, OpError};
+
+// every op in the completion table should be something quartz knows about
+#[test]
+fn ops_exist() {
+    for (name, _, _) in OPS {
+        if str_to_op_num(name) != 0 {
+            continue;
+        }
+        let op = format!("{}()", name);
+        assert!(
+            !matches!(str_to_net(&op), Err(OpError::Unknown(_))),
+            "{} is in the completion table but isn't an op",
+            name
+        );
+    }
+}
+
+#[test]
+fn complete_commands() {
+    assert_eq!(complete(":ts"), Some(":tsel ".to_string()));
+    assert_eq!(complete(":reset_c"), Some(":reset_cam ".to_string()));
+    assert_eq!(complete(":set n"), Some(":set n ".to_string()));
+    assert_eq!(complete(":set n 4v0; :ts"), Some(":set n 4v0; :tsel ".to_string()));
+    assert_eq!(complete("o"), None);
+    assert_eq!(complete(""), None);
+}
+
+#[test]
+fn complete_ops() {
+    assert_eq!(complete(":set op lowp"), Some(":set op lowpass(".to_string()));
+    assert_eq!(complete(":set op 4v0 sin"), Some(":set op 4v0 sin()".to_string()));
+    assert_eq!(complete(":set op var"), Some(":set op var()".to_string()));
+    assert_eq!(complete(":set op proc"), Some(":set op process".to_string()));
+    assert_eq!(complete(":set op reverb_"), Some(":set op reverb_mono(".to_string()));
+    assert_eq!(complete(":set op lowpass(1"), None);
+}
+
+#[test]
+fn fuzzy() {
+    assert_eq!(matches(&Context::Op("lwps"))[0].0, "lowpass");
+    assert_eq!(matches(&Context::Op("rvb"))[0].0, "reverb_mono");
+    assert!(matches(&Context::Op("zzzz")).is_empty());
+    assert_eq!(fuzzy_score("", "sine"), Some(0));
+    assert!(fuzzy_score("sin", "sine") > fuzzy_score("sin", "asin"));
+    assert!(fuzzy_score("sin", "asin") > fuzzy_score("sin", "signum"));
+}
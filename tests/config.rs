@@ -0,0 +1,156 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+#[path = "../src/functions.rs"]
+mod functions;
+
+#[path = "../src/config.rs"]
+mod config;
+
+use bevy::{color::Hsla, input::keyboard::KeyCode};
+use config::*;
+
+#[test]
+fn defaults() {
+    let (c, warnings) = Config::parse("");
+    assert!(warnings.is_empty());
+    assert_eq!(c, Config::default());
+    assert_eq!(c.node_limit, 500);
+    assert_eq!(c.osc.port, 1729);
+}
+
+#[test]
+fn full() {
+    let (c, warnings) = Config::parse(
+        r#"
+scene = "scenes/start.cor"
+node_limit = 1000
+text_size = 0.2
+draw_verts = 6
+connection_width = 2
+
+[osc]
+host = "192.168.1.5"
+port = 9000
+listen = 9001
+
+[colors]
+clear = [0, 0, 0.1]
+draw = [120, 1, 0.5, 0.8]
+
+[bloom]
+intensity = 0.5
+
+[update]
+focused = 120
+unfocused = 10
+
+[audio]
+host = 1
+out_device = 2
+sample_rate = 48000
+buffer = 256
+
+[keys]
+F5 = ":play"
+"ctrl+shift+s" = ":w backup.cor"
+"#,
+    );
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_eq!(c.scene.as_deref(), Some("scenes/start.cor"));
+    assert_eq!((c.node_limit, c.text_size, c.draw_verts), (1000, 0.2, 6));
+    assert_eq!(c.connection_width, 2.);
+    assert_eq!((c.osc.host.as_str(), c.osc.port, c.osc.listen), ("192.168.1.5", 9000, Some(9001)));
+    assert_eq!(c.colors.clear, Hsla::new(0., 0., 0.1, 1.));
+    assert_eq!(c.colors.draw, Hsla::new(120., 1., 0.5, 0.8));
+    assert_eq!(c.colors.command, Config::default().colors.command);
+    assert_eq!(c.bloom.intensity, 0.5);
+    assert_eq!(c.bloom.low_frequency_boost, 0.6);
+    assert_eq!(c.update, (120., 10.));
+    assert_eq!(c.audio.host, Some(1));
+    assert_eq!(c.audio.out_device, Some(2));
+    assert_eq!(c.audio.in_device, None);
+    assert_eq!((c.audio.sample_rate, c.audio.buffer), (Some(48000), Some(256)));
+    assert_eq!(c.keys.len(), 2);
+    assert_eq!((c.keys[0].key, c.keys[0].command.as_str()), (KeyCode::F5, ":play"));
+    let save = &c.keys[1];
+    assert_eq!((save.key, save.ctrl, save.shift, save.alt), (KeyCode::KeyS, true, true, false));
+}
+
+#[test]
+fn bad_values() {
+    // what's wrong is reported and left at the default, the rest still applies
+    let (c, warnings) = Config::parse(
+        r#"
+node_limit = -4
+draw_verts = 100
+fps = 60
+osc = 5
+
+[colors]
+draw = [1, 2]
+
+[audio]
+sample_rate = 44100
+
+[keys]
+"hyper+x" = ":play"
+F6 = 3
+"#,
+    );
+    assert_eq!(
+        warnings,
+        [
+            "node_limit: expected an integer",
+            "draw_verts: expected an integer in 3..=64",
+            "unknown key: fps",
+            "osc: expected a table",
+            "colors.draw: expected [hue, saturation, lightness, alpha]",
+            "keys.\"hyper+x\": unknown key",
+            "keys.\"F6\": expected a command",
+        ]
+    );
+    assert_eq!(c.node_limit, 500);
+    assert_eq!(c.draw_verts, 4);
+    assert_eq!(c.audio.sample_rate, Some(44100));
+    assert!(c.keys.is_empty());
+    // a syntax error keeps all the defaults
+    let (c, warnings) = Config::parse("node_limit = = 3");
+    assert_eq!(c, Config::default());
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn keys() {
+    assert_eq!(str_to_key("a"), Some(KeyCode::KeyA));
+    assert_eq!(str_to_key("Z"), Some(KeyCode::KeyZ));
+    assert_eq!(str_to_key("7"), Some(KeyCode::Digit7));
+    assert_eq!(str_to_key("f12"), Some(KeyCode::F12));
+    assert_eq!(str_to_key("f13"), None);
+    assert_eq!(str_to_key("f0"), None);
+    assert_eq!(str_to_key("PageUp"), Some(KeyCode::PageUp));
+    assert_eq!(str_to_key("["), Some(KeyCode::BracketLeft));
+    let b = str_to_binding("Ctrl+Alt+left").unwrap();
+    assert_eq!((b.key, b.ctrl, b.shift, b.alt), (KeyCode::ArrowLeft, true, false, true));
+    assert!(str_to_binding("ctrl+").is_none());
+    assert!(str_to_binding("ctrl+nope").is_none());
+}
+
+#[test]
+fn load() {
+    let path = std::env::temp_dir().join(format!("quartz-config-{}.toml", std::process::id()));
+    std::fs::write(&path, "node_limit = 42\n").unwrap();
+    let args = ["--config".to_string(), path.display().to_string()];
+    let (c, warnings) = Config::load(args);
+    assert!(warnings.is_empty());
+    assert_eq!(c.node_limit, 42);
+    let (c, _) = Config::load([format!("--config={}", path.display())]);
+    assert_eq!(c.node_limit, 42);
+    std::fs::remove_file(&path).unwrap();
+    // a missing file that was asked for is reported
+    let (c, warnings) = Config::load(["-c".to_string(), "/nonexistent/quartz.toml".to_string()]);
+    assert_eq!(c, Config::default());
+    assert_eq!(warnings.len(), 1);
+}
@@ -0,0 +1,354 @@
+// randomly spawn, connect, and delete circles while process runs
+//
+// deletion here is abrupt (straight despawns, no cleanup) so process and
+// friends see holes, targets, and parents that just vanished.
+// (plus a few fixed graphs that need the same setup)
+
+#![allow(dead_code, clippy::type_complexity, clippy::too_many_arguments)]
+
+#[path = "../src/components.rs"]
+mod components;
+#[path = "../src/connections.rs"]
+mod connections;
+#[path = "../src/functions.rs"]
+mod functions;
+#[path = "../src/graph.rs"]
+mod graph;
+#[path = "../src/layout.rs"]
+mod layout;
+#[path = "../src/nodes.rs"]
+mod nodes;
+#[path = "../src/osc.rs"]
+mod osc;
+#[path = "../src/process.rs"]
+mod process;
+
+use bevy::{
+    color::Hsla, input::keyboard::KeyboardInput, prelude::*,
+    render::view::screenshot::ScreenshotManager, sprite::Mesh2dHandle, winit::WinitSettings,
+};
+use fundsp::hacker32::*;
+
+use {components::*, connections::*, functions::*, osc::*, process::*};
+
+const OPS: &[&str] = &[
+    "empty",
+    "sum",
+    "product",
+    "collect",
+    "zip",
+    "unzip",
+    "push",
+    "pop",
+    "len",
+    "append",
+    "slice",
+    "resize",
+    "contains",
+    "set",
+    "get",
+    "repeat",
+    "store",
+    "num_push",
+    "spawn",
+    "del_target",
+    "connect_target",
+    "isolate_target",
+    "target_lt",
+    "distro",
+    "reorder",
+    "open_target",
+    "close_target",
+    "open_nth",
+    "spin_target",
+    "process",
+    "sine(440)",
+    "lowpass()",
+    "mul(0.5)",
+    "var()",
+    "kr()",
+    "+",
+    "*",
+    "-",
+    ">>",
+    "|",
+    "&",
+    "^",
+    "!",
+    "sum()",
+    "product()",
+    "pipe()",
+    "stack()",
+    "bus()",
+    "branch()",
+    "feedback()",
+    "select()",
+    "seq()",
+    "out()",
+    "scope()",
+    "spectrum(64)",
+];
+
+// (black, white) link types that ops actually look for
+const LINK_TYPES: &[(i8, i8)] = &[
+    (-1, 1),
+    (-1, 2),
+    (-1, 3),
+    (-1, -1),
+    (-1, -3),
+    (-1, -11),
+    (-2, -2),
+    (-6, -6),
+    (-12, -12),
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (-13, 1),
+    (-13, 2),
+    (-13, -13),
+    (-13, -1),
+    (-14, 1),
+    (-14, 2),
+    (-14, -14),
+];
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // xorshift64
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    fn pick<'a, T>(&mut self, from: &'a [T]) -> &'a T {
+        &from[self.below(from.len())]
+    }
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<Queue>()
+        .init_resource::<LoopQueue>()
+        .init_resource::<CursorInfo>()
+        .init_resource::<ButtonInput<MouseButton>>()
+        .init_resource::<ScreenshotManager>()
+        .init_resource::<WinitSettings>()
+        .init_resource::<ClearColor>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .insert_resource(SlotRes(Slot::new(Box::new(dc(0.) | dc(0.))).0))
+        .init_resource::<TransportRes>()
+        .init_resource::<Timing>()
+        .init_resource::<Profiler>()
+        .init_resource::<graph::Parts>()
+        .init_resource::<graph::OutGraph>()
+        .init_resource::<AudioStatusRes>()
+        .init_resource::<Pointers>()
+        .insert_resource(DefaultDrawColor(Hsla::new(330., 1., 0.5, 1.)))
+        .insert_resource(DefaultDrawVerts(4))
+        .insert_resource(HighlightColor(Hsla::new(0.0, 1.0, 0.5, 1.)))
+        .insert_resource(ConnectionColor(Hsla::new(0., 1., 1., 0.7)))
+        .insert_resource(ConnectionWidth(4.))
+        .insert_resource(CommandColor(Hsla::new(0., 0., 0.7, 1.)))
+        .insert_resource(IndicatorColor(Hsla::new(0., 1., 0.5, 0.3)))
+        .insert_resource(Indicator(Entity::PLACEHOLDER))
+        .insert_resource(TextSize(0.1))
+        .insert_resource(NodeLimit(500))
+        .insert_resource(OscSender { host: "127.0.0.1".to_string(), port: 1729 })
+        .insert_resource(OscReceiver { socket: None })
+        .init_resource::<InputRingRes>()
+        .insert_resource(ConnectionMat(Handle::default()))
+        .insert_resource(ArrowHandle(Mesh2dHandle(Handle::default())))
+        .insert_resource(PolygonHandles(vec![Some(Mesh2dHandle(Handle::default())); 65]))
+        .add_event::<OrderChange>()
+        .add_event::<DeleteCommand>()
+        .add_event::<ConnectCommand>()
+        .add_event::<KeyboardInput>()
+        .add_systems(Update, connect_targets)
+        .add_systems(PostUpdate, (sort_by_order, prepare_loop_queue, process).chain());
+    app
+}
+
+fn spawn_circle(world: &mut World, rng: &mut Rng) -> Entity {
+    let (sndr, rcvr) = crossbeam_channel::bounded(1);
+    let op = *rng.pick(OPS);
+    let t = Vec3::new(rng.below(1000) as f32, rng.below(1000) as f32, 0.);
+    world
+        .spawn((
+            Transform::from_translation(t),
+            Vertices(3 + rng.below(8)),
+            Col(Hsla::new(rng.below(360) as f32, 1., 0.5, 1.)),
+            Number(rng.below(8) as f32),
+            Arr((0..rng.below(6)).map(|x| x as f32).collect()),
+            Op(op.to_string()),
+            Targets(Vec::new()),
+            Holes(Vec::new()),
+            Order(1 + rng.below(4)),
+            (
+                OpNum(str_to_op_num(op)),
+                Network(str_to_net(op).unwrap()),
+                NetIns(Vec::new()),
+                OpChanged(true),
+                LostWH(false),
+                NetChannel(sndr, rcvr),
+            ),
+        ))
+        .id()
+}
+
+fn spawn_op(world: &mut World, op: &str, order: usize) -> Entity {
+    let (sndr, rcvr) = crossbeam_channel::bounded(1);
+    world
+        .spawn((
+            Transform::default(),
+            Vertices(4),
+            Col(Hsla::default()),
+            Number(0.),
+            Arr(Vec::new()),
+            Op(op.to_string()),
+            Targets(Vec::new()),
+            Holes(Vec::new()),
+            Order(order),
+            (
+                OpNum(str_to_op_num(op)),
+                Network(str_to_net(op).unwrap()),
+                NetIns(Vec::new()),
+                OpChanged(true),
+                LostWH(false),
+                NetChannel(sndr, rcvr),
+            ),
+        ))
+        .id()
+}
+
+// same shape as what `connect` spawns (minus the meshes)
+fn connect(world: &mut World, src: Entity, snk: Entity, lt: (i8, i8)) {
+    let arrow = world.spawn(Transform::default()).id();
+    let bh = world.spawn((Transform::default(), Col(Hsla::default()), Vertices(3))).id();
+    let wh = world
+        .spawn((
+            Transform::default(),
+            Col(Hsla::default()),
+            Vertices(3),
+            WhiteHole { bh_parent: src, bh, link_types: lt, open: true },
+            ConnectionArrow(arrow),
+        ))
+        .id();
+    world.entity_mut(bh).insert(BlackHole { wh, wh_parent: snk });
+    world.get_mut::<Holes>(src).unwrap().0.push(bh);
+    world.get_mut::<Holes>(snk).unwrap().0.push(wh);
+}
+
+fn fuzz(seed: u64, steps: usize) {
+    let mut app = app();
+    let mut rng = Rng(seed);
+    let mut circles: Vec<Entity> = Vec::new();
+    for _ in 0..steps {
+        let world = app.world_mut();
+        circles.retain(|e| world.get_entity(*e).is_some());
+        match rng.below(10) {
+            0..=2 => circles.push(spawn_circle(world, &mut rng)),
+            3..=5 if circles.len() > 1 => {
+                let src = *rng.pick(&circles);
+                let snk = *rng.pick(&circles);
+                if src != snk {
+                    let lt = *rng.pick(LINK_TYPES);
+                    connect(world, src, snk, lt);
+                }
+            }
+            6 if !circles.is_empty() => {
+                // point targets at whatever, including holes
+                let e = *rng.pick(&circles);
+                let mut targets: Vec<Entity> =
+                    (0..rng.below(4)).map(|_| *rng.pick(&circles)).collect();
+                if let Some(holes) = world.get::<Holes>(e) {
+                    targets.extend(holes.0.first());
+                }
+                world.get_mut::<Targets>(e).unwrap().0 = targets;
+            }
+            7 if !circles.is_empty() => {
+                // delete a circle, leaving its holes and its partners' holes behind
+                let e = circles.swap_remove(rng.below(circles.len()));
+                world.despawn(e);
+            }
+            8 if !circles.is_empty() => {
+                // delete one end of a connection
+                let e = *rng.pick(&circles);
+                let holes = world.get::<Holes>(e).unwrap().0.clone();
+                if !holes.is_empty() {
+                    let hole = *rng.pick(&holes);
+                    world.despawn(hole);
+                }
+            }
+            _ => {
+                // poke some numbers to get things flowing
+                for e in &circles {
+                    if rng.below(2) == 0 {
+                        world.get_mut::<Number>(*e).unwrap().0 = rng.below(4) as f32;
+                    }
+                }
+            }
+        }
+        world.send_event_default::<OrderChange>();
+        app.update();
+    }
+}
+
+#[test]
+fn spawn_connect_delete() {
+    for seed in 1..=32 {
+        fuzz(seed.wrapping_mul(0x9E3779B97F4A7C15), 500);
+    }
+}
+
+// a var() whose number is written by a circle that runs after it in the same frame
+#[test]
+fn var_late_change() {
+    let mut app = app();
+    let world = app.world_mut();
+    let var = spawn_op(world, "var()", 1);
+    let arr = spawn_op(world, "empty", 1);
+    let distro = spawn_op(world, "distro", 2);
+    world.get_mut::<Arr>(arr).unwrap().0 = vec![5.];
+    world.get_mut::<Targets>(distro).unwrap().0 = vec![var];
+    connect(world, arr, distro, (-13, -1));
+    app.update();
+    assert_eq!(app.world().get::<Number>(var).unwrap().0, 5.);
+    app.update();
+    let mut net = app.world().get::<Network>(var).unwrap().0.clone();
+    assert_eq!(net.get_mono(), 5.);
+}
+
+// a circle used twice through `+` keeps sounding (both copies carrying on
+// from where they were) when the graph around it is rebuilt
+#[test]
+fn duplicate_after_rebuild() {
+    let mut app = app();
+    let (slot, mut out) = Slot::new(Box::new(dc(0.) | dc(0.)));
+    let world = app.world_mut();
+    world.insert_resource(SlotRes(slot));
+    let sine = spawn_op(world, "sine(100)", 0);
+    let half = spawn_op(world, "dc(0.5)", 0);
+    let sum = spawn_op(world, "+", 1);
+    let stack = spawn_op(world, "|", 2);
+    let output = spawn_op(world, "out()", 3);
+    world.get_mut::<Number>(sum).unwrap().0 = 2.;
+    connect(world, sine, sum, (0, 0));
+    connect(world, sum, stack, (0, 0));
+    connect(world, stack, output, (0, 1));
+    app.update();
+    let before: Vec<(f32, f32)> = (0..1000).map(|_| out.get_stereo()).collect();
+    assert!(before.iter().any(|(l, _)| *l > 0.5));
+    // the graph as it was, without the rebuild
+    let mut old = out.clone();
+    connect(app.world_mut(), half, stack, (0, 1));
+    app.update();
+    for _ in 0..1000 {
+        assert_eq!(out.get_stereo(), (old.get_stereo().0, 0.5));
+    }
+}
@@ -0,0 +1,432 @@
+// golden-audio regression tests for str_to_net
+//
+// every op gets rendered with fixed parameters and a fixed input for a fixed
+// number of samples, and the result is compared against tests/golden/ops.txt
+// when an op changes on purpose (or after a fundsp bump) regenerate with:
+// QUARTZ_BLESS=1 cargo test --test golden
+
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+#[path = "../src/functions.rs"]
+mod functions;
+
+use fundsp::hacker32::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const SAMPLES: usize = 64;
+const SAMPLE_RATE: f64 = 44100.;
+const TOLERANCE: f32 = 1e-4;
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/ops.txt");
+
+const OPS: &[&str] = &[
+    // sources
+    "sine()",
+    "sine(440)",
+    "saw()",
+    "saw(220)",
+    "square()",
+    "square(330)",
+    "triangle()",
+    "triangle(110)",
+    "organ()",
+    "organ(220)",
+    "pulse()",
+    "brown()",
+    "pink()",
+    "white()",
+    "noise()",
+    "hammond()",
+    "hammond(220)",
+    "zero()",
+    "impulse()",
+    "lorenz()",
+    "rossler()",
+    "constant()",
+    "constant(0.5)",
+    "dc(1, 2)",
+    "dc(1, 2, 3, 4, 5, 6, 7, 8)",
+    "dsf_saw()",
+    "dsf_saw(0.5)",
+    "dsf_square()",
+    "dsf_square(0.5)",
+    "pluck(220, 0.5, 0.5)",
+    "mls()",
+    "mls(12)",
+    "soft_saw()",
+    "soft_saw(220)",
+    "ramp()",
+    // filters
+    "allpole()",
+    "allpole(0.5)",
+    "pinkpass()",
+    "allpass()",
+    "allpass(1)",
+    "allpass(1000, 1)",
+    "bandpass()",
+    "bandpass(1)",
+    "bandpass(1000, 1)",
+    "bandrez()",
+    "bandrez(1)",
+    "bandrez(1000, 1)",
+    "bell()",
+    "bell(1, 2)",
+    "bell(1000, 1, 2)",
+    "biquad(0.1, 0.2, 0.3, 0.4, 0.5)",
+    "butterpass()",
+    "butterpass(1000)",
+    "dcblock()",
+    "dcblock(20)",
+    "fir(0.5)",
+    "fir(0.25, 0.5, 0.25)",
+    "fir(1, 2, 3, 4, 5, 6, 7, 8, 9, 10)",
+    "fir3(0.5)",
+    "follow(0.01)",
+    "follow(0.01, 0.1)",
+    "highpass()",
+    "highpass(1)",
+    "highpass(1000, 1)",
+    "highpole()",
+    "highpole(1000)",
+    "highshelf()",
+    "highshelf(1, 2)",
+    "highshelf(1000, 1, 2)",
+    "lowpass()",
+    "lowpass(1)",
+    "lowpass(1000, 1)",
+    "lowpole()",
+    "lowpole(1000)",
+    "lowrez()",
+    "lowrez(1)",
+    "lowrez(1000, 1)",
+    "lowshelf()",
+    "lowshelf(1, 2)",
+    "lowshelf(1000, 1, 2)",
+    "moog()",
+    "moog(0.5)",
+    "moog(1000, 0.5)",
+    "morph()",
+    "morph(1000, 1, 0.5)",
+    "notch()",
+    "notch(1)",
+    "notch(1000, 1)",
+    "peak()",
+    "peak(1)",
+    "peak(1000, 1)",
+    "resonator()",
+    "resonator(1000, 100)",
+    // channels
+    "sink()",
+    "pass()",
+    "chan(1, 0, 1)",
+    "pan()",
+    "pan(0.5)",
+    "join(2)",
+    "join(8)",
+    "split(2)",
+    "split(8)",
+    "reverse(2)",
+    "reverse(8)",
+    // envelopes
+    "adsr(0.001, 0.001, 0.5, 0.001)",
+    "xd()",
+    "xd(10)",
+    "xD()",
+    "xD(0.5)",
+    "xD(0.001, 2)",
+    "ar()",
+    "ar(1, 2)",
+    "ar(0.0005, 1, 0.0005, 2)",
+    // other
+    "tick()",
+    "shift_reg()",
+    "snh()",
+    "meter(peak, 0.1)",
+    "meter(rms, 0.1)",
+    "chorus(0, 0.015, 0.005, 0.5)",
+    "clip()",
+    "clip(0.5, -0.5)",
+    "declick()",
+    "declick(0.001)",
+    "delay(0.0005)",
+    "hold(0.5)",
+    "hold(1000, 0.5)",
+    "limiter(0.001, 0.01)",
+    "limiter_stereo(0.001, 0.01)",
+    "reverb_stereo(10)",
+    "reverb_stereo(10, 2)",
+    "reverb_stereo(10, 2, 0.5)",
+    "reverb_mono(10)",
+    "reverb_mono(10, 2)",
+    "reverb_mono(10, 2, 0.5)",
+    "tap(0, 0.001)",
+    "tap_linear(0, 0.001)",
+    "samp_delay(3)",
+    "pdhalf_bi()",
+    "pdhalf_uni()",
+    // math
+    "add()",
+    "add(1, 2)",
+    "add(1, 2, 3, 4, 5, 6, 7, 8)",
+    "sub()",
+    "sub(1, 2)",
+    "sub(1, 2, 3, 4, 5, 6, 7, 8)",
+    "mul()",
+    "mul(1, 2)",
+    "mul(1, 2, 3, 4, 5, 6, 7, 8)",
+    "div()",
+    "div(1, 2)",
+    "div(1, 2, 3, 4, 5, 6, 7, 8)",
+    "rotate(1, 0.5)",
+    "t()",
+    "rise()",
+    "fall()",
+    ">()",
+    ">(0)",
+    "<()",
+    "<(0)",
+    "==()",
+    "==(0)",
+    "!=()",
+    "!=(0)",
+    ">=()",
+    ">=(0)",
+    "<=()",
+    "<=(0)",
+    "min()",
+    "min(0)",
+    "max()",
+    "max(0)",
+    "pow()",
+    "pow(2)",
+    "mod()",
+    "mod(0.3)",
+    "rem(0.3)",
+    "log()",
+    "log(2)",
+    "bitand()",
+    "bitand(3)",
+    "bitor()",
+    "bitor(3)",
+    "bitxor()",
+    "bitxor(3)",
+    "shl()",
+    "shl(2)",
+    "shr()",
+    "shr(2)",
+    "lerp()",
+    "lerp(-2, 2)",
+    "lerp11()",
+    "lerp11(-2, 2)",
+    "delerp()",
+    "delerp(-2, 2)",
+    "delerp11()",
+    "delerp11(-2, 2)",
+    "xerp()",
+    "xerp(1, 2)",
+    "xerp11()",
+    "xerp11(1, 2)",
+    "dexerp()",
+    "dexerp(1, 2)",
+    "dexerp11()",
+    "dexerp11(1, 2)",
+    "abs()",
+    "signum()",
+    "floor()",
+    "fract()",
+    "ceil()",
+    "round()",
+    "sqrt()",
+    "exp()",
+    "exp2()",
+    "exp10()",
+    "exp_m1()",
+    "ln_1p()",
+    "ln()",
+    "log2()",
+    "log10()",
+    "hypot()",
+    "atan2()",
+    "sin()",
+    "cos()",
+    "tan()",
+    "asin()",
+    "acos()",
+    "atan()",
+    "sinh()",
+    "cosh()",
+    "tanh()",
+    "asinh()",
+    "acosh()",
+    "atanh()",
+    "squared()",
+    "cubed()",
+    "dissonance()",
+    "dissonance_max()",
+    "db_amp()",
+    "amp_db()",
+    "a_weight()",
+    "m_weight()",
+    "spline()",
+    "spline_mono()",
+    "softsign()",
+    "softexp()",
+    "softmix()",
+    "smooth3()",
+    "smooth5()",
+    "smooth7()",
+    "smooth9()",
+    "uparc()",
+    "downarc()",
+    "sine_ease()",
+    "sin_hz()",
+    "cos_hz()",
+    "sqr_hz()",
+    "tri_hz()",
+    "semitone_ratio()",
+    "rnd1()",
+    "rnd2()",
+    "spline_noise()",
+    "fractal_noise()",
+    "wrap(1)",
+    "wrap(-0.5, 0.5)",
+    "mirror(-0.5, 0.5)",
+    "pol()",
+    "car()",
+    "deg()",
+    "rad()",
+    "recip()",
+    "rfft(16, 0)",
+    "ifft(16, 0)",
+    "normal()",
+];
+
+// deterministic input, different on every channel, sweeping roughly -1..1
+fn input(i: usize, channel: usize) -> f32 {
+    (((i * 7 + channel * 13) % 32) as f32 / 16. - 1.) * (1. + channel as f32 * 0.25)
+}
+
+// render an op, returns one buffer per output channel
+fn render(op: &str) -> Vec<Vec<f32>> {
+    let mut net = functions::str_to_net(op).unwrap_or_else(|e| panic!("{}", e));
+    net.set_sample_rate(SAMPLE_RATE);
+    let mut buffers = vec![Vec::with_capacity(SAMPLES); net.outputs()];
+    let mut i_frame = vec![0.; net.inputs()];
+    let mut o_frame = vec![0.; net.outputs()];
+    for i in 0..SAMPLES {
+        for (ch, x) in i_frame.iter_mut().enumerate() {
+            *x = input(i, ch);
+        }
+        net.tick(&i_frame, &mut o_frame);
+        for (buffer, x) in buffers.iter_mut().zip(&o_frame) {
+            buffer.push(*x);
+        }
+    }
+    buffers
+}
+
+fn serialize(renders: &[(&str, Vec<Vec<f32>>)]) -> String {
+    let mut s = String::new();
+    for (op, buffers) in renders {
+        for (ch, buffer) in buffers.iter().enumerate() {
+            write!(s, "{}\t{}\t", op, ch).unwrap();
+            let values: Vec<String> = buffer.iter().map(|x| format!("{:?}", x)).collect();
+            writeln!(s, "{}", values.join(" ")).unwrap();
+        }
+        if buffers.is_empty() {
+            writeln!(s, "{}\t-\t", op).unwrap();
+        }
+    }
+    s
+}
+
+fn deserialize(s: &str) -> HashMap<String, Vec<Vec<f32>>> {
+    let mut map: HashMap<String, Vec<Vec<f32>>> = HashMap::new();
+    for line in s.lines() {
+        let mut fields = line.split('\t');
+        let (Some(op), Some(ch), Some(values)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let buffers = map.entry(op.to_string()).or_default();
+        if ch == "-" {
+            continue;
+        }
+        buffers.push(values.split(' ').map(|x| x.parse::<f32>().unwrap()).collect());
+    }
+    map
+}
+
+fn same(a: f32, b: f32) -> bool {
+    (a.is_nan() && b.is_nan()) || a == b || (a - b).abs() <= TOLERANCE * a.abs().max(1.)
+}
+
+#[test]
+fn golden() {
+    let renders: Vec<(&str, Vec<Vec<f32>>)> = OPS.iter().map(|op| (*op, render(op))).collect();
+
+    if std::env::var("QUARTZ_BLESS").is_ok() {
+        std::fs::write(GOLDEN, serialize(&renders)).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(GOLDEN)
+        .expect("missing tests/golden/ops.txt, run with QUARTZ_BLESS=1 to generate it");
+    let golden = deserialize(&golden);
+
+    let mut failures = Vec::new();
+    for (op, buffers) in &renders {
+        let Some(expected) = golden.get(*op) else {
+            failures.push(format!("{}: no golden buffer (bless it?)", op));
+            continue;
+        };
+        if expected.len() != buffers.len() {
+            failures.push(format!(
+                "{}: expected {} channels, got {}",
+                op,
+                expected.len(),
+                buffers.len()
+            ));
+            continue;
+        }
+        for (ch, (e, b)) in expected.iter().zip(buffers).enumerate() {
+            if let Some(i) = (0..SAMPLES).find(|i| !same(e[*i], b[*i])) {
+                failures.push(format!(
+                    "{}: channel {} differs at sample {} (expected {}, got {})",
+                    op, ch, i, e[i], b[i]
+                ));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "golden mismatch:\n{}", failures.join("\n"));
+}
+
+// rendering the same op twice should give the same thing, otherwise the
+// golden buffers are meaningless
+#[test]
+fn deterministic() {
+    for op in OPS {
+        let a = render(op);
+        let b = render(op);
+        for (a, b) in a.iter().zip(&b) {
+            assert!(a.iter().zip(b).all(|(a, b)| same(*a, *b)), "{} isn't deterministic", op);
+        }
+    }
+}
+
+#[test]
+fn parse_errors() {
+    use functions::{str_to_net, OpError};
+    let err = |op: &str| str_to_net(op).err();
+    assert_eq!(err("foo()"), Some(OpError::Unknown("foo".to_string())));
+    assert_eq!(err("sine"), Some(OpError::Unknown("sine".to_string())));
+    assert!(matches!(err("lowpass(1000,"), Some(OpError::Parens(_))));
+    assert!(matches!(err("sine(abc)"), Some(OpError::BadNumber(..))));
+    assert!(matches!(err("pluck(1)"), Some(OpError::Arity(_, 1))));
+    for op in ["empty", "sum", "var()", "meter(peak, 0.1)", "lowpass(1000, 1)"] {
+        assert!(str_to_net(op).is_ok(), "{} should parse", op);
+    }
+}
@@ -0,0 +1,138 @@
+#![allow(dead_code)]
+
+#[path = "../src/graph.rs"]
+mod graph;
+
+use bevy::{ecs::component::Tick, prelude::Entity};
+use fundsp::{hacker32::*, realnet::NetBackend};
+use graph::*;
+
+fn circle(n: u32) -> Part {
+    Part::Circle(Entity::from_raw(n))
+}
+
+fn boxed(a: Part, b: Part, join: fn(Box<Part>, Box<Part>) -> Part) -> Part {
+    join(Box::new(a), Box::new(b))
+}
+
+// lay out a part whose circles have these nets (changed at these ticks)
+fn set(graph: &mut OutGraph, part: &Part, nets: &[(Net, u32)]) -> Option<NetBackend> {
+    graph.set(Some(part), |e| {
+        let (net, tick) = nets.get(e.index() as usize)?;
+        Some((net.clone(), Tick::new(*tick)))
+    })
+}
+
+fn run(backend: &mut NetBackend, n: usize) -> Vec<(f32, f32)> {
+    (0..n).map(|_| backend.get_stereo()).collect()
+}
+
+fn wrap(unit: impl AudioUnit + 'static) -> Net {
+    Net::wrap(Box::new(unit))
+}
+
+// (starting at phase 0, not one picked from where it is in the net)
+fn sine(f: f32) -> Net {
+    wrap(dc(f) >> An(Sine::with_phase(0.)))
+}
+
+// laid out node by node it sounds like the net the connective ops build
+#[test]
+fn same_as_net() {
+    let nets = [
+        (sine(110.), 0),
+        (wrap(lowpass_hz(500., 1.)), 0),
+        (sine(55.), 0),
+        (wrap(pass() * pass()), 0),
+        (wrap(dc(0.5)), 0),
+    ];
+    let net = |i: usize| nets[i].0.clone();
+    let cases = [
+        (boxed(circle(0), circle(1), Part::Pipe), net(0) >> net(1)),
+        (boxed(circle(0), circle(2), Part::Bus), net(0) & net(2)),
+        (boxed(circle(0), circle(2), Part::Sub), net(0) - net(2)),
+        (boxed(circle(0), circle(2), Part::Product), net(0) * net(2)),
+        (
+            boxed(
+                boxed(circle(0), circle(4), Part::Stack),
+                Part::Thru(Box::new(circle(3))),
+                Part::Pipe,
+            ),
+            (net(0) | net(4)) >> !net(3),
+        ),
+        (
+            boxed(circle(2), boxed(circle(1), circle(1), Part::Branch), Part::Pipe),
+            net(2) >> (net(1) ^ net(1)),
+        ),
+    ];
+    for (part, mut expected) in cases {
+        let mut graph = OutGraph::default();
+        let mut backend = set(&mut graph, &part, &nets).unwrap();
+        for (l, r) in run(&mut backend, 100) {
+            if expected.outputs() == 1 {
+                assert_eq!((l, r), (expected.get_mono(), 0.), "{:?}", part);
+            } else {
+                assert_eq!((l, r), expected.get_stereo(), "{:?}", part);
+            }
+        }
+    }
+}
+
+// every copy of a node used twice keeps going when the graph changes around it
+#[test]
+fn duplicate_keeps_going() {
+    let nets = [(sine(110.), 0), (wrap(dc(0.25)), 0)];
+    let twice = boxed(circle(0), circle(0), Part::Sum);
+    let mut expected = sine(110.) + sine(110.);
+    let mut graph = OutGraph::default();
+    let mut backend = set(&mut graph, &twice, &nets).unwrap();
+    for (l, _) in run(&mut backend, 100) {
+        assert_eq!(l, expected.get_mono());
+    }
+    let stereo = boxed(twice.clone(), circle(1), Part::Stack);
+    assert!(set(&mut graph, &stereo, &nets).is_none());
+    let after = run(&mut backend, 1000);
+    for (l, r) in &after {
+        assert_eq!((*l, *r), (expected.get_mono(), 0.25));
+    }
+    assert!(after.iter().any(|(l, _)| l.abs() > 1.5));
+    // and back
+    assert!(set(&mut graph, &twice, &nets).is_none());
+    for (l, r) in run(&mut backend, 100) {
+        assert_eq!((l, r), (expected.get_mono(), 0.));
+    }
+}
+
+// a circle whose net changed starts over, the others carry on
+#[test]
+fn changed_node_starts_over() {
+    let mut nets = [(sine(110.), 0), (sine(220.), 0)];
+    let part = boxed(circle(0), circle(1), Part::Stack);
+    let mut left = sine(110.);
+    let mut graph = OutGraph::default();
+    let mut backend = set(&mut graph, &part, &nets).unwrap();
+    run(&mut backend, 100);
+    for _ in 0..100 {
+        left.get_mono();
+    }
+    nets[1] = (sine(220.), 1);
+    set(&mut graph, &part, &nets);
+    let mut right = sine(220.);
+    for (l, r) in run(&mut backend, 100) {
+        assert_eq!((l, r), (left.get_mono(), right.get_mono()));
+    }
+}
+
+// nothing, or nets the output can't take, are silent
+#[test]
+fn silent() {
+    let nets = [(sine(110.), 0), (wrap(lowpass_hz(500., 1.)), 0)];
+    let mut graph = OutGraph::default();
+    let mut backend = graph.set(None, |_| None).unwrap();
+    assert_eq!(run(&mut backend, 10), [(0., 0.); 10]);
+    set(&mut graph, &circle(1), &nets);
+    assert_eq!(run(&mut backend, 10), [(0., 0.); 10]);
+    // mismatched pipe
+    set(&mut graph, &boxed(circle(0), circle(0), Part::Pipe), &nets);
+    assert_eq!(run(&mut backend, 10), [(0., 0.); 10]);
+}
@@ -0,0 +1,114 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+use fundsp::hacker32::*;
+use nodes::*;
+
+// 1000 samples per second, a millisecond each
+fn block(ring: &InputRing, node: &mut InputNode, size: usize) -> Vec<f32> {
+    ring.start_block(size, 1000.);
+    (0..size).map(|_| node.tick(&Frame::default())[0]).collect()
+}
+
+fn push(ring: &InputRing, from: usize, frames: usize) {
+    for i in from..from + frames {
+        assert!(ring.push(i as f32, -(i as f32)));
+    }
+}
+
+#[test]
+fn full() {
+    let ring = InputRing::new(4, 0.);
+    push(&ring, 1, 4);
+    assert!(!ring.push(5., -5.));
+    assert_eq!(ring.available(), 4);
+}
+
+#[test]
+fn latency() {
+    let ring = InputRing::new(1024, 10.);
+    let mut node = InputNode::new();
+    // silent until there's enough for the latency and a block
+    push(&ring, 1, 8);
+    assert_eq!(block(&ring, &mut node, 4), [0.; 4]);
+    // then it starts
+    push(&ring, 9, 12);
+    assert_eq!(block(&ring, &mut node, 4), [1., 2., 3., 4.]);
+    assert_eq!(ring.available(), 16);
+    // every node in the block gets the same frames
+    let mut other = InputNode::new();
+    ring.start_block(2, 1000.);
+    assert_eq!(node.tick(&Frame::default()), Frame::from([5., -5.]));
+    assert_eq!(other.tick(&Frame::default()), Frame::from([5., -5.]));
+    assert_eq!(node.tick(&Frame::default()), Frame::from([6., -6.]));
+    assert_eq!(other.tick(&Frame::default()), Frame::from([6., -6.]));
+}
+
+#[test]
+fn dry() {
+    let ring = InputRing::new(1024, 0.);
+    let mut node = InputNode::new();
+    push(&ring, 1, 4);
+    assert_eq!(block(&ring, &mut node, 4), [1., 2., 3., 4.]);
+    // nothing came in, wait for it
+    push(&ring, 5, 2);
+    assert_eq!(block(&ring, &mut node, 4), [0.; 4]);
+    push(&ring, 7, 2);
+    assert_eq!(block(&ring, &mut node, 4), [5., 6., 7., 8.]);
+}
+
+#[test]
+fn stall() {
+    let ring = InputRing::new(1024, 10.);
+    let mut node = InputNode::new();
+    push(&ring, 1, 14);
+    block(&ring, &mut node, 4);
+    // the output missed a lot of blocks, it skips back to the latency
+    push(&ring, 15, 500);
+    assert_eq!(block(&ring, &mut node, 4), [501., 502., 503., 504.]);
+    assert_eq!(ring.available(), 10);
+    assert_eq!(ring.take_skipped(), 496);
+}
+
+#[test]
+fn drift() {
+    // the input comes in chunks of 3 blocks, a frame short or over every 10 chunks
+    for extra in [-1, 1] {
+        let ring = InputRing::new(1024, 20.);
+        let mut node = InputNode::new();
+        let mut pushed = 1;
+        for i in 0..30000 {
+            if i % 3 == 0 {
+                let frames = if i % 30 == 0 { 30 + extra } else { 30 } as usize;
+                push(&ring, pushed, frames);
+                pushed += frames;
+            }
+            let out = block(&ring, &mut node, 10);
+            // once started, it's followed without ever running dry or skipping
+            if i == 10 {
+                ring.take_skipped();
+            } else if i > 10 {
+                assert!(out.iter().all(|x| *x != 0.));
+                assert!(ring.available() < 80);
+            }
+        }
+        assert_eq!(ring.take_skipped(), 0);
+    }
+}
+
+#[test]
+fn same_cycle() {
+    // input and output in the same callback (jack), with no latency
+    let ring = InputRing::new(1024, 0.);
+    let mut node = InputNode::new();
+    for i in 0..1000 {
+        push(&ring, 1 + i * 40, 40);
+        for j in 0..4 {
+            let first = (1 + i * 40 + j * 10) as f32;
+            assert_eq!(block(&ring, &mut node, 10)[0], first);
+        }
+    }
+    assert_eq!(ring.available(), 0);
+}
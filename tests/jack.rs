@@ -0,0 +1,81 @@
+#![allow(dead_code)]
+#![cfg(target_os = "linux")]
+
+#[path = "../src/audio.rs"]
+mod audio;
+#[path = "../src/components.rs"]
+mod components;
+#[path = "../src/config.rs"]
+mod config;
+#[path = "../src/functions.rs"]
+mod functions;
+#[path = "../src/jack.rs"]
+mod jack;
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+use ::jack::{AudioIn, Client, ClientOptions, ClosureProcessHandler, Control};
+use audio::OutShared;
+use fundsp::hacker32::*;
+use jack::*;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+#[test]
+fn bbt() {
+    assert_eq!(bbt_beats(1, 1, 0., 4.), 0.);
+    assert_eq!(bbt_beats(1, 3, 0.5, 4.), 2.5);
+    assert_eq!(bbt_beats(3, 2, 0., 3.), 7.);
+}
+
+#[test]
+#[ignore = "needs a jack server (jackd -d dummy)"]
+fn client() {
+    let (mut slot, backend) = Slot::new(Box::new(dc(0.) | dc(0.)));
+    let shared = OutShared::default();
+    let follow = Arc::new(AtomicBool::new(true));
+    let quartz = JackClient::new("quartz_test", backend, shared.clone(), follow).unwrap();
+    slot.set(Fade::Smooth, 0.01, Box::new(dc(0.25) | dc(-0.25)));
+    let ports = quartz.ports();
+    for port in IN_PORTS.iter().chain(&OUT_PORTS) {
+        assert!(ports.contains(&format!("{}:{}", quartz.name(), port)));
+    }
+
+    // another client listening to the left output
+    let (probe, _) = Client::new("probe", ClientOptions::NO_START_SERVER).unwrap();
+    let port = probe.register_port("in", AudioIn).unwrap();
+    let heard = Arc::new(AtomicU32::new(0));
+    let h = heard.clone();
+    let probe = probe
+        .activate_async(
+            (),
+            ClosureProcessHandler::new(move |_, ps| {
+                if let Some(x) = port.as_slice(ps).last() {
+                    h.store(x.to_bits(), Ordering::Relaxed);
+                }
+                Control::Continue
+            }),
+        )
+        .unwrap();
+    assert!(quartz.take_ports_changed());
+    let rules = [(OUT_PORTS[0].to_string(), "probe:.*".to_string())];
+    assert!(quartz.connect(&rules).is_empty());
+    // connecting again is fine
+    assert!(quartz.connect(&rules).is_empty());
+    std::thread::sleep(Duration::from_millis(300));
+    assert_eq!(f32::from_bits(heard.load(Ordering::Relaxed)), 0.25);
+
+    // the transport follows jack's
+    let transport = probe.as_client().transport();
+    transport.start().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    assert!(shared.transport.playing());
+    assert!(shared.transport.position() > 0.);
+    transport.stop().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    assert!(!shared.transport.playing());
+    assert!(!quartz.shut_down());
+}
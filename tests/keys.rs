@@ -0,0 +1,180 @@
+// type commands into the command line and see what they do
+
+#![allow(dead_code, clippy::type_complexity, clippy::too_many_arguments)]
+
+#[path = "../src/audio.rs"]
+mod audio;
+#[path = "../src/commands.rs"]
+mod commands;
+#[path = "../src/completion.rs"]
+mod completion;
+#[path = "../src/components.rs"]
+mod components;
+#[path = "../src/config.rs"]
+mod config;
+#[path = "../src/functions.rs"]
+mod functions;
+#[path = "../src/jack.rs"]
+mod jack;
+#[path = "../src/layout.rs"]
+mod layout;
+#[path = "../src/link.rs"]
+mod link;
+#[path = "../src/minimap.rs"]
+mod minimap;
+#[path = "../src/nodes.rs"]
+mod nodes;
+#[path = "../src/views.rs"]
+mod views;
+
+use bevy::{
+    color::Hsla,
+    input::{
+        keyboard::{Key, KeyboardInput, NativeKeyCode},
+        ButtonState,
+    },
+    prelude::*,
+    state::app::StatesPlugin,
+};
+use fundsp::hacker32::*;
+
+use {commands::*, components::*};
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin))
+        .init_state::<Mode>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<ShowInfoText>()
+        .init_resource::<DragModes>()
+        .insert_resource(DefaultDrawColor(Hsla::new(330., 1., 0.5, 1.)))
+        .insert_resource(DefaultDrawVerts(4))
+        .insert_resource(DefaultLT((0, 0)))
+        .init_resource::<Version>()
+        .insert_resource(NodeLimit(500))
+        .insert_resource(PasteChannel(crossbeam_channel::bounded::<String>(1)))
+        .init_resource::<CommandHistory>()
+        .init_resource::<CommandCursor>()
+        .init_resource::<PendingCommands>()
+        .init_resource::<TransportRes>()
+        .init_resource::<link::Link>()
+        .init_resource::<Timing>()
+        .init_resource::<Profiler>()
+        .init_resource::<InputRingRes>()
+        .init_resource::<jack::Jack>()
+        .init_resource::<config::Config>()
+        .init_resource::<CursorInfo>()
+        .insert_resource(TextSize(0.1))
+        .init_resource::<SearchResults>()
+        .init_resource::<Minimap>()
+        .init_resource::<Bookmarks>()
+        .init_resource::<Grid>()
+        .init_resource::<ConnectionStyle>()
+        .init_resource::<LinkColors>()
+        .add_event::<KeyboardInput>()
+        .add_event::<OrderChange>()
+        .add_event::<SaveCommand>()
+        .add_event::<CopyCommand>()
+        .add_event::<DeleteCommand>()
+        .add_event::<OutDeviceCommand>()
+        .add_event::<InDeviceCommand>()
+        .add_systems(Update, command_parser);
+    app.world_mut().spawn((Text::from_section("", TextStyle::default()), CommandText));
+    app.world_mut().spawn((Transform::default(), MainCamera));
+    app
+}
+
+fn type_keys(app: &mut App, keys: &str) {
+    for c in keys.chars() {
+        let window = Entity::PLACEHOLDER;
+        let logical_key = Key::Character(c.to_string().into());
+        let key_code = KeyCode::Unidentified(NativeKeyCode::Unidentified);
+        let state = ButtonState::Pressed;
+        app.world_mut().send_event(KeyboardInput { key_code, logical_key, state, window });
+        app.update();
+    }
+}
+
+// a `:` command, entered
+fn run(app: &mut App, line: &str) {
+    let mut query = app.world_mut().query_filtered::<&mut Text, With<CommandText>>();
+    query.single_mut(app.world_mut()).sections[0].value = format!("{}\t", line);
+    app.update();
+}
+
+// time spent in a circle's net over a few blocks
+fn time_net(app: &mut App, e: Entity, time: &nodes::CpuTime) -> u64 {
+    let mut net = app.world().get::<Network>(e).unwrap().0.clone();
+    let mut out = BufferVec::new(net.outputs());
+    for _ in 0..100 {
+        net.process(64, &BufferRef::empty(), &mut out.buffer_mut());
+    }
+    time.take()
+}
+
+fn command_line(app: &mut App) -> String {
+    let mut query = app.world_mut().query_filtered::<&Text, With<CommandText>>();
+    query.single(app.world()).sections[0].value.clone()
+}
+
+#[test]
+fn n_after_search() {
+    let mut app = app();
+    let world = app.world_mut();
+    let a = world.spawn((Transform::from_xyz(1., 2., 0.), Selected)).id();
+    let b = world.spawn(Transform::from_xyz(3., 4., 0.)).id();
+    world.entity_mut(a).insert(Network(Net::wrap(Box::new(pass() | pass()))));
+    let mut search = world.resource_mut::<SearchResults>();
+    search.pattern = "x".into();
+    search.matches = vec![a, b];
+
+    // n is the start of ni (and no, np, nc)
+    type_keys(&mut app, "ni");
+    assert_eq!(command_line(&mut app), format!(">INPUTS: [{}]2  ", a));
+    assert_eq!(app.world().resource::<SearchResults>().index, 0);
+
+    // right after a search it goes to the next match
+    let mut query = app.world_mut().query_filtered::<&mut Text, With<CommandText>>();
+    query.single_mut(app.world_mut()).sections[0].value = format!(">/x: 1/2 [{}]", a);
+    type_keys(&mut app, "n");
+    assert_eq!(app.world().resource::<SearchResults>().index, 1);
+    assert!(command_line(&mut app).starts_with(">/x: 2/2"));
+    let mut cam = app.world_mut().query_filtered::<&Transform, With<MainCamera>>();
+    assert_eq!(cam.single(app.world()).translation.xy(), Vec2::new(3., 4.));
+    // and back
+    type_keys(&mut app, "N");
+    assert_eq!(app.world().resource::<SearchResults>().index, 0);
+}
+
+#[test]
+fn profile_probes_nets_in_place() {
+    let mut app = app();
+    let world = app.world_mut();
+    let a = world
+        .spawn((
+            Vertices(4),
+            Holes(Vec::new()),
+            Op("sine_hz(110)".into()),
+            OpNum(0),
+            Network(Net::wrap(Box::new(sine_hz(110.)))),
+            OpChanged(false),
+        ))
+        .id();
+
+    // the net gets a probe without the circle being rebuilt
+    run(&mut app, ":profile on");
+    assert!(!app.world().get::<OpChanged>(a).unwrap().0);
+    let time = app.world().resource::<Profiler>().circles[&a].0.clone();
+    assert!(time_net(&mut app, a, &time) > 0);
+
+    // and so does an op-0 net built by the command line
+    run(&mut app, &format!(":set op {} sine_hz(220)", a));
+    assert_eq!(app.world().get::<Op>(a).unwrap().0, "sine_hz(220)");
+    let time = app.world().resource::<Profiler>().circles[&a].0.clone();
+    assert!(time_net(&mut app, a, &time) > 0);
+
+    // off, the probes stop counting
+    run(&mut app, ":profile off");
+    assert!(app.world().resource::<Profiler>().circles.is_empty());
+    assert_eq!(time_net(&mut app, a, &time), 0);
+}
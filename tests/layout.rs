@@ -0,0 +1,147 @@
+#![allow(dead_code)]
+
+#[path = "../src/layout.rs"]
+mod layout;
+
+use bevy::math::{Rect, Vec2};
+use layout::*;
+
+fn v(x: f32, y: f32) -> Vec2 {
+    Vec2::new(x, y)
+}
+
+#[test]
+fn aligning() {
+    let circles = [(v(0., 0.), 10.), (v(50., 30.), 20.), (v(100., -40.), 5.)];
+    assert_eq!(align(&circles, Side::Left), [v(0., 0.), v(10., 30.), v(-5., -40.)]);
+    assert_eq!(align(&circles, Side::Right), [v(95., 0.), v(85., 30.), v(100., -40.)]);
+    assert_eq!(align(&circles, Side::Center), [v(47.5, 0.), v(47.5, 30.), v(47.5, -40.)]);
+    assert_eq!(align(&circles, Side::Top), [v(0., 40.), v(50., 30.), v(100., 45.)]);
+    assert_eq!(str_to_side("middle"), Some(Side::Middle));
+    assert_eq!(str_to_side("b"), Some(Side::Bottom));
+    assert_eq!(str_to_side("up"), None);
+}
+
+#[test]
+fn distributing() {
+    // out of order on purpose, the ends stay put
+    let circles = [(v(100., 0.), 5.), (v(0., 10.), 5.), (v(10., 20.), 5.), (v(30., 30.), 5.)];
+    let to = distribute(&circles, false);
+    assert_eq!(to, [v(100., 0.), v(0., 10.), v(100. / 3., 20.), v(200. / 3., 30.)]);
+    let to = distribute(&circles, true);
+    assert_eq!(to, [v(100., 0.), v(0., 10.), v(10., 20.), v(30., 30.)]);
+    // too few to do anything
+    assert_eq!(distribute(&circles[..2], false), [v(100., 0.), v(0., 10.)]);
+}
+
+#[test]
+fn arranging() {
+    let circles =
+        [(v(0., 0.), 10., 2), (v(0., 100.), 10., 1), (v(50., 50.), 20., 2), (v(-20., 80.), 5., 1)];
+    // starts at the top left (-25, 110), order 1 then order 2
+    let to = columns(&circles, 10.);
+    assert_eq!(to[1], v(-15., 100.));
+    assert_eq!(to[3], v(-15., 75.));
+    // the order 1 column is 20 wide, then a gap. the widest in this one is 20
+    assert_eq!(to[2], v(25., 90.));
+    assert_eq!(to[0], v(25., 50.));
+}
+
+#[test]
+fn grid() {
+    assert_eq!(snap(14., 10.), 10.);
+    assert_eq!(snap(-16., 10.), -20.);
+    assert_eq!(snap(14., 0.), 14.);
+    let lines = grid_lines(Rect::new(-15., -5., 15., 5.), 10.);
+    // x from -20 to 20, y from -10 to 10
+    assert_eq!(lines.len(), (5 + 3) * 2);
+    assert_eq!(lines[0], [-20., -10., 0.]);
+    assert_eq!(lines[1], [-20., 10., 0.]);
+    assert!(grid_lines(Rect::new(0., 0., 3000., 10.), 10.).is_empty());
+    assert!(grid_lines(Rect::new(0., 0., 10., 10.), 0.).is_empty());
+}
+
+#[test]
+fn layers() {
+    // a chain piled up on one spot: 0 -> 1 -> 2, and 3 feeding 2
+    let circles =
+        [(v(0., 0.), 10., 1), (v(0., 0.), 10., 1), (v(0., 0.), 10., 1), (v(0., 0.), 10., 1)];
+    let to = layered(&circles, &[(0, 1), (1, 2), (3, 2)], 10.);
+    // the sources first, then 1, then 2 (each 30 to the right)
+    assert_eq!(to[0].x, to[3].x);
+    assert_eq!(to[1].x, to[0].x + 30.);
+    assert_eq!(to[2].x, to[1].x + 30.);
+    assert_ne!(to[0].y, to[3].y);
+    // a lone circle is centered on the tallest column
+    assert_eq!(to[1].y, (to[0].y + to[3].y) / 2.);
+    // higher orders go after, even against the connections
+    let circles = [(v(0., 0.), 10., 2), (v(0., 0.), 10., 1), (v(0., 0.), 10., 1)];
+    let to = layered(&circles, &[(0, 1)], 10.);
+    assert!(to[1].x == to[2].x && to[0].x > to[1].x);
+    // cycles still get laid out
+    let circles = [(v(0., 0.), 10., 1), (v(0., 0.), 10., 1)];
+    let to = layered(&circles, &[(0, 1), (1, 0)], 10.);
+    assert_ne!(to[0].x, to[1].x);
+}
+
+#[test]
+fn sources_sorted() {
+    // 0 above 1, their sinks are placed the other way around
+    let circles =
+        [(v(0., 10.), 5., 1), (v(0., 0.), 5., 1), (v(50., 0.), 5., 1), (v(50., 10.), 5., 1)];
+    let to = layered(&circles, &[(0, 2), (1, 3)], 10.);
+    // the sinks follow their sources
+    assert!(to[2].y > to[3].y);
+}
+
+#[test]
+fn holes_on_the_edge() {
+    assert_eq!(perimeter(v(0., 0.), 10., v(100., 0.)), v(10., 0.));
+    assert_eq!(perimeter(v(5., 5.), 2., v(5., -50.)), v(5., 3.));
+    // on top of each other, pick a side
+    assert_eq!(perimeter(v(0., 0.), 10., v(0., 0.)), v(10., 0.));
+}
+
+#[test]
+fn curves() {
+    let path = curve(v(0., 0.), 10., v(100., 50.), 5.);
+    // from the right of the first to the left of the second
+    assert_eq!(path[0], v(10., 0.));
+    assert_eq!(*path.last().unwrap(), v(95., 50.));
+    assert!(path.windows(2).all(|s| s[1].y >= s[0].y));
+}
+
+#[test]
+fn routing() {
+    // nothing in the way, across halfway
+    let path = route(v(0., 0.), v(100., 50.), &[], 5.);
+    assert_eq!(path, [v(0., 0.), v(50., 0.), v(50., 50.), v(100., 50.)]);
+    // straight, without the corners
+    let path = route(v(0., 0.), v(100., 0.), &[], 5.);
+    assert_eq!(path, [v(0., 0.), v(100., 0.)]);
+    // a circle in the middle, it goes over or under
+    let block = Rect::new(40., -10., 60., 10.);
+    let path = route(v(0., 0.), v(100., 0.), &[block], 5.);
+    assert_eq!(path.len(), 6);
+    assert!(path[2].y == 15. || path[2].y == -15.);
+    // it can't get around what's on the end, it crosses it the shortest way
+    let end = Rect::new(90., -10., 110., 10.);
+    assert_eq!(route(v(0., 0.), v(100., 0.), &[end], 5.), [v(0., 0.), v(100., 0.)]);
+}
+
+#[test]
+fn strokes() {
+    let mut path = vec![v(0., 0.), v(10., 0.), v(10., 10.)];
+    trim(&mut path, 2., 20.);
+    assert_eq!(path, [v(2., 0.), v(10., 0.), v(10., 0.)]);
+    let path = [v(0., 0.), v(10., 0.), v(10., 10.)];
+    let points = stroke(&path, 4., 0.);
+    assert_eq!(points.len(), 6);
+    assert_eq!(points[0], [0., 2., 0.]);
+    assert_eq!(points[1], [0., -2., 0.]);
+    // the corner is mitered, half as wide by then
+    assert_eq!(points[2], [9., 1., 0.]);
+    assert_eq!(points[3], [11., -1., 0.]);
+    // and it ends in a point
+    assert_eq!(points[4], points[5]);
+}
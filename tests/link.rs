@@ -0,0 +1,102 @@
+#![allow(dead_code)]
+
+#[path = "../src/components.rs"]
+mod components;
+#[path = "../src/link.rs"]
+mod link;
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+use bevy::prelude::*;
+use components::TransportRes;
+use link::*;
+use std::{
+    net::{Ipv4Addr, SocketAddrV4},
+    time::{Duration, Instant},
+};
+
+fn state() -> PeerState {
+    PeerState {
+        ident: *b"abcdefgh",
+        session: *b"12345678",
+        timeline: Timeline::new(120., 4., 1000),
+        endpoint: Some(SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 4321)),
+    }
+}
+
+#[test]
+fn discovery() {
+    let msg = encode_discovery(ALIVE, &state());
+    // protocol header, alive, ttl 5, group 0, ident
+    assert_eq!(&msg[..20], b"_asdp_v\x01\x01\x05\x00\x00abcdefgh");
+    // tempo in microseconds per beat
+    assert_eq!(&msg[20..28], b"tmln\x00\x00\x00\x18");
+    assert_eq!(&msg[28..36], &500000i64.to_be_bytes());
+    assert_eq!(decode_discovery(&msg), Some((ALIVE, 5, state())));
+
+    let bye = encode_discovery(BYEBYE, &state());
+    assert_eq!(bye.len(), 20);
+    assert_eq!(decode_discovery(&bye).map(|m| (m.0, m.2.ident)), Some((BYEBYE, *b"abcdefgh")));
+
+    assert_eq!(decode_discovery(b"_asdp_v\x01\x01"), None);
+    assert_eq!(decode_discovery(&msg[..30]), None);
+    assert_eq!(decode_discovery(b"/osc/address"), None);
+}
+
+#[test]
+fn measurement() {
+    let ping = encode_ping(20, Some(1000));
+    let pong = encode_pong(b"12345678", 1030, &ping[9..]);
+    assert_eq!(decode_pong(&pong), Some((*b"12345678", 1030, 20, Some(1000))));
+    assert_eq!(decode_pong(&ping), None);
+    // their clock is 1000us ahead, pong took 10us each way
+    assert_eq!(measure(1030, 20, None, 40), [1000.]);
+    assert_eq!(median(&mut [3., 1., 2.]), 2.);
+    assert_eq!(median(&mut [4., 1., 2., 3.]), 2.5);
+}
+
+#[test]
+fn sessions() {
+    // the older session (further ahead in ghost time) wins
+    assert!(should_join((*b"bbbbbbbb", 0), (*b"cccccccc", 2000000)));
+    assert!(!should_join((*b"bbbbbbbb", 2000000), (*b"aaaaaaaa", 0)));
+    // close enough, the smaller id wins
+    assert!(should_join((*b"bbbbbbbb", 0), (*b"aaaaaaaa", 100)));
+    assert!(!should_join((*b"aaaaaaaa", 0), (*b"bbbbbbbb", 100)));
+}
+
+#[test]
+fn timeline() {
+    let t = Timeline::new(120., 4., 1000);
+    assert_eq!(t.bpm(), 120.);
+    assert_eq!(t.beat_at(1000), 4.);
+    assert_eq!(t.beat_at(1000 + 250000), 4.5);
+    assert_eq!(Timeline::new(90., 0., 0).bpm(), 90.);
+}
+
+fn peer(bpm: f32) -> App {
+    let mut app = App::new();
+    app.init_resource::<Link>().init_resource::<TransportRes>().add_systems(Update, link_sync);
+    app.world().resource::<TransportRes>().0.set_bpm(bpm);
+    app.world_mut().resource_mut::<Link>().enabled = true;
+    app.update();
+    app
+}
+
+// two instances on this machine find each other, the newer one joins
+// the older one's session and takes its tempo
+#[test]
+fn join_session() {
+    let mut a = peer(120.);
+    std::thread::sleep(Duration::from_secs(1));
+    let mut b = peer(90.);
+    let start = Instant::now();
+    while b.world().resource::<TransportRes>().0.bpm() != 120. {
+        assert!(start.elapsed() < Duration::from_secs(10), "never joined");
+        a.update();
+        b.update();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(a.world().resource::<TransportRes>().0.bpm(), 120.);
+    assert_eq!(b.world().resource::<Link>().peers(), 1);
+}
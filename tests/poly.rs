@@ -0,0 +1,95 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+use fundsp::hacker32::*;
+use nodes::{NoteQueue, Poly};
+
+// voices output frequency * gate
+fn poly(n: usize, quietest: bool) -> (Poly, NoteQueue) {
+    let notes = NoteQueue::default();
+    let voices = (0..n).map(|_| Net::wrap(Box::new(pass() * pass()))).collect();
+    (Poly::new(voices, notes.clone(), quietest), notes)
+}
+
+fn tick(poly: &mut Poly) -> f32 {
+    let mut out = [0.];
+    poly.tick(&[], &mut out);
+    out[0]
+}
+
+#[test]
+fn allocate() {
+    let (mut poly, s) = poly(2, false);
+    s.push(100., 1., 1.);
+    s.push(200., 1., 1.);
+    assert_eq!(tick(&mut poly), 300.);
+    // the oldest is stolen, its gate drops for a sample first
+    s.push(300., 1., 1.);
+    assert_eq!(tick(&mut poly), 200.);
+    assert_eq!(tick(&mut poly), 500.);
+    s.push(200., 0., 0.);
+    assert_eq!(tick(&mut poly), 300.);
+    // released voices are reused before stealing
+    s.push(400., 1., 1.);
+    assert_eq!(tick(&mut poly), 700.);
+    // retriggering a held pitch
+    s.push(300., 1., 1.);
+    assert_eq!(tick(&mut poly), 400.);
+    assert_eq!(tick(&mut poly), 700.);
+}
+
+#[test]
+fn steal_quietest() {
+    let (mut poly, s) = poly(2, true);
+    s.push(200., 1., 1.);
+    s.push(100., 1., 1.);
+    tick(&mut poly);
+    s.push(50., 1., 1.);
+    tick(&mut poly);
+    assert_eq!(tick(&mut poly), 250.);
+}
+
+#[test]
+fn clones() {
+    let (mut first, s) = poly(2, false);
+    s.push(100., 1., 1.);
+    assert_eq!(tick(&mut first), 100.);
+    let mut copy = first.clone();
+    // both hear every note
+    s.push(200., 1., 1.);
+    assert_eq!(tick(&mut first), 300.);
+    assert_eq!(tick(&mut copy), 300.);
+    s.push(200., 0., 0.);
+    assert_eq!(tick(&mut first), 100.);
+    assert_eq!(tick(&mut copy), 100.);
+    // a copy doesn't replay what was sent before it was made
+    let (unused, s) = poly(2, false);
+    s.push(100., 1., 1.);
+    s.push(200., 1., 1.);
+    let mut copy = unused.clone();
+    assert_eq!(tick(&mut copy), 0.);
+    s.push(300., 1., 1.);
+    assert_eq!(tick(&mut copy), 300.);
+}
+
+// every voice sounds, in a poly made over a queue that was already in use
+// (the op was rebuilt) and in the copies of it
+#[test]
+fn every_voice_after_rebuild() {
+    let (mut old, s) = poly(4, false);
+    for pitch in [1., 2., 3., 4.] {
+        s.push(pitch, 1., 1.);
+    }
+    assert_eq!(tick(&mut old), 10.);
+    let voices = (0..4).map(|_| Net::wrap(Box::new(pass() * pass()))).collect();
+    let mut poly = Poly::new(voices, s.clone(), false);
+    let mut copy = poly.clone();
+    assert_eq!(tick(&mut poly), 0.);
+    for pitch in [100., 200., 300., 400.] {
+        s.push(pitch, 1., 1.);
+    }
+    assert_eq!(tick(&mut poly), 1000.);
+    assert_eq!(tick(&mut copy), 1000.);
+}
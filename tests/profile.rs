@@ -0,0 +1,39 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+use fundsp::hacker32::*;
+use nodes::*;
+
+#[test]
+fn nested_probes() {
+    let inner_time = CpuTime::default();
+    let outer_time = CpuTime::default();
+    let mut oscs = Net::wrap(Box::new(sine_hz(110.)));
+    for i in 0..63 {
+        oscs = oscs + Net::wrap(Box::new(sine_hz(220. + i as f32)));
+    }
+    let on = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let inner = Net::wrap(Box::new(Probe::new(oscs, inner_time.clone(), on.clone())));
+    let mut outer = Net::wrap(Box::new(Probe::new(inner >> pass(), outer_time.clone(), on)));
+    outer.set_sample_rate(44100.);
+    let mut out = BufferVec::new(1);
+    for _ in 0..100 {
+        outer.process(64, &BufferRef::empty(), &mut out.buffer_mut());
+    }
+    let (inner, outer) = (inner_time.take(), outer_time.take());
+    assert!(inner > 0);
+    // the outer probe doesn't count the oscillators
+    assert!(outer < inner);
+    assert_eq!(inner_time.take(), 0);
+}
+
+#[test]
+fn dsp_load() {
+    let load = DspLoad::default();
+    // 512 frames at 1000 samples per second is 512ms
+    load.record(std::time::Duration::from_millis(128), 512, 1000.);
+    assert_eq!(load.take(), (128_000_000, 512_000_000));
+    assert_eq!(load.take(), (0, 0));
+}
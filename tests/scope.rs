@@ -0,0 +1,60 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+#[path = "../src/functions.rs"]
+mod functions;
+
+use functions::*;
+use fundsp::hacker32::*;
+
+fn feed(f: impl Fn(usize) -> f32, samples: usize, capacity: usize) -> Snoop {
+    let (mut snoop, mut backend) = snoop(capacity);
+    for i in 0..samples {
+        backend.tick(&[f(i)].into());
+    }
+    snoop.update();
+    snoop
+}
+
+#[test]
+fn scope() {
+    // a ramp from -1 to 1 every 100 samples, starting half way
+    let ramp = |i: usize| ((i + 50) % 100) as f32 / 50. - 1.;
+    let snoop = feed(ramp, 1024, 512);
+    let trace = scope_trace(&snoop, 200, 200);
+    assert_eq!(trace.len(), 200);
+    // it starts right after a zero crossing, and covers two cycles
+    assert!(trace[0] > 0. && trace[0] < 0.05);
+    assert_eq!(trace[0], trace[100]);
+    // fewer points skip samples
+    let trace = scope_trace(&snoop, 200, 50);
+    assert_eq!(trace.len(), 50);
+    assert_eq!(trace[0], trace[25]);
+    // no crossing, the latest samples
+    let snoop = feed(|i| i as f32, 1024, 512);
+    assert_eq!(scope_trace(&snoop, 4, 4), [1020., 1021., 1022., 1023.]);
+}
+
+#[test]
+fn spectrum() {
+    // a sine on bin 64 of 1024 (full scale)
+    let sine = |i: usize| (i as f32 * std::f32::consts::TAU * 64. / 1024.).sin();
+    let snoop = feed(sine, 2048, 2048);
+    let levels = spectrum_trace(&snoop, 1024, 512);
+    assert_eq!(levels.len(), 512);
+    let (peak, level) =
+        levels
+            .iter()
+            .enumerate()
+            .fold((0, f32::MIN), |a, (i, x)| if *x > a.1 { (i, *x) } else { a });
+    // the first point reaching bin 64 (they're log spaced from 1 to 512)
+    assert_eq!(peak, (64f32.ln() / 512f32.ln() * 512.).ceil() as usize);
+    assert!(level.abs() < 0.5);
+    // far from it is way down
+    assert!(levels[0] < -60. && levels[511] < -60.);
+    // silence
+    let snoop = feed(|_| 0., 2048, 2048);
+    assert!(spectrum_trace(&snoop, 1024, 16).iter().all(|x| *x < -100.));
+}
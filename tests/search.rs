@@ -0,0 +1,36 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+#[path = "../src/functions.rs"]
+mod functions;
+
+use functions::SearchPattern;
+
+#[test]
+fn ops() {
+    let p = SearchPattern::new("lowpass").unwrap();
+    assert!(p.matches("4v0", Some("lowpass()"), Some(0.)));
+    assert!(p.matches("4v0", Some("lowpass(1000, 0.5)"), None));
+    assert!(!p.matches("4v0", Some("highpass()"), None));
+    assert!(!p.matches("4v0", None, None));
+    // it's a regex
+    let p = SearchPattern::new("^(low|high)pass").unwrap();
+    assert!(p.matches("4v0", Some("highpass()"), None));
+    assert!(!p.matches("4v0", Some("bandpass()"), None));
+    assert!(SearchPattern::new("sine(").is_err());
+}
+
+#[test]
+fn ids_and_nums() {
+    let p = SearchPattern::new("12v3").unwrap();
+    assert!(p.matches("12v3", Some("empty"), Some(0.)));
+    // only the whole id
+    assert!(!p.matches("112v3", Some("empty"), Some(0.)));
+    let p = SearchPattern::new("440").unwrap();
+    assert!(p.matches("4v0", Some("empty"), Some(440.)));
+    assert!(p.matches("4v0", Some("sine(440)"), Some(0.)));
+    assert!(!p.matches("4v0", Some("empty"), Some(441.)));
+    assert!(!p.matches("440v0", Some("empty"), Some(0.)));
+}
@@ -0,0 +1,21 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+#[path = "../src/functions.rs"]
+mod functions;
+
+#[test]
+fn read_commands() {
+    let path = std::env::temp_dir().join(format!("quartz-source-{}", std::process::id()));
+    std::fs::write(
+        &path,
+        "// comment\n:set n 4v0 1; :set n 5v0 2\n\n  vt  \n:set op 4v0 lowpass(1, 2);;\n",
+    )
+    .unwrap();
+    let lines = functions::read_commands(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(lines, [":set n 4v0 1", ":set n 5v0 2", "vt", ":set op 4v0 lowpass(1, 2)"]);
+    assert!(functions::read_commands(std::path::Path::new("/nonexistent/quartzrc")).is_err());
+}
@@ -0,0 +1,132 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+use fundsp::hacker32::*;
+use nodes::*;
+
+// the output unit playing the given net at 100 samples per second
+fn output(transport: &Transport, net: Net) -> Clocked {
+    let input = InputRing::default();
+    let slot = Slot::new(Box::new(net)).1;
+    Clocked::new(slot, transport.clone(), AudioClock::default(), input, 100.)
+}
+
+#[test]
+fn swing() {
+    assert_eq!(swung(0.5, 0.), 0.5);
+    assert_eq!(swung(2.5, 0.), 2.5);
+    assert_eq!(swung(1.5, 0.5), 1.);
+    assert_eq!(swung(1.75, 0.5), 1.5);
+    assert_eq!(swung(3.5, 0.5), 3.);
+}
+
+#[test]
+fn clock() {
+    let transport = Transport::default();
+    // 120 bpm, 2 beats per second, so a beat every 50 samples
+    let net = Net::wrap(Box::new(An(Clock::new(transport.clone(), 1.))))
+        | Net::wrap(Box::new(An(Clock::new(transport.clone(), 2.))));
+    let mut out = BlockRateAdapter::new(Box::new(output(&transport, net)));
+    let mut beats = Vec::new();
+    let mut halves = Vec::new();
+    let mut frame = [0.; 4];
+    for i in 0..256 {
+        out.tick(&[], &mut frame);
+        if frame[0] != 0. {
+            beats.push(i);
+        }
+        if frame[2] != 0. {
+            halves.push(i);
+        }
+    }
+    assert_eq!(beats, [0, 50, 100, 150, 200, 250]);
+    assert_eq!(halves, (0..11).map(|i| i * 25).collect::<Vec<_>>());
+    assert!((transport.position() - 256. / 50.).abs() < 1e-4);
+    assert_eq!(transport.beat(), 1.);
+    assert_eq!(transport.bar(), 1.);
+
+    // stopped, nothing moves
+    transport.set_playing(false);
+    let pos = transport.position();
+    for _ in 0..200 {
+        out.tick(&[], &mut frame);
+        assert_eq!((frame[0], frame[2]), (0., 0.));
+    }
+    assert_eq!(transport.position(), pos);
+}
+
+#[test]
+fn timed_var() {
+    // 1000 samples per second, a millisecond each
+    let clock = AudioClock::default();
+    let queue = EventQueue::default();
+    let net = Net::wrap(Box::new(An(TimedVar::new(queue.clone(), clock.clone(), 0.))));
+    let transport = Transport::default();
+    let input = InputRing::default();
+    let mut out = Clocked::new(Slot::new(Box::new(net)).1, transport, clock.clone(), input, 1000.);
+    let mut frame = [0.];
+    clock.start_callback();
+    out.tick(&[], &mut frame);
+    clock.take_sample();
+    let start = clock.block_time();
+    queue.push(1., start + 10500);
+    queue.push(2., start + 20000);
+    let mut changes = Vec::new();
+    for i in 1..40 {
+        out.tick(&[], &mut frame);
+        clock.take_sample();
+        if changes.last().is_none_or(|(_, v)| *v != frame[0]) {
+            changes.push((i, frame[0]));
+        }
+    }
+    assert_eq!(changes, [(1, 0.), (11, 1.), (20, 2.)]);
+
+    // late events are applied right away
+    queue.push(3., start);
+    out.tick(&[], &mut frame);
+    assert_eq!(frame[0], 3.);
+}
+
+#[test]
+fn timed_var_without_device() {
+    // no callbacks, nothing to schedule against
+    let queue = EventQueue::default();
+    let mut var = TimedVar::new(queue.clone(), AudioClock::default(), 0.);
+    assert_eq!(var.tick(&Frame::default())[0], 0.);
+    queue.push(1., i64::MAX);
+    assert_eq!(var.tick(&Frame::default())[0], 1.);
+}
+
+// a trigger lands on its sample in the middle of a block, not at the start of one
+#[test]
+fn trig_in_block() {
+    let clock = AudioClock::default();
+    let queue = EventQueue::default();
+    let trig = An(TimedVar::trig(queue.clone(), clock.clone()));
+    // play the note at index 0 for a second, as soon as it's triggered
+    let seq = (trig | dc(0.) | dc(0.) | dc(1.)) >> An(Seq::new(vec![Net::wrap(Box::new(dc(1.)))]));
+    let slot = Slot::new(Box::new(Net::wrap(Box::new(seq)))).1;
+    let clocked =
+        Clocked::new(slot, Transport::default(), clock.clone(), InputRing::default(), 1000.);
+    // processed 64 samples at a time, like the device does
+    let mut out = BlockRateAdapter::new(Box::new(clocked));
+    out.set_sample_rate(1000.);
+    clock.start_callback();
+    for _ in 0..64 {
+        assert_eq!(out.get_mono(), 0.);
+        clock.take_sample();
+    }
+    // 10.5ms into the second block
+    queue.push(1., clock.block_time() + 64000 + 10500);
+    let second: Vec<f32> = (0..64)
+        .map(|_| {
+            let x = out.get_mono();
+            clock.take_sample();
+            x
+        })
+        .collect();
+    assert_eq!(second.iter().position(|x| *x != 0.), Some(11));
+    assert!(second[11..].iter().all(|x| *x == 1.));
+}
@@ -0,0 +1,36 @@
+#![allow(dead_code)]
+
+#[path = "../src/nodes.rs"]
+mod nodes;
+
+use nodes::*;
+use std::time::Duration;
+
+#[test]
+fn underruns() {
+    let status = AudioStatus::default();
+    let ms = Duration::from_millis;
+    // first callback, on time, late, too slow
+    status.callback(None, ms(10), ms(2));
+    status.callback(Some(ms(11)), ms(10), ms(2));
+    status.callback(Some(ms(25)), ms(10), ms(2));
+    status.callback(Some(ms(10)), ms(10), ms(12));
+    assert_eq!(status.counts(), [2, 0, 0, 0]);
+}
+
+#[test]
+fn errors() {
+    let status = AudioStatus::default();
+    status.overflow(64);
+    status.error("backend hiccup".into(), true, false);
+    assert!(!status.lost(true));
+    status.error("device gone".into(), false, true);
+    assert_eq!(status.counts(), [0, 64, 2, 1]);
+    assert!(status.lost(false));
+    assert!(!status.lost(true));
+    // only the latest error is shown
+    assert_eq!(status.take_error().as_deref(), Some("device gone"));
+    assert_eq!(status.take_error(), None);
+    status.found(false);
+    assert!(!status.lost(false));
+}